@@ -0,0 +1,130 @@
+//! Deserialization benchmarks
+//!
+//! Each input is parsed from a `&str`, a `&[u8]`, and an `io::Read`, and the
+//! equivalent JSON is parsed by `serde_json` as a point of comparison. The
+//! `reuse` group instead compares parsing many small inputs with fresh
+//! deserializers and with one reset between inputs. Run with `cargo bench`,
+//! optionally filtering by group, as in `cargo bench -- escapes`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A typical application state query string, as from the rison-node tests
+const QUERY: &str = "(_g:(refreshInterval:(pause:!t,value:0),time:(from:now-15m,to:now)))";
+
+/// A short object mixing idents, numbers, and quoted strings
+const SMALL: &str = "(a:0,b:foo,c:'23skidoo',d:!(!t,!f,!n),e:-1.5e3)";
+
+/// A large, deeply nested dashboard definition
+fn dashboard() -> String {
+    let panels: Vec<String> = (0..200)
+        .map(|i| {
+            format!(
+                "(embeddableConfig:(vis:(colors:(Count:'#{i:06x}'),legendOpen:!f)),\
+                 gridData:(h:15,i:'{i}',w:24,x:{x},y:{y}),id:panel-{i},\
+                 panelIndex:'{i}',title:'Panel {i}',type:visualization,version:'7.10.0')",
+                x = (i % 2) * 24,
+                y = (i / 2) * 15,
+            )
+        })
+        .collect();
+    format!(
+        "(description:'',filters:!(),fullScreenMode:!f,\
+         options:(hidePanelTitles:!f,useMargins:!t),panels:!({}),\
+         query:(language:kuery,query:''),timeRestore:!f,title:'Big dashboard',viewMode:view)",
+        panels.join(",")
+    )
+}
+
+/// A long list of unquoted strings
+fn idents() -> String {
+    let idents: Vec<String> = (0..2000).map(|i| format!("ident_{i}.a-b~c")).collect();
+    format!("!({})", idents.join(","))
+}
+
+/// A list of long quoted strings with occasional escapes
+fn prose() -> String {
+    let paragraph = "'Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod \
+                     tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, \
+                     quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo \
+                     consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse \
+                     cillum dolore eu fugiat nulla pariatur!!'";
+    format!("!({})", [paragraph; 200].join(","))
+}
+
+/// A long list of quoted strings dense with escapes
+fn escapes() -> String {
+    format!(
+        "!({})",
+        ["'it!'s !!important!! !'quoted!''"; 2000].join(",")
+    )
+}
+
+fn bench_input(c: &mut Criterion, group: &str, rison: &str) {
+    let json = serde_json::to_string(&rison::from_str::<Value>(rison).unwrap()).unwrap();
+
+    let mut group = c.benchmark_group(group);
+    group.throughput(Throughput::Bytes(rison.len() as u64));
+    group.bench_with_input(BenchmarkId::new("rison", "str"), rison, |b, input| {
+        b.iter(|| rison::from_str::<Value>(input).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("rison", "slice"), rison, |b, input| {
+        b.iter(|| rison::from_slice::<Value>(input.as_bytes()).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("rison", "reader"), rison, |b, input| {
+        b.iter(|| rison::from_reader::<Value, _>(input.as_bytes()).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("serde_json", "str"), &*json, |b, input| {
+        b.iter(|| serde_json::from_str::<Value>(input).unwrap())
+    });
+    group.finish();
+}
+
+/// Many small query parameters with escapes, each parsed by a fresh
+/// deserializer or by one reset for each
+fn reuse(c: &mut Criterion) {
+    let params: Vec<String> = (0..1000)
+        .map(|i| format!("(q:'it!'s #{i}',page:{i})"))
+        .collect();
+    let len = params.iter().map(String::len).sum::<usize>();
+
+    let mut group = c.benchmark_group("reuse");
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_function("fresh", |b| {
+        b.iter(|| {
+            for param in &params {
+                rison::from_str::<Value>(param).unwrap();
+            }
+        })
+    });
+    group.bench_function("reset", |b| {
+        let mut de = rison::Deserializer::from_str("");
+        b.iter(|| {
+            for param in &params {
+                de.reset(rison::de::read::StrRead::new(param));
+                Value::deserialize(&mut de).unwrap();
+                de.end().unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn small(c: &mut Criterion) {
+    bench_input(c, "query", QUERY);
+    bench_input(c, "small", SMALL);
+}
+
+fn large(c: &mut Criterion) {
+    bench_input(c, "dashboard", &dashboard());
+}
+
+fn strings(c: &mut Criterion) {
+    bench_input(c, "idents", &idents());
+    bench_input(c, "prose", &prose());
+    bench_input(c, "escapes", &escapes());
+}
+
+criterion_group!(benches, small, large, strings, reuse);
+criterion_main!(benches);
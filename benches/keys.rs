@@ -0,0 +1,138 @@
+//! Key interning benchmarks
+//!
+//! Compares deserializing many rows with the same keys into maps keyed by
+//! `String`, which allocates every key, and by `SharedKey`, which shares the
+//! keys held by a `KnownKeys` interner. Parsing time is dominated by the
+//! values, so the gain is in allocations: the `keys` group counts heap
+//! allocations per iteration rather than timing it. Run with
+//! `cargo bench --bench keys`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rison::de::{KnownKeys, SharedKey};
+use serde::Deserialize;
+
+/// The system allocator, counting each allocation it makes
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Measures the number of heap allocations made
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> usize {
+        ALLOCATIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, start: usize) -> usize {
+        ALLOCATIONS.load(Ordering::Relaxed) - start
+    }
+
+    fn add(&self, a: &usize, b: &usize) -> usize {
+        a + b
+    }
+
+    fn zero(&self) -> usize {
+        0
+    }
+
+    fn to_f64(&self, value: &usize) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let n = match *throughput {
+            Throughput::Bytes(n) | Throughput::BytesDecimal(n) | Throughput::Elements(n) => n,
+        };
+        for value in values {
+            *value /= n as f64;
+        }
+        "allocs/elem"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+const KEYS: [&str; 4] = ["timestamp", "hostname", "status_code", "duration_ms"];
+
+/// A list of rows, each an object with the same four keys
+fn rows(n: usize) -> String {
+    let rows: Vec<String> = (0..n)
+        .map(|i| {
+            format!(
+                "(timestamp:{i},hostname:{},status_code:200,duration_ms:{i})",
+                i % 7
+            )
+        })
+        .collect();
+    format!("!({})", rows.join(","))
+}
+
+fn keys(c: &mut Criterion<Allocations>) {
+    let input = rows(1000);
+    let builder = rison::Deserializer::builder().key_interner(KnownKeys::new(KEYS));
+    let mut group = c.benchmark_group("keys");
+    group.throughput(Throughput::Elements(1000));
+    group.bench_function("String", |b| {
+        b.iter(|| Vec::<HashMap<String, u64>>::deserialize(&mut builder.from_str(&input)).unwrap())
+    });
+    group.bench_function("SharedKey", |b| {
+        b.iter(|| {
+            Vec::<HashMap<SharedKey, u64>>::deserialize(&mut builder.from_str(&input)).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = keys
+}
+criterion_main!(benches);
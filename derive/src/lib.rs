@@ -0,0 +1,54 @@
+//! Derive macros for the `rison` crate, re-exported by it with the `derive`
+//! feature
+//!
+//! See [`RisonDisplay`].
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, DeriveInput, WhereClause, WherePredicate};
+
+/// Implement `Display` and `FromStr` for a type by way of its Rison
+/// encoding
+///
+/// The type must implement `Serialize` and `DeserializeOwned` too.
+/// Formatting writes the value as compact Rison, or fails with
+/// `fmt::Error` where serializing fails, and parsing reads it with
+/// `rison::from_str`, failing with a `rison::Error`.
+#[proc_macro_derive(RisonDisplay)]
+pub fn derive_rison_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let display_where = with_predicate(
+        where_clause,
+        parse_quote!(Self: ::rison::__private::serde::Serialize),
+    );
+    let from_str_where = with_predicate(
+        where_clause,
+        parse_quote!(Self: ::rison::__private::serde::de::DeserializeOwned),
+    );
+
+    quote! {
+        impl #impl_generics ::core::fmt::Display for #name #ty_generics #display_where {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::rison::ser::to_fmt_writer(f, self).map_err(|_| ::core::fmt::Error)
+            }
+        }
+
+        impl #impl_generics ::core::str::FromStr for #name #ty_generics #from_str_where {
+            type Err = ::rison::Error;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                ::rison::from_str(s)
+            }
+        }
+    }
+    .into()
+}
+
+/// The where clause of the type, if any, with `predicate` added
+fn with_predicate(where_clause: Option<&WhereClause>, predicate: WherePredicate) -> WhereClause {
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+    where_clause.predicates.push(predicate);
+    where_clause
+}
@@ -0,0 +1,99 @@
+//! Read and rewrite the state which Kibana keeps in the query string of its
+//! Discover URLs
+//!
+//! ```sh
+//! cargo run --example kibana -- 'https://kibana.example/app/discover#/?_g=...&_a=...'
+//! ```
+//!
+//! Without an argument, an example URL is used.
+
+use serde::{Deserialize, Serialize};
+
+const EXAMPLE_URL: &str = "https://kibana.example/app/discover#/?_g=(filters:!(),refreshInterval:(pause:!t,value:0),time:(from:now-15m,to:now))&_a=(columns:!(host,message),interval:auto,query:(language:kuery,query:'response:500'),sort:!(!('@timestamp',desc)))";
+
+/// State shared between Kibana apps, in the `_g` parameter
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalState {
+    #[serde(default)]
+    filters: Vec<serde_json::Value>,
+    refresh_interval: RefreshInterval,
+    time: TimeRange,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RefreshInterval {
+    pause: bool,
+    value: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TimeRange {
+    from: String,
+    to: String,
+}
+
+/// State of the Discover app, in the `_a` parameter
+#[derive(Debug, Serialize, Deserialize)]
+struct AppState {
+    #[serde(default)]
+    columns: Vec<String>,
+    query: Query,
+    #[serde(default)]
+    sort: Vec<(String, String)>,
+    /// Fields this example does not model are kept as they were
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Query {
+    language: String,
+    query: String,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| EXAMPLE_URL.into());
+    // Kibana keeps its state in the fragment, which is otherwise laid out as
+    // a query string
+    let (base, query) = url.split_once('?').ok_or("URL has no query string")?;
+
+    let mut global: GlobalState = rison::from_query(query, "_g")?.ok_or("URL has no `_g`")?;
+    let mut app: AppState = rison::from_query(query, "_a")?.ok_or("URL has no `_a`")?;
+
+    println!("time:    {} to {}", global.time.from, global.time.to);
+    println!("query:   {} ({})", app.query.query, app.query.language);
+    println!("columns: {}", app.columns.join(", "));
+    for (field, direction) in &app.sort {
+        println!("sort:    {} {}", field, direction);
+    }
+
+    // Widen the time range, and only show errors
+    global.time.from = "now-24h".into();
+    app.query.query = format!("({}) and level:error", app.query.query);
+
+    println!(
+        "\n{}?_g={}&_a={}",
+        base,
+        encode(&rison::to_string(&global)?),
+        encode(&rison::to_string(&app)?),
+    );
+    Ok(())
+}
+
+/// Percent-encode the characters which may not appear in a query string
+/// as they are, leaving Rison's own punctuation readable
+fn encode(rison: &str) -> String {
+    rison
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => (b as char).to_string(),
+            b'-' | b'_' | b'.' | b'~' | b'!' | b'\'' | b'(' | b')' | b',' | b':' | b'@' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+//! Deserialize strings without allocating a `String` for each
+//!
+//! ```sh
+//! cargo run --example small_strings
+//! ```
+//!
+//! The deserializer provides strings to visitors as `&str`, so types which
+//! store short strings inline, such as `CompactString`, need no heap
+//! allocation for them, and a `DeserializeSeed` can copy them wherever it
+//! likes, here into a single arena.
+
+use std::fmt;
+use std::ops::Range;
+
+use compact_str::CompactString;
+use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::Deserialize;
+
+/// Search parameters, each of whose short strings is stored inline
+#[derive(Debug, Deserialize)]
+struct Search {
+    q: CompactString,
+    tags: Vec<CompactString>,
+}
+
+/// Strings stored end to end in one buffer, referred to by their ranges
+#[derive(Default)]
+struct Arena {
+    buffer: String,
+}
+
+impl Arena {
+    fn push(&mut self, s: &str) -> Range<usize> {
+        let start = self.buffer.len();
+        self.buffer.push_str(s);
+        start..self.buffer.len()
+    }
+
+    fn get(&self, range: Range<usize>) -> &str {
+        &self.buffer[range]
+    }
+}
+
+/// Deserializes a list of strings into an arena, producing their ranges
+struct ArenaStrings<'a>(&'a mut Arena);
+
+impl<'de> DeserializeSeed<'de> for ArenaStrings<'_> {
+    type Value = Vec<Range<usize>>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ArenaStrings<'_> {
+    type Value = Vec<Range<usize>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of strings")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut ranges = Vec::new();
+        while let Some(range) = seq.next_element_seed(ArenaString(&mut *self.0))? {
+            ranges.push(range);
+        }
+        Ok(ranges)
+    }
+}
+
+/// Deserializes a single string into an arena, producing its range
+struct ArenaString<'a>(&'a mut Arena);
+
+impl<'de> DeserializeSeed<'de> for ArenaString<'_> {
+    type Value = Range<usize>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ArenaString<'_> {
+    type Value = Range<usize>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(self.0.push(s))
+    }
+}
+
+fn main() -> rison::Result<()> {
+    let search: Search = rison::from_str("(q:'error rate',tags:!(prod,eu-west,'can!'t'))")?;
+    println!("{}: {:?}", search.q, search.tags);
+    assert!(search.tags.iter().all(|tag| !tag.is_heap_allocated()));
+
+    let mut arena = Arena::default();
+    let mut de = rison::Deserializer::from_str("!(host,message,'@timestamp')");
+    let ranges = ArenaStrings(&mut arena).deserialize(&mut de)?;
+    de.end()?;
+    for range in ranges {
+        println!("{}", arena.get(range));
+    }
+
+    Ok(())
+}
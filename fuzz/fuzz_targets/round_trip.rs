@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Any value which parses must serialize, and its serialization must be
+// stable across a further parse. Exact equality with the parsed value is not
+// asserted, as numbers which overflow to infinity serialize as `!n`.
+fuzz_target!(|data: &[u8]| {
+    let Ok(value) = rison::from_slice::<rison::Value>(data) else {
+        return;
+    };
+    let encoded = rison::to_string(&value).expect("parsed values serialize");
+    let reparsed: rison::Value = rison::from_str(&encoded).expect("serialized values parse");
+    let reencoded = rison::to_string(&reparsed).expect("parsed values serialize");
+
+    assert_eq!(encoded, reencoded);
+});
@@ -0,0 +1,419 @@
+//! Extractors for parsing Rison from request query strings in `axum`
+//!
+//! [`Rison`] parses the entire query string as a single Rison value, as in
+//! `/search?(q:rust,page:2)`. [`RisonParam`] parses the value of a single
+//! named query parameter, as in `/search?state=(q:rust,page:2)&tab=open`.
+//!
+//! In both cases the query text is percent-decoded, with `+` decoding to a
+//! space, as it is parsed. Parameters are found as by
+//! [`from_query`](crate::from_query). Failures are rejected with a `400 Bad Request`
+//! response whose JSON body describes the problem.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use axum_core::extract::FromRequestParts;
+use axum_core::response::{IntoResponse, Response};
+use http::request::Parts;
+use http::{header, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+
+/// Extracts a `T` from the entire query string of a request
+///
+/// ```
+/// use rison::axum::Rison;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Search {
+///     q: String,
+///     page: u32,
+/// }
+///
+/// async fn search(Rison(search): Rison<Search>) -> String {
+///     format!("{} (page {})", search.q, search.page)
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rison<T>(pub T);
+
+impl<T> Rison<T> {
+    /// Unwrap the extracted value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Rison<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Rison<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<S, T> FromRequestParts<S> for Rison<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = RisonRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().ok_or(RisonRejection::MissingQuery)?;
+        crate::query::parse_param(query)
+            .map(Rison)
+            .map_err(RisonRejection::InvalidRison)
+    }
+}
+
+/// The name of a query parameter extracted by [`RisonParam`]
+///
+/// ```
+/// struct State;
+///
+/// impl rison::axum::QueryParam for State {
+///     const NAME: &'static str = "state";
+/// }
+/// ```
+pub trait QueryParam {
+    /// The name of the parameter, as it appears in the query string
+    const NAME: &'static str;
+}
+
+/// Extracts a `T` from the query parameter named by `P`
+///
+/// The parameter is found and decoded as by [`from_query`](crate::from_query),
+/// so if it appears more than once, the first occurrence is used.
+///
+/// ```
+/// use rison::axum::{QueryParam, RisonParam};
+///
+/// struct State;
+///
+/// impl QueryParam for State {
+///     const NAME: &'static str = "state";
+/// }
+///
+/// #[derive(serde::Deserialize)]
+/// struct Search {
+///     q: String,
+///     page: u32,
+/// }
+///
+/// async fn search(state: RisonParam<State, Search>) -> String {
+///     format!("{} (page {})", state.q, state.page)
+/// }
+/// ```
+pub struct RisonParam<P, T> {
+    value: T,
+    param: PhantomData<fn() -> P>,
+}
+
+impl<P, T> RisonParam<P, T> {
+    /// Unwrap the extracted value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<P, T> Deref for RisonParam<P, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<P, T> DerefMut for RisonParam<P, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<P, T> fmt::Debug for RisonParam<P, T>
+where
+    P: QueryParam,
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RisonParam")
+            .field("name", &P::NAME)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<S, P, T> FromRequestParts<S> for RisonParam<P, T>
+where
+    P: QueryParam,
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = RisonRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let value = crate::query::find_param(query, P::NAME)
+            .ok_or(RisonRejection::MissingParam(P::NAME))?;
+        crate::query::parse_param(value)
+            .map(|value| RisonParam {
+                value,
+                param: PhantomData,
+            })
+            .map_err(RisonRejection::InvalidRison)
+    }
+}
+
+/// The reason a request was rejected by [`Rison`] or [`RisonParam`]
+///
+/// All variants respond with `400 Bad Request` and a JSON body, as produced
+/// by [`body_json`](RisonRejection::body_json), describing the problem.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RisonRejection {
+    /// The request has no query string
+    MissingQuery,
+    /// The query string has no parameter with the given name
+    MissingParam(&'static str),
+    /// The decoded query text could not be parsed as the requested type
+    InvalidRison(Error),
+}
+
+impl RisonRejection {
+    /// The status code of the rejection response
+    pub fn status(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    /// A plain-text description of the problem
+    pub fn body_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// The JSON body of the rejection response
+    ///
+    /// This is an object whose `error` is the [`body_text`](Self::body_text).
+    /// Where Rison failed to parse, it also has the `position`, `line` and
+    /// `column` in the decoded text at which the error was detected, where
+    /// those are known, as an editor or form would need to highlight it.
+    ///
+    /// ```
+    /// # use rison::axum::RisonRejection;
+    /// let err = rison::from_str::<u32>("(a:1").unwrap_err();
+    /// let rejection = RisonRejection::InvalidRison(err);
+    ///
+    /// assert!(rejection.body_json().starts_with(r#"{"error":"Failed to deserialize"#));
+    /// assert!(rejection.body_json().ends_with(r#","position":0,"line":1,"column":1}"#));
+    /// ```
+    pub fn body_json(&self) -> String {
+        use std::io::Write;
+
+        let mut body = b"{\"error\":".to_vec();
+        crate::transcode::write_json_string(&mut body, &self.body_text())
+            .expect("writing to a Vec cannot fail");
+        if let RisonRejection::InvalidRison(err) = self {
+            let fields = [
+                ("position", err.position()),
+                ("line", err.line()),
+                ("column", err.column()),
+            ];
+            for (name, value) in fields {
+                if let Some(value) = value {
+                    write!(body, ",\"{}\":{}", name, value).expect("writing to a Vec cannot fail");
+                }
+            }
+        }
+        body.push(b'}');
+        String::from_utf8(body).expect("JSON is written as valid UTF-8")
+    }
+}
+
+impl fmt::Display for RisonRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RisonRejection::MissingQuery => f.write_str("Missing query string"),
+            RisonRejection::MissingParam(name) => {
+                write!(f, "Missing query parameter `{}`", name)
+            }
+            RisonRejection::InvalidRison(err) => {
+                write!(f, "Failed to deserialize query string as Rison: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RisonRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RisonRejection::InvalidRison(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for RisonRejection {
+    fn into_response(self) -> Response {
+        let content_type = [(header::CONTENT_TYPE, "application/json")];
+        (self.status(), content_type, self.body_json()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use axum_core::extract::FromRequestParts;
+    use http::{Request, StatusCode};
+
+    use super::{QueryParam, Rison, RisonParam, RisonRejection};
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Search {
+        q: String,
+        page: u32,
+    }
+
+    struct State;
+
+    impl QueryParam for State {
+        const NAME: &'static str = "state";
+    }
+
+    /// Run an extractor to completion; none of them ever wait
+    fn extract<E>(uri: &str) -> Result<E, E::Rejection>
+    where
+        E: FromRequestParts<()>,
+    {
+        let (mut parts, ()) = Request::get(uri).body(()).unwrap().into_parts();
+        let mut future = pin!(E::from_request_parts(&mut parts, &()));
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("extractor did not complete"),
+        }
+    }
+
+    #[test]
+    fn extract_whole_query() {
+        let Rison(search) = extract::<Rison<Search>>("/search?(q:'rust+serde',page:2)").unwrap();
+
+        assert_eq!(
+            search,
+            Search {
+                q: "rust serde".into(),
+                page: 2
+            }
+        );
+    }
+    #[test]
+    fn extract_percent_encoded_query() {
+        let Rison(search) =
+            extract::<Rison<Search>>("/search?%28q%3A%27it%21%27s%27%2Cpage%3A1%29").unwrap();
+
+        assert_eq!(search.q, "it's");
+    }
+    #[test]
+    fn extract_named_param() {
+        let search =
+            extract::<RisonParam<State, Search>>("/search?tab=open&state=(q:rust,page:3)").unwrap();
+
+        assert_eq!(
+            search.into_inner(),
+            Search {
+                q: "rust".into(),
+                page: 3
+            }
+        );
+    }
+    #[test]
+    fn reject_missing_query() {
+        let err = extract::<Rison<Search>>("/search").unwrap_err();
+
+        assert!(matches!(err, RisonRejection::MissingQuery));
+    }
+    #[test]
+    fn reject_missing_param() {
+        let err = extract::<RisonParam<State, Search>>("/search?tab=open").unwrap_err();
+
+        assert!(matches!(err, RisonRejection::MissingParam("state")));
+        assert_eq!(err.body_text(), "Missing query parameter `state`");
+    }
+    #[test]
+    fn reject_invalid_rison() {
+        let err = extract::<Rison<Search>>("/search?(q:rust,page:").unwrap_err();
+
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+        assert!(matches!(err, RisonRejection::InvalidRison(_)));
+        assert!(err
+            .body_text()
+            .starts_with("Failed to deserialize query string as Rison: "));
+
+        let body: serde_json::Value = serde_json::from_str(&err.body_json()).unwrap();
+        assert_eq!(body["error"], err.body_text());
+        assert_eq!(
+            (&body["position"], &body["line"], &body["column"]),
+            (&13.into(), &1.into(), &14.into())
+        );
+        let response = axum_core::response::IntoResponse::into_response(err);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+    }
+    #[test]
+    fn extract_param_after_undecodable_names() {
+        let search =
+            extract::<RisonParam<State, Search>>("/search?%FF=1&state=(q:a,page:1)").unwrap();
+
+        assert_eq!(search.page, 1);
+    }
+    #[test]
+    fn extract_param_as_from_query() {
+        for uri in [
+            "/search?state=(q:'it!'s+ok',page:%32)&state=(q:b,page:3)",
+            "/search?tab=%FF&st%61te=(q:'it!'s%20ok',page:2)",
+            "/search?state%zz=(q:x,page:1)&state=(q:'it!'s+ok',page:2)",
+        ] {
+            let query = uri.split_once('?').unwrap().1;
+            let expected: Search = crate::from_query(query, "state").unwrap().unwrap();
+            let search = extract::<RisonParam<State, Search>>(uri).unwrap();
+            assert_eq!(search.into_inner(), expected, "{uri}");
+            assert_eq!(expected.q, "it's ok");
+        }
+
+        let err = extract::<RisonParam<State, Search>>("/search?state=(q:%FF,page:1)").unwrap_err();
+        let RisonRejection::InvalidRison(err) = err else {
+            panic!("{err:?}");
+        };
+        assert!(matches!(
+            err.code(),
+            crate::error::ErrorCode::InvalidUnicode
+        ));
+    }
+    #[test]
+    fn reject_invalid_encoding() {
+        let err = extract::<Rison<Search>>("/search?(q:%FF,page:1)").unwrap_err();
+        let RisonRejection::InvalidRison(err) = err else {
+            panic!("{err:?}");
+        };
+
+        assert!(matches!(
+            err.code(),
+            crate::error::ErrorCode::InvalidUnicode
+        ));
+    }
+}
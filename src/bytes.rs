@@ -0,0 +1,25 @@
+//! The convention by which byte strings are represented in Rison
+//!
+//! Rison has no native byte string type, so bytes are written as strings
+//! containing their unpadded URL-safe base64 encoding (RFC 4648 §5). The
+//! alphabet consists only of ident characters, so encoded bytes are quoted
+//! only when they begin with a digit or `-`. Padding is accepted, but not
+//! required, when decoding.
+
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::{alphabet, Engine};
+
+const ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    ENGINE.encode(bytes)
+}
+
+pub(crate) fn decode(encoded: &str) -> Option<Vec<u8>> {
+    ENGINE.decode(encoded).ok()
+}
@@ -0,0 +1,18 @@
+//! Helpers for serializing dates and times in the styles common in Rison
+//!
+//! Applications such as Kibana embed timestamps in Rison either as quoted
+//! ISO 8601 strings with millisecond precision, as in
+//! `time:(from:'2023-01-01T00:00:00.000Z')`, or as integer milliseconds since
+//! the Unix epoch. Each supported date and time library has a module for
+//! each style, for use with `#[serde(with = "...")]`:
+//!
+//! - [`chrono`], with the `chrono` feature, for `chrono::DateTime<Utc>`
+//! - [`time`], with the `time` feature, for `time::OffsetDateTime`
+//!
+//! Both styles parse back to the same instant, but the ISO 8601 style
+//! truncates to whole milliseconds, and is always written in UTC.
+
+#[cfg(feature = "chrono")]
+pub mod chrono;
+#[cfg(feature = "time")]
+pub mod time;
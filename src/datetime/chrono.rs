@@ -0,0 +1,127 @@
+//! Serialization of `chrono::DateTime<Utc>`
+//!
+//! ```
+//! use chrono::{DateTime, Utc};
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Range {
+//!     #[serde(with = "rison::datetime::chrono::iso8601")]
+//!     from: DateTime<Utc>,
+//!     #[serde(with = "rison::datetime::chrono::epoch_millis")]
+//!     to: DateTime<Utc>,
+//! }
+//!
+//! let range: Range = rison::from_str("(from:'2023-01-01T00:00:00.000Z',to:1672531260000)").unwrap();
+//!
+//! assert_eq!((range.to - range.from).num_minutes(), 1);
+//! ```
+
+/// As a quoted ISO 8601 string in UTC with millisecond precision, such as
+/// `'2023-01-01T00:00:00.000Z'`
+///
+/// Any RFC 3339 date and time, with any precision and offset, is accepted
+/// when deserializing.
+pub mod iso8601 {
+    use std::fmt;
+
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::de::{Deserializer, Visitor};
+    use serde::ser::Serializer;
+
+    /// Serialize a date and time as an ISO 8601 string
+    pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_rfc3339_opts(SecondsFormat::Millis, true))
+    }
+
+    /// Deserialize a date and time from an RFC 3339 string
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(Iso8601Visitor)
+    }
+
+    struct Iso8601Visitor;
+
+    impl Visitor<'_> for Iso8601Visitor {
+        type Value = DateTime<Utc>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an ISO 8601 date and time")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<DateTime<Utc>, E>
+        where
+            E: serde::de::Error,
+        {
+            DateTime::parse_from_rfc3339(v)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(E::custom)
+        }
+    }
+}
+
+/// As an integer number of milliseconds since the Unix epoch, such as
+/// `1672531200000`
+pub mod epoch_millis {
+    use chrono::{DateTime, Utc};
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    /// Serialize a date and time as milliseconds since the Unix epoch,
+    /// truncating any sub-millisecond precision
+    pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(value.timestamp_millis())
+    }
+
+    /// Deserialize a date and time from milliseconds since the Unix epoch
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = i64::deserialize(deserializer)?;
+        DateTime::from_timestamp_millis(millis)
+            .ok_or_else(|| serde::de::Error::custom("timestamp out of range"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Range {
+        #[serde(with = "super::iso8601")]
+        from: DateTime<Utc>,
+        #[serde(with = "super::epoch_millis")]
+        to: DateTime<Utc>,
+    }
+
+    #[test]
+    fn serialize_date_times() {
+        let range = Range {
+            from: Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap(),
+            to: Utc.with_ymd_and_hms(2023, 1, 1, 0, 1, 0).unwrap(),
+        };
+        let rison = crate::to_string(&range).unwrap();
+
+        assert_eq!(rison, "(from:'2023-01-01T00:00:00.000Z',to:1672531260000)");
+        assert_eq!(crate::from_str::<Range>(&rison).unwrap(), range);
+    }
+    #[test]
+    fn deserialize_date_times() {
+        let range: Range =
+            crate::from_str("(from:'2023-01-01T01:00:00.123456+01:00',to:-1)").unwrap();
+
+        assert_eq!(range.from.to_rfc3339(), "2023-01-01T00:00:00.123456+00:00");
+        assert_eq!(range.to.timestamp_millis(), -1);
+        assert!(crate::from_str::<Range>("(from:'2023-01-01',to:0)").is_err());
+        assert!(crate::from_str::<Range>("(from:'2023-01-01T00:00:00Z',to:now)").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Serialization of `time::OffsetDateTime`
+//!
+//! ```
+//! use time::OffsetDateTime;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Range {
+//!     #[serde(with = "rison::datetime::time::iso8601")]
+//!     from: OffsetDateTime,
+//!     #[serde(with = "rison::datetime::time::epoch_millis")]
+//!     to: OffsetDateTime,
+//! }
+//!
+//! let range: Range = rison::from_str("(from:'2023-01-01T00:00:00.000Z',to:1672531260000)").unwrap();
+//!
+//! assert_eq!((range.to - range.from).whole_minutes(), 1);
+//! ```
+
+/// As a quoted ISO 8601 string in UTC with millisecond precision, such as
+/// `'2023-01-01T00:00:00.000Z'`
+///
+/// Any RFC 3339 date and time, with any precision and offset, is accepted
+/// when deserializing.
+pub mod iso8601 {
+    use std::fmt;
+
+    use serde::de::{Deserializer, Visitor};
+    use serde::ser::Serializer;
+    use time::format_description::well_known::Rfc3339;
+    use time::{OffsetDateTime, UtcOffset};
+
+    /// Serialize a date and time as an ISO 8601 string
+    pub fn serialize<S>(value: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let utc = value.to_offset(UtcOffset::UTC);
+        serializer.collect_str(&format_args!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            utc.year(),
+            u8::from(utc.month()),
+            utc.day(),
+            utc.hour(),
+            utc.minute(),
+            utc.second(),
+            utc.millisecond(),
+        ))
+    }
+
+    /// Deserialize a date and time from an RFC 3339 string
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(Iso8601Visitor)
+    }
+
+    struct Iso8601Visitor;
+
+    impl Visitor<'_> for Iso8601Visitor {
+        type Value = OffsetDateTime;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an ISO 8601 date and time")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<OffsetDateTime, E>
+        where
+            E: serde::de::Error,
+        {
+            OffsetDateTime::parse(v, &Rfc3339).map_err(E::custom)
+        }
+    }
+}
+
+/// As an integer number of milliseconds since the Unix epoch, such as
+/// `1672531200000`
+pub mod epoch_millis {
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::Serializer;
+    use time::OffsetDateTime;
+
+    const NANOS_PER_MILLI: i128 = 1_000_000;
+
+    /// Serialize a date and time as milliseconds since the Unix epoch,
+    /// truncating any sub-millisecond precision
+    pub fn serialize<S>(value: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let millis = value.unix_timestamp_nanos().div_euclid(NANOS_PER_MILLI);
+        // Every representable date and time is within range of an `i64`
+        serializer.serialize_i64(millis as i64)
+    }
+
+    /// Deserialize a date and time from milliseconds since the Unix epoch
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = i64::deserialize(deserializer)?;
+        OffsetDateTime::from_unix_timestamp_nanos(i128::from(millis) * NANOS_PER_MILLI)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use time::{Date, Month, OffsetDateTime, Time, UtcOffset};
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Range {
+        #[serde(with = "super::iso8601")]
+        from: OffsetDateTime,
+        #[serde(with = "super::epoch_millis")]
+        to: OffsetDateTime,
+    }
+
+    fn date_time(hour: u8, minute: u8) -> OffsetDateTime {
+        let date = Date::from_calendar_date(2023, Month::January, 1).unwrap();
+        let time = Time::from_hms(hour, minute, 0).unwrap();
+        date.with_time(time).assume_utc()
+    }
+
+    #[test]
+    fn serialize_date_times() {
+        let range = Range {
+            from: date_time(0, 0),
+            to: date_time(0, 1),
+        };
+        let rison = crate::to_string(&range).unwrap();
+
+        assert_eq!(rison, "(from:'2023-01-01T00:00:00.000Z',to:1672531260000)");
+        assert_eq!(crate::from_str::<Range>(&rison).unwrap(), range);
+    }
+    #[test]
+    fn deserialize_date_times() {
+        let range: Range =
+            crate::from_str("(from:'2023-01-01T01:00:00.123456+01:00',to:-1)").unwrap();
+
+        assert_eq!(
+            range.from.to_offset(UtcOffset::UTC),
+            date_time(0, 0).replace_microsecond(123456).unwrap()
+        );
+        assert_eq!(range.to.unix_timestamp_nanos(), -1_000_000);
+        assert!(crate::from_str::<Range>("(from:'2023-01-01',to:0)").is_err());
+        assert!(crate::from_str::<Range>("(from:'2023-01-01T00:00:00Z',to:now)").is_err());
+    }
+}
@@ -1,20 +1,174 @@
 //! Deserialize Rison data to Rust data structures
+//!
+//! # Strings
+//!
+//! Strings are provided to visitors as `&str`, never as an owned `String`:
+//! through `visit_borrowed_str` when they are borrowed from the input, or
+//! through `visit_str` when they were unescaped or read from a stream into
+//! the deserializer's scratch buffer. Types which store short strings
+//! inline, such as `compact_str::CompactString` or `smol_str::SmolStr`,
+//! therefore deserialize without allocating, and a
+//! [`DeserializeSeed`](serde::de::DeserializeSeed) may copy strings into an
+//! arena or interner of its own. See `examples/small_strings.rs`.
+
+#[cfg(feature = "tokio")]
+mod async_read;
+mod builder;
+mod events;
+mod intern;
+mod observe;
+mod partial;
+pub mod read;
+mod spanned;
+
+#[cfg(feature = "path")]
+use crate::error::Segment;
+use crate::error::{Error, ErrorCode, Result};
+use builder::Options;
+use read::Read;
 
-mod read;
+pub use builder::{DeserializerBuilder, ParseLimits, DEFAULT_RECURSION_LIMIT};
+pub use events::{Event, Parser};
+pub use intern::{KeyInterner, KnownKeys, SharedKey};
+pub use observe::{ParseEvent, ParseObserver, ValueKind};
+pub use partial::PartialDeserializer;
+pub use spanned::Spanned;
 
-use crate::error::{Code, Error, Result};
-use read::Read;
+use read::InfallibleIter;
+pub(crate) use read::{Reference, UrlDecode, NOT_ID_CHARS};
 
 /// A deserializer for Rison into Rust values
 pub struct Deserializer<R> {
     read: R,
     scratch: Vec<u8>,
+    remaining_depth: usize,
+    /// The number of lists and objects the value being parsed is nested in,
+    /// as reported to a [`ParseObserver`]
+    depth: usize,
+    /// Characters which terminate an unquoted string under the configured
+    /// options
+    not_id_chars: Box<read::ByteSet>,
+    options: Options,
+    /// A character consumed by an optional value to look at the character
+    /// following it, such as the `!` of a marker which turned out not to be
+    /// `!n`, which is yet to be parsed again
+    pending: Option<u8>,
+    /// The text of the most recently parsed object key
+    #[cfg(feature = "path")]
+    last_key: String,
+}
+
+/// A deserializer reading from any input source, chosen at runtime
+///
+/// Every [`Deserializer`] may be converted into one with
+/// [`into_dyn`](Deserializer::into_dyn), so that deserializers reading from
+/// strings, slices and streams share a single type. This suits plugin
+/// systems, which may store them, or pass them on as a
+/// `&mut dyn erased_serde::Deserializer`, without being generic over the
+/// source. Each byte is read through dynamic dispatch, at some cost in
+/// speed.
+///
+/// ```
+/// use rison::de::DynDeserializer;
+/// use serde::Deserialize;
+///
+/// let stream: &[u8] = b"!(3,4)";
+/// let mut sources: Vec<DynDeserializer> = vec![
+///     rison::Deserializer::from_str("!(1,2)").into_dyn(),
+///     rison::Deserializer::from_reader(stream).into_dyn(),
+/// ];
+/// for de in &mut sources {
+///     let pair = <(u8, u8)>::deserialize(&mut *de).unwrap();
+///     de.end().unwrap();
+///     # let _ = pair;
+/// }
+/// ```
+pub type DynDeserializer<'de> = Deserializer<Box<dyn Read<'de> + 'de>>;
+
+/// Insignificant whitespace, skipped between tokens when permitted by
+/// [`DeserializerBuilder::allow_whitespace`]
+const WHITESPACE: &[u8] = b" \t\n\r";
+
+/// Delimiters of URL query strings and fragments, which end numbers and
+/// unquoted strings parsed by [`from_str_partial`] and [`from_slice_partial`]
+const PARTIAL_DELIMITERS: &[u8] = b"&=#";
+
+macro_rules! check_recursion {
+    ($this:ident $($body:tt)*) => {
+        if !$this.recursion_limit_disabled() {
+            if $this.remaining_depth == 0 {
+                return Err(Error::new(ErrorCode::RecursionLimitExceeded, $this.read.position().into()));
+            }
+            $this.remaining_depth -= 1;
+        }
+        $this.depth += 1;
+
+        $this $($body)*
+
+        $this.depth -= 1;
+        if !$this.recursion_limit_disabled() {
+            $this.remaining_depth += 1;
+        }
+    };
+}
+
+impl Deserializer<()> {
+    /// Create a builder for configuring a Rison deserializer
+    ///
+    /// ```
+    /// let mut de = rison::Deserializer::builder()
+    ///     .recursion_limit(16)
+    ///     .from_str("(a:!(1,2,3))");
+    /// # let _ = &mut de;
+    /// ```
+    pub fn builder() -> DeserializerBuilder {
+        DeserializerBuilder::new()
+    }
 }
 
 impl<R: std::io::Read> Deserializer<read::IoRead<R>> {
     /// Create a Rison deserializer from an `io::Read`
     pub fn from_reader(reader: R) -> Self {
-        Self::new(read::IoRead::new(reader))
+        Self::new(read::IoRead::new(read::bytes(reader)))
+    }
+}
+impl<R: std::io::BufRead> Deserializer<read::BufIoRead<R>> {
+    /// Create a Rison deserializer from an `io::BufRead`, reading a buffered
+    /// chunk at a time
+    ///
+    /// This is faster than [`from_reader`](Deserializer::from_reader) for
+    /// readers which are already buffered. See [`read::BufIoRead`].
+    pub fn from_bufread(reader: R) -> Self {
+        Self::new(read::BufIoRead::new(reader))
+    }
+}
+impl<I: Iterator<Item = u8>> Deserializer<read::IterRead<InfallibleIter<I>>> {
+    /// Create a Rison deserializer from an iterator of bytes
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        Self::new(read::IterRead::new(read::infallible(iter.into_iter())))
+    }
+}
+impl<I: Iterator<Item = std::io::Result<u8>>> Deserializer<read::IterRead<I>> {
+    /// Create a Rison deserializer from an iterator of bytes, each of which
+    /// may instead be an IO error, as when decompressing a stream
+    pub fn from_fallible_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        Self::new(read::IterRead::new(iter.into_iter()))
+    }
+}
+impl<'a> Deserializer<read::UrlEncodedRead<'a>> {
+    /// Create a Rison deserializer from a component of a URL query string,
+    /// decoding `+` as a space and `%xx` escapes as it is read
+    ///
+    /// See [`crate::query`] for extracting a component by parameter name.
+    pub fn from_url_encoded(component: &'a str) -> Self {
+        Self::new(read::IterRead::new(read::UrlDecode::new(component)))
     }
 }
 impl<'a> Deserializer<read::SliceRead<'a>> {
@@ -23,123 +177,670 @@ impl<'a> Deserializer<read::SliceRead<'a>> {
         Self::new(read::SliceRead::new(slice))
     }
 }
+impl<'a> Deserializer<read::MutSliceRead<'a>> {
+    /// Create a Rison deserializer from a `&mut [u8]`, unescaping quoted
+    /// strings in place
+    ///
+    /// See [`from_bytes_mut`].
+    pub fn from_mut_slice(slice: &'a mut [u8]) -> Self {
+        Self::new(read::MutSliceRead::new(slice))
+    }
+}
 impl<'a> Deserializer<read::StrRead<'a>> {
     /// Create a Rison deserializer from a `&str`
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &'a str) -> Self {
         Self::new(read::StrRead::new(s))
     }
 }
 
 impl<'de, R: Read<'de>> Deserializer<R> {
-    fn new(read: R) -> Self {
+    /// Create a Rison deserializer from any [`read::Read`]
+    ///
+    /// Typically created with [`from_str`](Deserializer::from_str) and the
+    /// like instead, unless reading from a custom input source.
+    pub fn new(read: R) -> Self {
+        Self::with_options(read, Options::default())
+    }
+
+    fn with_options(read: R, options: Options) -> Self {
         Self {
             read,
             scratch: Vec::new(),
+            remaining_depth: options.recursion_limit,
+            depth: 0,
+            pending: None,
+            not_id_chars: options.not_id_chars(),
+            options,
+            #[cfg(feature = "path")]
+            last_key: String::new(),
+        }
+    }
+
+    /// Replace the input, so that the deserializer and its buffers can be
+    /// reused for another value
+    ///
+    /// Constructing a deserializer allocates, as does its scratch buffer when
+    /// parsing escaped strings or reading from a stream. Services parsing
+    /// many small inputs can instead keep one deserializer per thread and
+    /// reset it for each input, keeping its configuration.
+    /// [`DeserializerBuilder::max_retained_scratch`] bounds the memory kept
+    /// between inputs.
+    ///
+    /// ```
+    /// use rison::de::read::StrRead;
+    /// use serde::Deserialize;
+    ///
+    /// let params = ["'it!'s'", "'!!important'"];
+    /// let mut de = rison::Deserializer::from_str("");
+    /// for param in params {
+    ///     de.reset(StrRead::new(param));
+    ///     let value = String::deserialize(&mut de).unwrap();
+    ///     de.end().unwrap();
+    ///     # let _ = value;
+    /// }
+    /// ```
+    pub fn reset(&mut self, read: R) {
+        self.read = read;
+        self.release_scratch();
+        self.remaining_depth = self.options.recursion_limit;
+        self.depth = 0;
+        self.pending = None;
+        #[cfg(feature = "path")]
+        self.last_key.clear();
+    }
+
+    /// Convert into a [`DynDeserializer`], keeping the input read so far
+    /// and the configuration
+    pub fn into_dyn(self) -> DynDeserializer<'de>
+    where
+        R: 'de,
+    {
+        Deserializer {
+            read: Box::new(self.read),
+            scratch: self.scratch,
+            remaining_depth: self.remaining_depth,
+            depth: self.depth,
+            not_id_chars: self.not_id_chars,
+            options: self.options,
+            pending: self.pending,
+            #[cfg(feature = "path")]
+            last_key: self.last_key,
+        }
+    }
+
+    /// Empty the scratch buffer, shrinking it to the capacity configured
+    /// with [`DeserializerBuilder::max_retained_scratch`]
+    fn release_scratch(&mut self) {
+        self.scratch.clear();
+        if let Some(capacity) = self.options.max_scratch_capacity {
+            self.scratch.shrink_to(capacity);
+        }
+    }
+
+    /// Report a value parsed at the current depth to the configured
+    /// [`ParseObserver`], if any
+    fn observe(&self, kind: ValueKind, len: Option<usize>) {
+        self.options.observe(kind, self.depth, len);
+    }
+
+    /// Set the maximum nesting depth of lists and objects
+    ///
+    /// Input nested more deeply than this fails with a syntax error rather
+    /// than risking overflowing the stack. Defaults to
+    /// [`DEFAULT_RECURSION_LIMIT`].
+    pub fn set_recursion_limit(&mut self, limit: usize) {
+        self.options.recursion_limit = limit;
+        self.remaining_depth = limit;
+    }
+
+    /// Parse arbitrarily deeply nested input without a recursion limit
+    ///
+    /// This is only appropriate for trusted input, or when deserializing on
+    /// a thread with a suitably large stack. Deeply nested input may
+    /// otherwise overflow the stack and abort the process.
+    #[cfg(feature = "unbounded_depth")]
+    pub fn disable_recursion_limit(&mut self) {
+        self.options.disable_recursion_limit = true;
+    }
+
+    fn recursion_limit_disabled(&self) -> bool {
+        self.options.recursion_limit_disabled()
+    }
+
+    /// Resolve the line and column of an error, where the source can locate
+    /// its position, and suggest a fix where the source holds its input
+    fn locate(&mut self, err: Error) -> Error {
+        let err = err.locate_with(|position| self.read.location(position));
+        match self.read.input() {
+            Some(input) => err.suggest(input),
+            None => err,
         }
     }
 
     fn peek(&mut self) -> Result<Option<u8>> {
+        if let Some(ch) = self.pending {
+            return Ok(Some(ch));
+        }
         self.read.peek()
     }
 
+    /// The position of the character most recently peeked, which precedes
+    /// the read position if a `!` has been consumed to look ahead for `!n`
+    fn peek_position(&mut self) -> usize {
+        self.read.position() - usize::from(self.pending.is_some())
+    }
+
+    /// Peek at the character following a `!`, reading `!T`, `!F`, and `!N`
+    /// as their lowercase forms if permitted
+    fn peek_marker(&mut self) -> Result<Option<u8>> {
+        match self.peek()? {
+            Some(ch @ (b'T' | b'F' | b'N')) if self.options.case_insensitive_markers => {
+                Ok(Some(ch.to_ascii_lowercase()))
+            }
+            peek => Ok(peek),
+        }
+    }
+
+    /// Skip any insignificant whitespace, if permitted, and peek at the
+    /// following character
+    fn parse_whitespace(&mut self) -> Result<Option<u8>> {
+        let max_input_len = self.options.limits.input_len();
+        if self.read.position() > max_input_len {
+            return Err(Error::new(
+                ErrorCode::InputLimitExceeded,
+                max_input_len.into(),
+            ));
+        }
+        loop {
+            match self.peek()? {
+                Some(ch) if self.options.allow_whitespace && WHITESPACE.contains(&ch) => {
+                    self.eat_char();
+                }
+                other => return Ok(other),
+            }
+        }
+    }
+
     fn eat_char(&mut self) {
-        self.read.discard();
+        if self.pending.take().is_none() {
+            self.read.discard();
+        }
+    }
+
+    /// Consume a single value without materializing any of it
+    fn ignore_value(&mut self) -> Result<()> {
+        match self.parse_whitespace()? {
+            Some(b'!') => {
+                let start = self.peek_position();
+                self.eat_char();
+                match self.peek_marker()? {
+                    Some(marker @ (b'n' | b't' | b'f')) => {
+                        self.eat_char();
+                        let kind = if marker == b'n' {
+                            ValueKind::Null
+                        } else {
+                            ValueKind::Bool
+                        };
+                        self.observe(kind, None);
+                        Ok(())
+                    }
+                    Some(b'(') => {
+                        self.observe(ValueKind::List, None);
+                        check_recursion! {
+                            self.eat_char();
+                            let ret = self.ignore_list(start);
+                        }
+                        ret
+                    }
+                    Some(_) => Err(Error::new(
+                        ErrorCode::InvalidMarker,
+                        self.read.position().into(),
+                    )),
+                    None => Err(Error::new(
+                        ErrorCode::EofMarker,
+                        self.read.position().into(),
+                    )),
+                }
+            }
+            Some(b'(') => {
+                let start = self.peek_position();
+                self.observe(ValueKind::Object, None);
+                check_recursion! {
+                    self.eat_char();
+                    let ret = self.ignore_object(start);
+                }
+                ret
+            }
+            Some(b'\'') => {
+                self.eat_char();
+                self.ignore_str(ValueKind::String)
+            }
+            Some(b'-' | b'0'..=b'9') if !self.options.profile.numeric_ids() => {
+                self.scan_number(|_| {})
+            }
+            Some(_) => self.ignore_ident(ValueKind::String),
+            None => Err(Error::new(ErrorCode::EofValue, self.read.position().into())),
+        }
+    }
+
+    /// Consume the `,` between elements of a list or object, returning
+    /// whether another element follows it
+    ///
+    /// A `,` directly before the closing `)` is permitted only when
+    /// configured with [`DeserializerBuilder::allow_trailing_commas`].
+    fn eat_separator(&mut self) -> Result<bool> {
+        let comma_position = self.read.position();
+        self.eat_char();
+        if self.parse_whitespace()? != Some(b')') {
+            return Ok(true);
+        }
+        if self.options.allow_trailing_commas {
+            Ok(false)
+        } else {
+            Err(Error::new(ErrorCode::TrailingComma, comma_position.into()))
+        }
+    }
+
+    /// Consume the remainder of a list following its opening `!(` at
+    /// `start`
+    fn ignore_list(&mut self, start: usize) -> Result<()> {
+        let mut first = true;
+        let mut len = 0;
+        loop {
+            match self.parse_whitespace()? {
+                Some(b')') => {
+                    self.eat_char();
+                    return Ok(());
+                }
+                Some(b',') if !first => {
+                    if !self.eat_separator()? {
+                        continue;
+                    }
+                }
+                Some(_) if first => first = false,
+                Some(_) => return Err(self.unexpected_char(ErrorCode::ExpectedListSepOrEnd)),
+                None => return Err(self.unclosed(ErrorCode::UnclosedList(start))),
+            }
+            len += 1;
+            self.check_collection_len(len)?;
+            self.ignore_value()?;
+        }
     }
 
-    fn next_char(&mut self) -> Result<Option<u8>> {
-        self.read.next()
+    /// Consume the remainder of an object following its opening `(` at
+    /// `start`
+    fn ignore_object(&mut self, start: usize) -> Result<()> {
+        let mut first = true;
+        let mut len = 0;
+        loop {
+            match self.parse_whitespace()? {
+                Some(b')') => {
+                    self.eat_char();
+                    return Ok(());
+                }
+                Some(b',') if !first => {
+                    if !self.eat_separator()? {
+                        continue;
+                    }
+                }
+                Some(_) if first => first = false,
+                Some(_) => return Err(self.unexpected_char(ErrorCode::ExpectedObjectSepOrEnd)),
+                None => return Err(self.unclosed(ErrorCode::UnclosedObject(start))),
+            }
+            len += 1;
+            self.check_collection_len(len)?;
+            match self.parse_whitespace()? {
+                Some(b'\'') => {
+                    self.eat_char();
+                    self.ignore_str(ValueKind::Key)?;
+                }
+                Some(b'!' | b'(') => self.ignore_value()?,
+                _ => self.ignore_ident(ValueKind::Key)?,
+            }
+            if self.parse_whitespace()? != Some(b':') {
+                return Err(self.unexpected_char(ErrorCode::ExpectedColon));
+            }
+            self.eat_char();
+            self.ignore_value()?;
+        }
     }
 
-    fn end(&mut self) -> Result<()> {
+    /// Consume a number, passing each of its bytes to `push`
+    ///
+    /// Numbers follow the grammar `-?(0|[1-9][0-9]*)(\.[0-9]+)?(e-?[0-9]+)?`,
+    /// so leading zeros, `+` signs, and capital or repeated exponents are
+    /// rejected with [`ErrorCode::InvalidNumber`] at the offending
+    /// character, unless a [`Profile`](crate::Profile) reading numbers as rison.js does is
+    /// configured. A number directly followed by any other character which
+    /// may continue an unquoted string, as in `1x` or `-a`, fails with
+    /// [`ErrorCode::InvalidIdStart`] at its start instead: the Rison grammar
+    /// reads values starting with a digit or `-` as numbers, so such strings
+    /// must be quoted. Object keys are exempt, and read as strings.
+    fn scan_number(&mut self, mut push: impl FnMut(u8)) -> Result<()> {
+        let js = self.options.profile.js_numbers();
+        let start_position = self.read.position();
+        let mut state = NumberState::Start;
+        let mut len = 0;
+        while let Some(b) = self.peek()? {
+            let Some(next) = state.next(b, js) else {
+                break;
+            };
+            state = next;
+            push(b);
+            self.eat_char();
+            len += 1;
+        }
+
         match self.peek()? {
-            Some(_) => Err(Error {
-                code: Code::TrailingChars,
-                position: self.read.position().into(),
-            }),
-            None => Ok(()),
+            Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') => Err(Error::new(
+                ErrorCode::InvalidNumber,
+                self.read.position().into(),
+            )),
+            Some(b) if !self.not_id_chars.contains(b) => {
+                Err(Error::new(ErrorCode::InvalidIdStart, start_position.into()))
+            }
+            _ if state.is_complete(js) => {
+                self.observe(ValueKind::Number, Some(len));
+                Ok(())
+            }
+            _ => Err(Error::new(
+                ErrorCode::InvalidNumber,
+                self.read.position().into(),
+            )),
         }
     }
-}
 
-impl<'de, 'a, R: Read<'de>> serde::de::Deserializer<'de> for &'a mut Deserializer<R> {
-    type Error = Error;
+    /// Parse a value beginning with `-` or a digit, which is a number
+    /// unless the configured [`Profile`](crate::Profile) reads such values
+    /// as unquoted strings when they are not numbers
+    ///
+    /// Numbers are returned in the Rison grammar, even where the profile
+    /// permits other forms.
+    fn parse_numeric(&mut self) -> Result<Numeric<'de, '_>> {
+        let js = self.options.profile.js_numbers();
+        let number = if self.options.profile.numeric_ids() {
+            // Borrow fields individually, so the value can be reported as
+            // whichever it turns out to be
+            let start_position = self.read.position();
+            self.scratch.clear();
+            let s = self
+                .read
+                .parse_ident(&mut self.scratch, &self.not_id_chars)?;
+            let s = check_string_len(s, start_position, &self.options)?;
+            if !is_number(s.as_bytes(), js) {
+                self.options
+                    .observe(ValueKind::String, self.depth, Some(s.len()));
+                return Ok(Numeric::Ident(s));
+            }
+            self.options
+                .observe(ValueKind::Number, self.depth, Some(s.len()));
+            s.to_owned()
+        } else {
+            let mut number = String::new();
+            self.scan_number(|b| number.push(b as char))?;
+            number
+        };
+        if js && !is_number(number.as_bytes(), false) {
+            return Ok(Numeric::Number(canonicalize_number(&number)));
+        }
+        Ok(Numeric::Number(number))
+    }
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    /// Deserialize a number written as an integer as a `u64` or `i64` if it
+    /// is in range. Other numbers are deserialized as an `i32` if they are
+    /// integral and in range, or as an `f64` otherwise, failing if they are
+    /// too large for one.
+    fn deserialize_number<V>(&mut self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        match self.peek()? {
+        match self.parse_whitespace()? {
+            Some(b'-' | b'0'..=b'9') => {}
+            _ => return serde::de::Deserializer::deserialize_any(self, visitor),
+        }
+        let start_position = self.read.position();
+        let number = match self.parse_numeric()? {
+            Numeric::Number(number) => number,
+            Numeric::Ident(read::Reference::Borrowed(borrowed)) => {
+                return visitor.visit_borrowed_str(borrowed)
+            }
+            Numeric::Ident(read::Reference::Copied(copied)) => return visitor.visit_str(copied),
+        };
+        let v: f64 = number
+            .parse()
+            .expect("the Rison number grammar is a subset of Rust's");
+        if !v.is_finite() {
+            return Err(Error::new(
+                ErrorCode::NumberOutOfRange,
+                start_position.into(),
+            ));
+        }
+
+        const MAX_INT: f64 = i32::MAX as _;
+        const MIN_INT: f64 = i32::MIN as _;
+        let truncated = v.trunc();
+        let value = if let Ok(u) = number.parse() {
+            visitor.visit_u64(u)
+        } else if let Ok(i) = number.parse() {
+            visitor.visit_i64(i)
+        } else if truncated == v && (MIN_INT..MAX_INT).contains(&truncated) {
+            visitor.visit_i32(truncated as i32)
+        } else {
+            visitor.visit_f64(v)
+        };
+        value.map_err(|err: Error| err.fix_position(start_position))
+    }
+
+    /// Parse a quoted string following its opening `'`
+    fn parse_str(&mut self) -> Result<read::Reference<'de, '_, str>> {
+        self.parse_str_as(ValueKind::String)
+    }
+
+    /// Parse a quoted string following its opening `'`, reporting it to
+    /// observers as a value of `kind`
+    fn parse_str_as(&mut self, kind: ValueKind) -> Result<read::Reference<'de, '_, str>> {
+        let start_position = self.read.position();
+        self.scratch.clear();
+        let s = self.read.parse_str(&mut self.scratch)?;
+        let s = check_control_chars(s, start_position, &self.options)?;
+        let s = check_string_len(s, start_position, &self.options)?;
+        self.options.observe(kind, self.depth, Some(s.len()));
+        Ok(s)
+    }
+
+    /// Consume a quoted string following its opening `'`, parsing it only
+    /// if its contents must be validated
+    fn ignore_str(&mut self, kind: ValueKind) -> Result<()> {
+        if self.options.reject_control_chars {
+            self.parse_str_as(kind).map(drop)
+        } else {
+            self.read.ignore_str()?;
+            self.observe(kind, None);
+            Ok(())
+        }
+    }
+
+    /// Consume an unquoted string
+    fn ignore_ident(&mut self, kind: ValueKind) -> Result<()> {
+        let start_position = self.read.position();
+        self.read.ignore_ident(&self.not_id_chars)?;
+        let len = self.read.position() - start_position;
+        self.observe(kind, Some(len));
+        Ok(())
+    }
+
+    /// An error for an unexpected character, reported as
+    /// [`ErrorCode::ReservedChar`] if it is one of the reserved `*` or `@`
+    fn unexpected_char(&mut self, code: ErrorCode) -> Error {
+        let code = match self.peek() {
+            Ok(Some(b'*' | b'@')) => ErrorCode::ReservedChar,
+            _ => code,
+        };
+        Error::new(code, self.read.position().into())
+    }
+
+    /// An error for input ending before the `)` of an unclosed list or
+    /// object, given its code recording where the list or object started
+    fn unclosed(&mut self, code: ErrorCode) -> Error {
+        Error::new(code, self.read.position().into())
+    }
+
+    /// Parse an unquoted string
+    ///
+    /// It is reported to observers as a string, or as the value it stands
+    /// for if it is a JSON literal and those are accepted.
+    fn parse_ident(&mut self) -> Result<read::Reference<'de, '_, str>> {
+        let start_position = self.read.position();
+        self.scratch.clear();
+        let s = self
+            .read
+            .parse_ident(&mut self.scratch, &self.not_id_chars)?;
+        let s = check_string_len(s, start_position, &self.options)?;
+        let (kind, len) = match &*s {
+            "true" | "false" if self.options.json_literals => (ValueKind::Bool, None),
+            "null" if self.options.json_literals => (ValueKind::Null, None),
+            _ => (ValueKind::String, Some(s.len())),
+        };
+        self.options.observe(kind, self.depth, len);
+        Ok(s)
+    }
+
+    /// Parse an object key as a string, whether quoted or not, reporting it
+    /// to observers as a value of `kind`
+    ///
+    /// Values such as characters and unit variants are parsed as keys are,
+    /// but reported as strings.
+    fn parse_key(&mut self, kind: ValueKind) -> Result<read::Reference<'de, '_, str>> {
+        let quoted = match self.parse_whitespace()? {
+            Some(b'\'') => {
+                self.eat_char();
+                true
+            }
+            Some(_) => false,
+            None => return Err(Error::new(ErrorCode::EofValue, self.read.position().into())),
+        };
+
+        // Borrow fields individually, so the key can be recorded for paths
+        // while the returned reference is held
+        let start_position = self.read.position();
+        self.scratch.clear();
+        let key = if quoted {
+            let key = self.read.parse_str(&mut self.scratch)?;
+            check_control_chars(key, start_position, &self.options)?
+        } else {
+            self.read
+                .parse_ident(&mut self.scratch, &self.not_id_chars)?
+        };
+        let key = match (key, &self.options.key_interner) {
+            (read::Reference::Copied(copied), Some(interner)) => match interner.0.intern(copied) {
+                Some(interned) => read::Reference::Borrowed(interned),
+                None => read::Reference::Copied(copied),
+            },
+            (key, _) => key,
+        };
+        #[cfg(feature = "path")]
+        {
+            self.last_key.clear();
+            self.last_key.push_str(&key);
+        }
+        let key = check_string_len(key, start_position, &self.options)?;
+        self.options.observe(kind, self.depth, Some(key.len()));
+        Ok(key)
+    }
+
+    /// Fail if a list or object has more than the permitted number of
+    /// elements, given the number seen so far
+    fn check_collection_len(&mut self, len: usize) -> Result<()> {
+        if len > self.options.limits.collection_len() {
+            return Err(Error::new(
+                ErrorCode::CollectionLimitExceeded,
+                self.read.position().into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn deserialize_raw_value<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // The first character of an optional raw value such as `!t` may have
+        // already been read, and can't be recovered into the raw text
+        if self.pending.is_some() {
+            return Err(Error::new(
+                ErrorCode::InvalidMarker,
+                self.read.position().into(),
+            ));
+        }
+        self.read.begin_raw_buffering();
+        serde::de::Deserialize::deserialize(&mut *self).map(|serde::de::IgnoredAny| ())?;
+        let raw = self.read.end_raw_buffering()?;
+
+        visitor.visit_map(crate::value::raw::RawDeserializer::new(raw))
+    }
+
+    /// Parse any value, passing it to the visitor as the type it is written
+    /// as
+    fn parse_any<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.parse_whitespace()? {
             Some(b'!') => {
+                let start = self.peek_position();
                 self.eat_char();
-                let peek = self.peek()?.ok_or(Error {
-                    code: Code::EofMarker,
-                    position: self.read.position().into(),
-                })?;
+                let peek = self.peek_marker()?.ok_or(Error::new(
+                    ErrorCode::EofMarker,
+                    self.read.position().into(),
+                ))?;
                 match peek {
                     b'n' => {
                         self.eat_char();
+                        self.observe(ValueKind::Null, None);
                         visitor.visit_unit()
                     }
                     b't' => {
                         self.eat_char();
+                        self.observe(ValueKind::Bool, None);
                         visitor.visit_bool(true)
                     }
                     b'f' => {
                         self.eat_char();
+                        self.observe(ValueKind::Bool, None);
                         visitor.visit_bool(false)
                     }
                     b'(' => {
-                        self.eat_char();
-
-                        let ret = visitor.visit_seq(SeqAccess::new(self));
-
-                        if let b')' = self.peek()?.ok_or(Error {
-                            code: Code::EofList,
-                            position: self.read.position().into(),
-                        })? {
+                        self.observe(ValueKind::List, None);
+                        check_recursion! {
+                            self.eat_char();
+                            let ret = visitor.visit_seq(SeqAccess::new(self, start));
+                        }
+                        let value = ret?;
+
+                        if let b')' = self
+                            .parse_whitespace()?
+                            .ok_or_else(|| self.unclosed(ErrorCode::UnclosedList(start)))?
+                        {
                             self.eat_char();
                         } else {
                             // TODO: Unreachable?
-                            return Err(Error {
-                                code: Code::TrailingChars,
-                                position: self.read.position().into(),
-                            });
+                            return Err(self.unexpected_char(ErrorCode::TrailingChars));
                         };
 
-                        ret
+                        Ok(value)
                     }
-                    _ => Err(Error {
-                        code: Code::InvalidMarker,
-                        position: self.read.position().into(),
-                    }),
-                }
-            }
-            Some(b'-' | b'0'..=b'9') => {
-                let mut f = String::new();
-                while let Some(ch @ (b'-' | b'0'..=b'9' | b'.' | b'e')) = self.peek()? {
-                    f.push(ch as char);
-                    self.eat_char();
-                }
-
-                let v: f64 = f.parse().map_err(|_e| Error {
-                    code: Code::InvalidNumber,
-                    position: self.read.position().into(),
-                })?;
-
-                const MAX_INT: f64 = std::i32::MAX as _;
-                const MIN_INT: f64 = std::i32::MIN as _;
-                let truncated = v.trunc();
-                if truncated == v && (MIN_INT..MAX_INT).contains(&truncated) {
-                    visitor.visit_i32(truncated as i32)
-                } else {
-                    visitor.visit_f64(v)
+                    _ => Err(Error::new(
+                        ErrorCode::InvalidMarker,
+                        self.read.position().into(),
+                    )),
                 }
             }
+            Some(b'-' | b'0'..=b'9') => self.deserialize_number(visitor),
             Some(b'\'') => {
                 self.eat_char();
 
-                self.scratch.clear();
-                let s = self.read.parse_str(&mut self.scratch)?;
+                let s = self.parse_str()?;
 
                 match s {
                     read::Reference::Borrowed(borrowed) => visitor.visit_borrowed_str(borrowed),
@@ -147,400 +848,2902 @@ impl<'de, 'a, R: Read<'de>> serde::de::Deserializer<'de> for &'a mut Deserialize
                 }
             }
             Some(b'(') => {
-                self.eat_char();
-
-                let ret = visitor.visit_map(MapAccess::new(self));
+                let start = self.peek_position();
+                self.observe(ValueKind::Object, None);
+                check_recursion! {
+                    self.eat_char();
+                    let ret = visitor.visit_map(MapAccess::new(self, start));
+                }
+                let value = ret?;
 
-                if let b')' = self.peek()?.ok_or(Error {
-                    code: Code::EofObject,
-                    position: self.read.position().into(),
-                })? {
+                if let b')' = self
+                    .parse_whitespace()?
+                    .ok_or_else(|| self.unclosed(ErrorCode::UnclosedObject(start)))?
+                {
                     self.eat_char();
                 } else {
                     // TODO: Unreachable?
-                    return Err(Error {
-                        code: Code::TrailingChars,
-                        position: self.read.position().into(),
-                    });
+                    return Err(self.unexpected_char(ErrorCode::TrailingChars));
                 };
 
-                ret
+                Ok(value)
             }
             Some(_) => {
-                self.scratch.clear();
-                let value = self.read.parse_ident(&mut self.scratch)?;
+                let json_literals = self.options.json_literals;
+                let value = self.parse_ident()?;
                 match value {
+                    _ if json_literals && &*value == "true" => visitor.visit_bool(true),
+                    _ if json_literals && &*value == "false" => visitor.visit_bool(false),
+                    _ if json_literals && &*value == "null" => visitor.visit_unit(),
                     read::Reference::Borrowed(borrowed) => visitor.visit_borrowed_str(borrowed),
                     read::Reference::Copied(copied) => visitor.visit_str(copied),
                 }
             }
-            None => Err(Error {
-                code: Code::EofValue,
-                position: self.read.position().into(),
-            }),
+            None => Err(Error::new(ErrorCode::EofValue, self.read.position().into())),
         }
     }
 
-    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    /// Deserialize an identifier, reporting it to observers as a value of
+    /// `kind`
+    fn deserialize_identifier_as<V>(&mut self, kind: ValueKind, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        match self.peek()? {
-            Some(b'!') => {
-                self.eat_char();
-                if self.next_char()? != Some(b'n') {
-                    return Err(Error {
-                        code: Code::InvalidMarker,
-                        position: self.read.position().into(),
-                    });
+        match self.parse_whitespace()? {
+            Some(b'!' | b'(') | None => serde::de::Deserializer::deserialize_any(self, visitor),
+            Some(_) => {
+                let start_position = self.read.position();
+                let value = match self.parse_key(kind)? {
+                    read::Reference::Borrowed(borrowed) => visitor.visit_borrowed_str(borrowed),
+                    read::Reference::Copied(copied) => visitor.visit_str(copied),
+                };
+                value.map_err(|err: Error| err.fix_position(start_position))
+            }
+        }
+    }
+
+    /// Deserialize a list of exactly `len` elements, for tuples and arrays
+    ///
+    /// The visitor reports lists which are too short. Lists which are too
+    /// long are counted to their end and reported as an invalid length,
+    /// rather than as unexpected characters after the last element the
+    /// visitor wanted. Either is reported at the start of the list.
+    fn deserialize_fixed_len<V>(
+        &mut self,
+        len: usize,
+        expected: std::fmt::Arguments,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        use serde::de::{Error as _, SeqAccess as _};
+
+        if self.parse_whitespace()? != Some(b'!') {
+            return serde::de::Deserializer::deserialize_any(self, visitor);
+        }
+        // A `!` may already have been consumed to look ahead for `!n`
+        let start_position = self.peek_position();
+        self.eat_char();
+        if self.peek()? != Some(b'(') {
+            self.pending = Some(b'!');
+            return serde::de::Deserializer::deserialize_any(self, visitor);
+        }
+
+        self.observe(ValueKind::List, None);
+        check_recursion! {
+            self.eat_char();
+            let mut seq = SeqAccess::new(self, start_position);
+            let ret = visitor.visit_seq(&mut seq).and_then(|value| {
+                while seq.next_element::<serde::de::IgnoredAny>()?.is_some() {}
+                if seq.len > len {
+                    return Err(Error::invalid_length(seq.len, &&*expected.to_string()));
                 }
-                visitor.visit_none()
+                Ok(value)
+            });
+        }
+        let value = ret.map_err(|err| err.fix_position(start_position))?;
+
+        match self.parse_whitespace()? {
+            Some(b')') => {
+                self.eat_char();
+                Ok(value)
             }
-            _ => visitor.visit_some(self),
+            Some(_) => Err(self.unexpected_char(ErrorCode::ExpectedListSepOrEnd)),
+            None => Err(self.unclosed(ErrorCode::UnclosedList(start_position))),
         }
     }
 
-    serde::forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any
+    /// Deserialize a string, passing it to `visit` a chunk at a time rather
+    /// than holding all of it at once
+    ///
+    /// Quoted strings read from a stream, as with
+    /// [`from_reader`](Deserializer::from_reader), are passed on in chunks as
+    /// they are read, so that a string of many megabytes may be written out
+    /// or hashed without first being copied into memory whole. Other input
+    /// sources, and unquoted strings, pass the string as a single chunk. Each
+    /// chunk is valid utf-8, and the limit on the length of strings applies
+    /// to the string as a whole.
+    ///
+    /// ```
+    /// let input = format!("'{}'", "a".repeat(100_000));
+    /// let mut de = rison::Deserializer::from_reader(input.as_bytes());
+    /// let mut len = 0;
+    /// de.visit_str_chunks(|chunk| {
+    ///     len += chunk.len();
+    ///     Ok(())
+    /// })
+    /// .unwrap();
+    /// de.end().unwrap();
+    ///
+    /// assert_eq!(len, 100_000);
+    /// ```
+    pub fn visit_str_chunks(&mut self, mut visit: impl FnMut(&str) -> Result<()>) -> Result<()> {
+        if self.parse_whitespace()? != Some(b'\'') {
+            return serde::de::Deserializer::deserialize_str(self, StrChunkVisitor(&mut visit))?;
+        }
+        self.eat_char();
+        let start_position = self.read.position();
+        let options = &self.options;
+        let mut len = 0;
+        let mut chunk_position = start_position;
+        self.scratch.clear();
+        self.read.parse_str_visit(&mut self.scratch, &mut |chunk| {
+            len += chunk.len();
+            if len > options.limits.string_len() {
+                return Err(Error::new(
+                    ErrorCode::StringLimitExceeded,
+                    start_position.into(),
+                ));
+            }
+            check_control_chars(read::Reference::Copied(chunk), chunk_position, options)?;
+            // Each `!` or `'` in the chunk was unescaped from two characters
+            let escapes = chunk.bytes().filter(|&b| b == b'!' || b == b'\'').count();
+            chunk_position += chunk.len() + escapes;
+            visit(chunk)
+        })
     }
-}
 
-struct MapAccess<'d, R: 'd> {
-    de: &'d mut Deserializer<R>,
-    first: bool,
+    /// Check that the input has been fully consumed, other than any
+    /// permitted trailing whitespace
+    ///
+    /// This should be called after deserializing a value when driving the
+    /// deserializer manually, as `from_str` and friends do, so that input
+    /// such as `1,2` is not accepted as `1`.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// let mut de = rison::Deserializer::from_str("(a:1)(b:2)");
+    /// let value = serde_json::Value::deserialize(&mut de).unwrap();
+    ///
+    /// assert_eq!(value, serde_json::json!({"a": 1}));
+    /// assert!(de.end().is_err());
+    /// ```
+    pub fn end(&mut self) -> Result<()> {
+        self.release_scratch();
+        match self.parse_whitespace()? {
+            Some(_) => Err(self.unexpected_char(ErrorCode::TrailingChars)),
+            None => Ok(()),
+        }
+    }
 }
 
-impl<'a, R: 'a> MapAccess<'a, R> {
-    fn new(de: &'a mut Deserializer<R>) -> Self {
-        MapAccess { de, first: true }
-    }
+macro_rules! deserialize_number {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: serde::de::Visitor<'de>,
+            {
+                self.deserialize_number(visitor)
+            }
+        )*
+    };
 }
 
-impl<'de, 'a, R: Read<'de> + 'a> serde::de::MapAccess<'de> for MapAccess<'a, R> {
+impl<'de, R: Read<'de>> serde::de::Deserializer<'de> for &mut Deserializer<R> {
     type Error = Error;
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
-        K: serde::de::DeserializeSeed<'de>,
+        V: serde::de::Visitor<'de>,
     {
-        match self.de.peek()? {
-            Some(b')') => return Ok(None),
-            Some(b',') if !self.first => {
-                self.de.eat_char();
-            }
-            Some(_) => {
-                if self.first {
-                    self.first = false;
-                } else {
-                    return Err(Error {
-                        code: Code::ExpectedObjectSepOrEnd,
-                        position: self.de.read.position().into(),
-                    });
-                }
-            }
-            None => {
-                return Err(Error {
-                    code: Code::EofObject,
-                    position: self.de.read.position().into(),
-                });
-            }
-        };
-
-        seed.deserialize(&mut *self.de).map(Some)
+        self.parse_whitespace()?;
+        let start_position = self.read.position();
+        self.parse_any(visitor)
+            .map_err(|err: Error| err.fix_position(start_position))
     }
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
-        V: serde::de::DeserializeSeed<'de>,
+        V: serde::de::Visitor<'de>,
     {
-        match self.de.peek()? {
-            Some(b':') => {
-                self.de.eat_char();
-            }
-            _ => {
-                return Err(Error {
-                    code: Code::ExpectedColon,
-                    position: self.de.read.position().into(),
-                })
+        let start_position = self.read.position();
+        let encoded = match self.parse_whitespace()? {
+            Some(b'!' | b'(') => return self.deserialize_any(visitor),
+            Some(b'\'') => {
+                self.eat_char();
+                self.parse_str()?
             }
+            Some(_) => self.parse_ident()?,
+            None => return Err(Error::new(ErrorCode::EofValue, self.read.position().into())),
+        };
+
+        match crate::bytes::decode(&encoded) {
+            Some(bytes) => visitor
+                .visit_byte_buf(bytes)
+                .map_err(|err: Error| err.fix_position(start_position)),
+            None => Err(Error::new(ErrorCode::InvalidBase64, start_position.into())),
         }
-        seed.deserialize(&mut *self.de)
     }
-}
 
-struct SeqAccess<'d, R: 'd> {
+    /// Characters are accepted as quoted strings or idents of exactly one
+    /// character
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let start_position = self.read.position();
+        let s = match self.parse_whitespace()? {
+            Some(b'!' | b'(') | None => return self.deserialize_any(visitor),
+            Some(_) => self.parse_key(ValueKind::String)?,
+        };
+
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor
+                .visit_char(c)
+                .map_err(|err: Error| err.fix_position(start_position)),
+            _ => Err(Error::new(ErrorCode::InvalidChar, start_position.into())),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.parse_whitespace()? {
+            Some(b'!') => {
+                self.eat_char();
+                if self.peek_marker()? == Some(b'n') {
+                    self.eat_char();
+                    self.observe(ValueKind::Null, None);
+                    return visitor.visit_none();
+                }
+                // Any other marker begins a present value, such as `!t` or
+                // `!(..)`, so hand the `!` back to be parsed as part of it
+                self.pending = Some(b'!');
+                visitor.visit_some(self)
+            }
+            Some(b'\'') if self.options.empty_string_as_none => {
+                self.eat_char();
+                if self.peek()? == Some(b'\'') {
+                    self.eat_char();
+                    self.observe(ValueKind::String, Some(0));
+                    return visitor.visit_none();
+                }
+                self.pending = Some(b'\'');
+                visitor.visit_some(self)
+            }
+            Some(b'n') if self.options.json_literals => {
+                let start_position = self.read.position();
+                let ident = self.parse_ident()?;
+                if &*ident == "null" {
+                    visitor.visit_none()
+                } else {
+                    visitor.visit_some(IdentDeserializer {
+                        ident,
+                        start_position,
+                    })
+                }
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if name == crate::value::raw::TOKEN {
+            return self.deserialize_raw_value(visitor);
+        }
+        if name == spanned::TOKEN {
+            self.parse_whitespace()?;
+            // A `!` may already have been consumed to look ahead for `!n`
+            let start = self.peek_position();
+            return visitor.visit_map(spanned::SpannedDeserializer::new(self, start));
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        if name == crate::number::TOKEN {
+            if let Some(b'-' | b'0'..=b'9') = self.parse_whitespace()? {
+                return match self.parse_numeric()? {
+                    Numeric::Number(number) => {
+                        visitor.visit_map(crate::number::NumberDeserializer::new(number))
+                    }
+                    Numeric::Ident(read::Reference::Borrowed(borrowed)) => {
+                        visitor.visit_borrowed_str(borrowed)
+                    }
+                    Numeric::Ident(read::Reference::Copied(copied)) => visitor.visit_str(copied),
+                };
+            }
+        }
+
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_fixed_len(len, format_args!("a list of length {}", len), visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_fixed_len(
+            len,
+            format_args!("tuple struct {} with {} elements", name, len),
+            visitor,
+        )
+    }
+
+    /// Unit structs are written as `!n`, as units are
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        serde::de::Deserializer::deserialize_unit(self, visitor)
+    }
+
+    /// Identifiers, such as struct field names, are parsed directly as
+    /// strings, bypassing number detection so that keys like `1` or `-a`
+    /// are not mistaken for the start of a number
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_identifier_as(ValueKind::String, visitor)
+    }
+
+    /// Ignored values are skipped syntactically, without allocating strings
+    /// or parsing numbers
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.ignore_value()?;
+        visitor.visit_unit()
+    }
+
+    /// With the `arbitrary_precision` feature, numbers are provided to
+    /// string visitors as their exact text
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(b'-' | b'0'..=b'9') = self.parse_whitespace()? {
+            return match self.parse_numeric()? {
+                Numeric::Number(number) => visitor.visit_string(number),
+                Numeric::Ident(read::Reference::Borrowed(borrowed)) => {
+                    visitor.visit_borrowed_str(borrowed)
+                }
+                Numeric::Ident(read::Reference::Copied(copied)) => visitor.visit_str(copied),
+            };
+        }
+
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    deserialize_number! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    /// Unit variants are written as their name, and other variants as an
+    /// object with a single entry, from their name to their content
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.parse_whitespace()? {
+            Some(b'(') => {
+                let start = self.peek_position();
+                self.observe(ValueKind::Object, None);
+                check_recursion! {
+                    self.eat_char();
+                    let ret = visitor.visit_enum(VariantAccess { de: self });
+                }
+                let value = ret?;
+
+                match self.parse_whitespace()? {
+                    Some(b')') => {
+                        self.eat_char();
+                        Ok(value)
+                    }
+                    Some(_) => Err(self.unexpected_char(ErrorCode::ExpectedObjectEnd)),
+                    None => Err(self.unclosed(ErrorCode::UnclosedObject(start))),
+                }
+            }
+            Some(_) => {
+                let start_position = self.read.position();
+                visitor
+                    .visit_enum(UnitVariantAccess { de: self })
+                    .map_err(|err| err.fix_position(start_position))
+            }
+            None => Err(Error::new(ErrorCode::EofValue, self.read.position().into())),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool
+        unit seq map struct
+    }
+}
+
+/// Accesses a variant written as an object with a single entry, such as
+/// `(Newtype:1)`, from within its `(`
+struct VariantAccess<'a, R: 'a> {
+    de: &'a mut Deserializer<R>,
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> serde::de::EnumAccess<'de> for VariantAccess<'a, R> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        self.de.parse_whitespace()?;
+        let start_position = self.de.read.position();
+        let variant = seed
+            .deserialize(MapKey { de: &mut *self.de })
+            .map_err(|err| err.fix_position(start_position))?;
+        match self.de.parse_whitespace()? {
+            Some(b':') => {
+                self.de.eat_char();
+            }
+            _ => return Err(self.de.unexpected_char(ErrorCode::ExpectedColon)),
+        }
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> serde::de::VariantAccess<'de> for VariantAccess<'a, R> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        serde::de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        serde::de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        serde::de::Deserializer::deserialize_struct(self.de, "", fields, visitor)
+    }
+}
+
+/// Accesses a unit variant, written as its name alone
+struct UnitVariantAccess<'a, R: 'a> {
+    de: &'a mut Deserializer<R>,
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> serde::de::EnumAccess<'de> for UnitVariantAccess<'a, R> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> serde::de::VariantAccess<'de> for UnitVariantAccess<'a, R> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, _seed: T) -> Result<T::Value>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        Err(serde::de::Error::invalid_type(
+            serde::de::Unexpected::UnitVariant,
+            &"newtype variant",
+        ))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        Err(serde::de::Error::invalid_type(
+            serde::de::Unexpected::UnitVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        Err(serde::de::Error::invalid_type(
+            serde::de::Unexpected::UnitVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+struct MapAccess<'d, R: 'd> {
     de: &'d mut Deserializer<R>,
+    /// The position of the opening `(`
+    start: usize,
     first: bool,
+    len: usize,
+    /// The text of the most recent key, with which errors in its value are
+    /// annotated
+    #[cfg(feature = "path")]
+    key: String,
 }
 
-impl<'a, R: 'a> SeqAccess<'a, R> {
-    fn new(de: &'a mut Deserializer<R>) -> Self {
-        SeqAccess { de, first: true }
+impl<'a, R: 'a> MapAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, start: usize) -> Self {
+        MapAccess {
+            de,
+            start,
+            first: true,
+            len: 0,
+            #[cfg(feature = "path")]
+            key: String::new(),
+        }
     }
 }
 
-impl<'de, 'a, R: Read<'de> + 'a> serde::de::SeqAccess<'de> for SeqAccess<'a, R> {
+impl<'de, 'a, R: Read<'de> + 'a> serde::de::MapAccess<'de> for MapAccess<'a, R> {
     type Error = Error;
 
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
-        T: serde::de::DeserializeSeed<'de>,
+        K: serde::de::DeserializeSeed<'de>,
     {
-        match self.de.peek()? {
+        match self.de.parse_whitespace()? {
             Some(b')') => return Ok(None),
             Some(b',') if !self.first => {
-                self.de.eat_char();
+                if !self.de.eat_separator()? {
+                    return Ok(None);
+                }
             }
             Some(_) => {
                 if self.first {
                     self.first = false;
                 } else {
-                    return Err(Error {
-                        code: Code::ExpectedListSepOrEnd,
-                        position: self.de.read.position().into(),
-                    });
+                    return Err(self.de.unexpected_char(ErrorCode::ExpectedObjectSepOrEnd));
                 }
             }
-            None => {
-                return Err(Error {
-                    code: Code::EofList,
-                    position: self.de.read.position().into(),
-                })
-            }
+            None => return Err(self.de.unclosed(ErrorCode::UnclosedObject(self.start))),
         };
 
-        seed.deserialize(&mut *self.de).map(Some)
+        self.len += 1;
+        self.de.check_collection_len(self.len)?;
+
+        #[cfg(feature = "path")]
+        self.de.last_key.clear();
+        self.de.parse_whitespace()?;
+        let start_position = self.de.read.position();
+        let key = seed
+            .deserialize(MapKey { de: &mut *self.de })
+            .map_err(|err| err.fix_position(start_position))?;
+        #[cfg(feature = "path")]
+        std::mem::swap(&mut self.key, &mut self.de.last_key);
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        match self.de.parse_whitespace()? {
+            Some(b':') => {
+                self.de.eat_char();
+            }
+            _ => return Err(self.de.unexpected_char(ErrorCode::ExpectedColon)),
+        }
+        // Values such as untagged enums may fail after being parsed in full,
+        // so are attributed to where they begin
+        self.de.parse_whitespace()?;
+        let start_position = self.de.read.position();
+        let value = seed
+            .deserialize(&mut *self.de)
+            .map_err(|err| err.fix_position(start_position));
+        #[cfg(feature = "path")]
+        let value = value.map_err(|err| err.within(Segment::Key(self.key.clone())));
+        value
+    }
+}
+
+/// Deserializes object keys, which are parsed as strings unless a non-string
+/// type is requested, in which case the key text is parsed as that type
+struct MapKey<'a, R: 'a> {
+    de: &'a mut Deserializer<R>,
+}
+
+macro_rules! deserialize_numeric_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: serde::de::Visitor<'de>,
+            {
+                let start_position = self.de.read.position();
+                let key = self.de.parse_key(ValueKind::Key)?;
+                match key.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(Error::new(ErrorCode::InvalidNumber, start_position.into())),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, R: Read<'de>> serde::de::Deserializer<'de> for MapKey<'_, R> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.de.parse_whitespace()? {
+            Some(b'!' | b'(') => self.de.deserialize_any(visitor),
+            _ => match self.de.parse_key(ValueKind::Key)? {
+                read::Reference::Borrowed(borrowed) => visitor.visit_borrowed_str(borrowed),
+                read::Reference::Copied(copied) => visitor.visit_str(copied),
+            },
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.de.parse_whitespace()? {
+            Some(b'!') => self.de.deserialize_any(visitor),
+            _ => {
+                let start_position = self.de.read.position();
+                match &*self.de.parse_key(ValueKind::Key)? {
+                    "true" => visitor.visit_bool(true),
+                    "false" => visitor.visit_bool(false),
+                    _ => Err(Error::new(ErrorCode::InvalidBool, start_position.into())),
+                }
+            }
+        }
+    }
+
+    deserialize_numeric_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    /// Keys requested as a [`SharedKey`] share the copy provided by the
+    /// interner, if any
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if let (intern::TOKEN, Some(interner)) = (name, self.de.options.key_interner.clone()) {
+            let key = self.de.parse_key(ValueKind::Key)?;
+            return match interner.0.intern_shared(&key) {
+                Some(shared) => intern::visit_shared(&key, shared, visitor),
+                None => match key {
+                    read::Reference::Borrowed(borrowed) => visitor.visit_borrowed_str(borrowed),
+                    read::Reference::Copied(copied) => visitor.visit_str(copied),
+                },
+            };
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.de.deserialize_identifier_as(ValueKind::Key, visitor)
+    }
+
+    /// Only unit variants can be written as keys
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_enum(UnitVariantAccess { de: self.de })
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct ignored_any
     }
 }
 
-fn from_trait<'de, R, T>(read: R) -> Result<T>
-where
-    R: Read<'de>,
-    T: serde::de::Deserialize<'de>,
-{
-    let mut de = Deserializer::new(read);
-    let value = serde::de::Deserialize::deserialize(&mut de)?;
+/// Deserializes an unquoted string which has already been parsed, as when
+/// checking for a `null` literal before an optional value
+struct IdentDeserializer<'de, 's> {
+    ident: read::Reference<'de, 's, str>,
+    start_position: usize,
+}
+
+impl<'de> serde::de::Deserializer<'de> for IdentDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.ident {
+            read::Reference::Borrowed(borrowed) => visitor.visit_borrowed_str(borrowed),
+            read::Reference::Copied(copied) => visitor.visit_str(copied),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match crate::bytes::decode(&self.ident) {
+            Some(bytes) => visitor.visit_byte_buf(bytes),
+            None => Err(Error::new(
+                ErrorCode::InvalidBase64,
+                self.start_position.into(),
+            )),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        option unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct SeqAccess<'d, R: 'd> {
+    de: &'d mut Deserializer<R>,
+    /// The position of the opening `!(`
+    start: usize,
+    first: bool,
+    len: usize,
+}
+
+impl<'a, R: 'a> SeqAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, start: usize) -> Self {
+        SeqAccess {
+            de,
+            start,
+            first: true,
+            len: 0,
+        }
+    }
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> serde::de::SeqAccess<'de> for SeqAccess<'a, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        match self.de.parse_whitespace()? {
+            Some(b')') => return Ok(None),
+            Some(b',') if !self.first => {
+                if !self.de.eat_separator()? {
+                    return Ok(None);
+                }
+            }
+            Some(_) => {
+                if self.first {
+                    self.first = false;
+                } else {
+                    return Err(self.de.unexpected_char(ErrorCode::ExpectedListSepOrEnd));
+                }
+            }
+            None => return Err(self.de.unclosed(ErrorCode::UnclosedList(self.start))),
+        };
+
+        self.len += 1;
+        self.de.check_collection_len(self.len)?;
+        self.de.parse_whitespace()?;
+        let start_position = self.de.read.position();
+        let element = seed
+            .deserialize(&mut *self.de)
+            .map_err(|err| err.fix_position(start_position));
+        #[cfg(feature = "path")]
+        let element = element.map_err(|err| err.within(Segment::Index(self.len - 1)));
+        element.map(Some)
+    }
+}
+
+/// A position within the grammar of numbers, recognized by
+/// [`Deserializer::scan_number`]
+#[derive(Clone, Copy)]
+enum NumberState {
+    Start,
+    /// After a leading `-`
+    Minus,
+    /// After a leading `0`, which may not be followed by digits
+    Zero,
+    Int,
+    /// After a `.`, which must be followed by digits
+    Point,
+    Frac,
+    /// After an `e`, which must be followed by digits or a `-`
+    Exp,
+    ExpMinus,
+    ExpDigits,
+}
+
+impl NumberState {
+    /// The state after `b`, if it may continue a number
+    ///
+    /// With `js`, numbers are read as rison.js reads them, additionally
+    /// permitting leading zeros, a `.` without digits after it, and a
+    /// capital `E`.
+    fn next(self, b: u8, js: bool) -> Option<Self> {
+        use NumberState::*;
+
+        Some(match (self, b) {
+            (Start, b'-') => Minus,
+            (Start | Minus, b'0') if !js => Zero,
+            (Start | Minus | Int, b'0'..=b'9') => Int,
+            (Zero, b'0'..=b'9') if js => Int,
+            (Zero | Int, b'.') => Point,
+            (Point | Frac, b'0'..=b'9') => Frac,
+            (Zero | Int | Frac, b'e') => Exp,
+            (Zero | Int | Point | Frac, b'e' | b'E') if js => Exp,
+            (Exp, b'-') => ExpMinus,
+            (Exp | ExpMinus | ExpDigits, b'0'..=b'9') => ExpDigits,
+            _ => return None,
+        })
+    }
+
+    /// Whether a number may end in this state
+    fn is_complete(self, js: bool) -> bool {
+        use NumberState::*;
+
+        matches!(self, Zero | Int | Frac | ExpDigits) || js && matches!(self, Point)
+    }
+}
+
+/// Whether `bytes` are a number in their entirety, read as rison.js reads
+/// numbers with `js`
+pub(crate) fn is_number(bytes: &[u8], js: bool) -> bool {
+    let mut state = NumberState::Start;
+    for &b in bytes {
+        match state.next(b, js) {
+            Some(next) => state = next,
+            None => return false,
+        }
+    }
+    state.is_complete(js)
+}
+
+/// Rewrite a number read as rison.js reads numbers in the grammar of Rison,
+/// as in `7` for `007`, `1` for `1.`, or `1e3` for `1E3`
+fn canonicalize_number(number: &str) -> String {
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", number),
+    };
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let int = int.trim_start_matches('0');
+
+    let mut canonical = String::from(sign);
+    canonical.push_str(if int.is_empty() { "0" } else { int });
+    if !frac.is_empty() {
+        canonical.push('.');
+        canonical.push_str(frac);
+    }
+    if let Some(exponent) = exponent {
+        canonical.push('e');
+        canonical.push_str(exponent);
+    }
+    canonical
+}
+
+/// A value beginning with `-` or a digit, read by
+/// [`Deserializer::parse_numeric`]
+enum Numeric<'de, 's> {
+    /// The text of a number
+    Number(String),
+    /// An unquoted string which is not a number
+    Ident(read::Reference<'de, 's, str>),
+}
+
+/// Fail if a string is longer than permitted
+fn check_string_len<'de, 's>(
+    s: read::Reference<'de, 's, str>,
+    start_position: usize,
+    options: &Options,
+) -> Result<read::Reference<'de, 's, str>> {
+    if s.len() > options.limits.string_len() {
+        return Err(Error::new(
+            ErrorCode::StringLimitExceeded,
+            start_position.into(),
+        ));
+    }
+    Ok(s)
+}
+
+/// Passes a string deserialized other than as a quoted string to
+/// [`Deserializer::visit_str_chunks`] whole, returning what it returns
+struct StrChunkVisitor<'a>(&'a mut dyn FnMut(&str) -> Result<()>);
+
+impl serde::de::Visitor<'_> for StrChunkVisitor<'_> {
+    type Value = Result<()>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Result<()>, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok((self.0)(v))
+    }
+}
+
+/// Fail if a quoted string contains a control character while configured
+/// to reject them, at the position of the first in the input
+fn check_control_chars<'de, 's>(
+    s: read::Reference<'de, 's, str>,
+    start_position: usize,
+    options: &Options,
+) -> Result<read::Reference<'de, 's, str>> {
+    if !options.reject_control_chars {
+        return Ok(s);
+    }
+    if let Some(index) = s.bytes().position(|b| b < 0x20) {
+        // Each `!` or `'` before it was unescaped from two characters
+        let escapes = s.as_bytes()[..index]
+            .iter()
+            .filter(|&&b| b == b'!' || b == b'\'')
+            .count();
+        return Err(Error::new(
+            ErrorCode::ControlCharInString,
+            (start_position + index + escapes).into(),
+        ));
+    }
+    Ok(s)
+}
+
+pub(crate) fn from_trait<'de, R, T>(read: R) -> Result<T>
+where
+    R: Read<'de>,
+    T: serde::de::Deserialize<'de>,
+{
+    from_trait_seed(read, std::marker::PhantomData)
+}
+
+fn from_trait_seed<'de, R, S>(read: R, seed: S) -> Result<S::Value>
+where
+    R: Read<'de>,
+    S: serde::de::DeserializeSeed<'de>,
+{
+    from_deserializer_seed(Deserializer::new(read), seed)
+}
+
+fn from_deserializer_seed<'de, R, S>(mut de: Deserializer<R>, seed: S) -> Result<S::Value>
+where
+    R: Read<'de>,
+    S: serde::de::DeserializeSeed<'de>,
+{
+    let value = seed
+        .deserialize(&mut de)
+        .map_err(|err| err.fix_position(de.read.position()))
+        .map_err(|err| de.locate(err))?;
+
+    de.end().map_err(|err| de.locate(err))?;
+
+    Ok(value)
+}
+
+fn from_trait_partial<'de, R, T>(read: R) -> Result<(T, usize)>
+where
+    R: Read<'de>,
+    T: serde::de::Deserialize<'de>,
+{
+    let options = Options {
+        partial: true,
+        ..Options::default()
+    };
+    let mut de = Deserializer::with_options(read, options);
+    let value = serde::de::Deserialize::deserialize(&mut de)
+        .map_err(|err| err.fix_position(de.read.position()))
+        .map_err(|err| de.locate(err))?;
+
+    Ok((value, de.read.position()))
+}
+
+/// Deserialize an instance of `T` from a byte slice of Rison
+pub fn from_slice<'a, T>(v: &'a [u8]) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+{
+    from_trait(read::SliceRead::new(v))
+}
+
+/// Deserialize an instance of `T` from a mutable byte slice of Rison,
+/// unescaping quoted strings in place
+///
+/// Unlike [`from_slice`], which must copy strings containing `!!` or `!'`
+/// escapes, every string can be borrowed from the input, so `&str` fields
+/// never fail to deserialize and `Cow<str>`s are never owned. The contents of
+/// `v` are unspecified afterwards. Errors have a position, but no line or
+/// column.
+///
+/// ```
+/// #[derive(serde::Deserialize)]
+/// struct Search<'a> {
+///     q: &'a str,
+/// }
+///
+/// let mut input = b"(q:'it!'s')".to_vec();
+/// let search: Search = rison::de::from_bytes_mut(&mut input).unwrap();
+///
+/// assert_eq!(search.q, "it's");
+/// ```
+pub fn from_bytes_mut<'a, T>(v: &'a mut [u8]) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+{
+    from_trait(read::MutSliceRead::new(v))
+}
+
+/// Deserialize an instance of `T` from a string of Rison
+pub fn from_str<'a, T>(v: &'a str) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+{
+    from_trait(read::StrRead::new(v))
+}
+
+/// Deserialize an instance of `T` from an IO stream of Rison
+pub fn from_reader<'a, T, I>(v: I) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+    I: std::io::Read,
+{
+    from_trait(read::IoRead::new(read::bytes(v)))
+}
+
+/// Deserialize an instance of `T` from a buffered IO stream of Rison
+///
+/// ```
+/// use std::io::BufReader;
+///
+/// let file = BufReader::new("(a:!(1,2),b:'x y')".as_bytes());
+/// let value: serde_json::Value = rison::from_bufread(file).unwrap();
+///
+/// assert_eq!(value["b"], "x y");
+/// ```
+pub fn from_bufread<'a, T, R>(v: R) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+    R: std::io::BufRead,
+{
+    from_trait(read::BufIoRead::new(v))
+}
+
+/// Deserialize an instance of `T` from an iterator of bytes of Rison
+///
+/// ```
+/// let input = "(a:1,b:!(x,y))".bytes();
+/// let value: serde_json::Value = rison::from_iter(input).unwrap();
+///
+/// assert_eq!(value, serde_json::json!({"a": 1, "b": ["x", "y"]}));
+/// ```
+pub fn from_iter<'a, T, I>(v: I) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+    I: IntoIterator<Item = u8>,
+{
+    from_trait(read::IterRead::new(read::infallible(v.into_iter())))
+}
+
+/// Deserialize an instance of `T` from an iterator of bytes of Rison, each
+/// of which may instead be an IO error
+pub fn from_fallible_iter<'a, T, I>(v: I) -> Result<T>
+where
+    T: serde::de::Deserialize<'a>,
+    I: IntoIterator<Item = std::io::Result<u8>>,
+{
+    from_trait(read::IterRead::new(v.into_iter()))
+}
+
+/// Deserialize a value from a byte slice of Rison using a stateful `seed`
+///
+/// See [`from_str_seed`] for details.
+pub fn from_slice_seed<'a, S>(v: &'a [u8], seed: S) -> Result<S::Value>
+where
+    S: serde::de::DeserializeSeed<'a>,
+{
+    from_trait_seed(read::SliceRead::new(v), seed)
+}
+
+/// Deserialize a value from a string of Rison using a stateful `seed`
+///
+/// This is [`from_str`] for a [`DeserializeSeed`](serde::de::DeserializeSeed)
+/// rather than a `Deserialize` type, for deserialization which needs state of
+/// its own, such as an arena to allocate into or a schema to decode against.
+/// As with [`from_str`], trailing input is an error.
+///
+/// ```
+/// use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+/// use std::fmt;
+///
+/// // Appends the elements of a list to an existing vector
+/// struct Extend<'a>(&'a mut Vec<u32>);
+///
+/// impl<'de> DeserializeSeed<'de> for Extend<'_> {
+///     type Value = ();
+///
+///     fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+///         deserializer.deserialize_seq(self)
+///     }
+/// }
+///
+/// impl<'de> Visitor<'de> for Extend<'_> {
+///     type Value = ();
+///
+///     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         f.write_str("a list of integers")
+///     }
+///
+///     fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+///         while let Some(n) = seq.next_element()? {
+///             self.0.push(n);
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let mut all = vec![1];
+/// rison::de::from_str_seed("!(2,3)", Extend(&mut all)).unwrap();
+/// rison::de::from_str_seed("!(4)", Extend(&mut all)).unwrap();
+///
+/// assert_eq!(all, [1, 2, 3, 4]);
+/// ```
+pub fn from_str_seed<'a, S>(v: &'a str, seed: S) -> Result<S::Value>
+where
+    S: serde::de::DeserializeSeed<'a>,
+{
+    from_trait_seed(read::StrRead::new(v), seed)
+}
+
+/// Deserialize an instance of `T` from an async IO stream of Rison
+///
+/// The stream is read a chunk at a time into an internal buffer, tracking
+/// the nesting of lists and objects as it arrives, so that reading stops as
+/// soon as the value is followed by anything other than the end of the
+/// stream. As `serde` deserializes synchronously, the value itself is still
+/// buffered in full, and then parsed as by [`from_slice`]. Unlike with
+/// [`from_reader`], errors therefore carry line and column numbers even when
+/// detected on an earlier line than the one being read.
+///
+/// The length of the value is not limited. Use [`from_async_reader_with`]
+/// and [`ParseLimits::max_input_len`] to bound the memory used by untrusted
+/// streams, such as request bodies.
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader<T, R>(reader: R) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+    R: tokio::io::AsyncRead + Unpin,
+{
+    from_async_reader_with(&DeserializerBuilder::new(), reader).await
+}
+
+/// Deserialize an instance of `T` from an async IO stream of Rison, with
+/// the configuration of `builder`
+///
+/// See [`from_async_reader`]. Reading stops, failing with
+/// [`ErrorCode::InputLimitExceeded`],
+/// once the stream continues beyond [`ParseLimits::max_input_len`], so no
+/// more than that is buffered.
+///
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let builder = rison::Deserializer::builder()
+///     .limits(rison::de::ParseLimits::new().max_input_len(1024));
+/// let body: &[u8] = b"(q:'search terms',limit:20)";
+/// let value: serde_json::Value = rison::de::from_async_reader_with(&builder, body).await.unwrap();
+///
+/// assert_eq!(value, serde_json::json!({"q": "search terms", "limit": 20}));
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader_with<T, R>(builder: &DeserializerBuilder, reader: R) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let options = &builder.options;
+    let input =
+        async_read::read_value(reader, options.limits.input_len(), options.allow_whitespace)
+            .await?;
+    from_deserializer_seed(builder.from_slice(&input), std::marker::PhantomData)
+}
+
+/// Deserialize an instance of `T` from the start of a byte slice of Rison,
+/// returning it along with the offset at which parsing stopped
+///
+/// Unlike [`from_slice`], any input following the value is not an error.
+/// See [`from_str_partial`] for details.
+pub fn from_slice_partial<'a, T>(v: &'a [u8]) -> Result<(T, usize)>
+where
+    T: serde::de::Deserialize<'a>,
+{
+    from_trait_partial(read::SliceRead::new(v))
+}
+
+/// Deserialize an instance of `T` from the start of a string of Rison,
+/// returning it along with the byte offset at which parsing stopped
+///
+/// Unlike [`from_str`], any input following the value is not an error, which
+/// allows Rison to be embedded in a larger format, such as a URL query
+/// string. Numbers and unquoted strings end at the next character which may
+/// not appear in them, which for this purpose includes the `&`, `=` and `#`
+/// delimiting query parameters and fragments, so those must be quoted
+/// within strings. Lists, objects and quoted strings end at their closing
+/// character, so may be followed by anything.
+///
+/// ```
+/// let (v, end): (Vec<u32>, _) = rison::de::from_str_partial("!(1,2)&page=3").unwrap();
+/// assert_eq!((v, end), (vec![1, 2], 6));
+///
+/// let (v, end): (u32, _) = rison::de::from_str_partial("3&sort=name").unwrap();
+/// assert_eq!((v, end), (3, 1));
+/// ```
+pub fn from_str_partial<'a, T>(v: &'a str) -> Result<(T, usize)>
+where
+    T: serde::de::Deserialize<'a>,
+{
+    from_trait_partial(read::StrRead::new(v))
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn deserialize_true() {
+        let v: bool = super::from_str("!t").unwrap();
+
+        assert!(v);
+    }
+    #[test]
+    fn deserialize_false() {
+        let v: bool = super::from_str("!f").unwrap();
+
+        assert!(!v);
+    }
+    #[test]
+    fn deserialize_integer() {
+        let v: u32 = super::from_str("12").unwrap();
+
+        assert_eq!(v, 12);
+    }
+    #[test]
+    #[allow(clippy::redundant_pattern_matching)]
+    fn fail_deserialize_nonintegral_as_integer() {
+        let v: super::Result<u32> = super::from_str("12.4");
+
+        assert!(matches!(v, Err(_)));
+    }
+    #[test]
+    fn deserialize_integral_float() {
+        let v: f64 = super::from_str("12").unwrap();
+
+        assert_eq!(v, 12.0);
+    }
+    #[test]
+    fn deserialize_float() {
+        let v: f64 = super::from_str("12.4").unwrap();
+
+        assert_eq!(v, 12.4);
+    }
+    #[test]
+    fn deserialize_float_exp() {
+        let v: f64 = super::from_str("12.4e4").unwrap();
+
+        assert_eq!(v, 12.4e4);
+    }
+    #[test]
+    fn deserialize_float_neg_exp() {
+        let v: f64 = super::from_str("12.4e-4").unwrap();
+
+        assert_eq!(v, 12.4e-4);
+    }
+    #[test]
+    #[allow(clippy::redundant_pattern_matching)]
+    fn fail_deserialize_bool_trailing() {
+        let v: super::Result<bool> = super::from_str("!ff");
+
+        assert!(matches!(v, Err(_)));
+    }
+    #[test]
+    fn deserialize_quoted_empty_string() {
+        let v: String = super::from_str("''").unwrap();
+
+        assert_eq!(v, "");
+    }
+    #[test]
+    fn deserialize_quoted_string() {
+        let v: String = super::from_str("'hello, rison'").unwrap();
+
+        assert_eq!(v, "hello, rison");
+    }
+    #[test]
+    fn deserialize_quoted_string_with_escapes() {
+        let v: String = super::from_str("'hello, !'rison!'!!'").unwrap();
+
+        assert_eq!(v, "hello, 'rison'!");
+    }
+    #[test]
+    fn deserialize_ident_string() {
+        let v: String = super::from_str("hellorison").unwrap();
+
+        assert_eq!(v, "hellorison");
+    }
+    #[test]
+    fn deserialize_none() {
+        let v: Option<String> = super::from_str("!n").unwrap();
+
+        assert_eq!(v, None);
+    }
+    #[test]
+    fn deserialize_some_ident_string() {
+        let v: Option<String> = super::from_str("hellorison").unwrap();
+
+        assert_eq!(v, Some("hellorison".into()));
+    }
+    #[test]
+    fn deserialize_empty_struct() {
+        #[derive(serde::Deserialize)]
+        struct Empty {}
+        let _v: Empty = super::from_str("()").unwrap();
+    }
+    #[test]
+    fn deserialize_struct() {
+        #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+        struct Full {
+            a: String,
+            b: String,
+        }
+        let v: Full = super::from_str("(a:hello,b:world)").unwrap();
+
+        assert_eq!(
+            v,
+            Full {
+                a: "hello".into(),
+                b: "world".into()
+            }
+        );
+    }
+    #[test]
+    fn deserialize_struct_with_optional_present() {
+        #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+        struct Full {
+            a: String,
+            b: Option<String>,
+        }
+        let v: Full = super::from_str("(a:hello,b:world)").unwrap();
+
+        assert_eq!(
+            v,
+            Full {
+                a: "hello".into(),
+                b: Some("world".into())
+            }
+        );
+    }
+    #[test]
+    fn deserialize_struct_with_optional_missing() {
+        #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+        struct Full {
+            a: String,
+            b: Option<String>,
+        }
+        let v: Full = super::from_str("(a:hello)").unwrap();
+
+        assert_eq!(
+            v,
+            Full {
+                a: "hello".into(),
+                b: None
+            }
+        );
+    }
+    #[test]
+    fn deserialize_optional_markers() {
+        let v: Vec<Option<bool>> = super::from_str("!(!t,!n,!f)").unwrap();
+        assert_eq!(v, vec![Some(true), None, Some(false)]);
+
+        let v: Option<Vec<u8>> = super::from_str("!(1,2)").unwrap();
+        assert_eq!(v, Some(vec![1, 2]));
+
+        let v: Option<Vec<u8>> = super::from_reader("!()".as_bytes()).unwrap();
+        assert_eq!(v, Some(vec![]));
+
+        let err = super::from_str::<Option<bool>>("!x").unwrap_err();
+        assert_eq!(err.to_string(), "invalid marker at line 1 column 2");
+    }
+    #[test]
+    fn deserialize_empty_strings_as_none() {
+        use serde::Deserialize;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Search {
+            q: Option<String>,
+            page: Option<u32>,
+            tags: Vec<String>,
+        }
+        let builder = super::Deserializer::builder().empty_string_as_none(true);
+
+        for input in ["(q:'',page:'',tags:!(''))", "(q:!n,page:!n,tags:!(''))"] {
+            let expected = Search {
+                q: None,
+                page: None,
+                tags: vec!["".into()],
+            };
+            assert_eq!(
+                Search::deserialize(&mut builder.from_str(input)).unwrap(),
+                expected
+            );
+            let mut de = builder.from_reader(input.as_bytes());
+            assert_eq!(Search::deserialize(&mut de).unwrap(), expected);
+        }
+        let v = Search::deserialize(&mut builder.from_str("(q:'a b',page:2,tags:!())")).unwrap();
+        assert_eq!((v.q.as_deref(), v.page), (Some("a b"), Some(2)));
+
+        let v: Option<String> = super::from_str("''").unwrap();
+        assert_eq!(v.as_deref(), Some(""));
+    }
+    #[test]
+    fn deserialize_trailing_commas_when_allowed() {
+        use serde::Deserialize;
+
+        let builder = super::Deserializer::builder().allow_trailing_commas(true);
+        for (input, expected) in [
+            ("!(1,2,)", serde_json::json!([1, 2])),
+            ("(a:1,b:!(x,),)", serde_json::json!({"a": 1, "b": ["x"]})),
+            ("!(!(),())", serde_json::json!([[], {}])),
+        ] {
+            let v = serde_json::Value::deserialize(&mut builder.from_str(input)).unwrap();
+            assert_eq!(v, expected);
+            let v = serde_json::Value::deserialize(&mut builder.from_reader(input.as_bytes()));
+            assert_eq!(v.unwrap(), expected);
+            let v = serde::de::IgnoredAny::deserialize(&mut builder.from_str(input));
+            assert!(v.is_ok());
+        }
+        assert!(serde_json::Value::deserialize(&mut builder.from_str("(,)")).is_err());
+    }
+    #[test]
+    fn fail_deserialize_trailing_commas() {
+        for (input, position) in [("!(1,2,)", 5), ("(a:1,)", 4), ("!((a:!(x,)))", 8)] {
+            let err = super::from_str::<serde_json::Value>(input).unwrap_err();
+            assert!(matches!(err.code(), super::ErrorCode::TrailingComma));
+            assert_eq!(err.position(), Some(position));
+            let err = super::from_str::<serde::de::IgnoredAny>(input).unwrap_err();
+            assert!(matches!(err.code(), super::ErrorCode::TrailingComma));
+        }
+    }
+    #[test]
+    fn fail_deserialize_control_chars_when_rejected() {
+        use serde::Deserialize;
+
+        let builder = super::Deserializer::builder().reject_control_chars(true);
+        for (input, position) in [
+            ("'a\nb'", 2),
+            ("!('x!!y!'\0')", 9),
+            ("('k\x1f':1)", 3),
+            ("(a:!(1,'\r'),b:2)", 8),
+        ] {
+            let err = serde_json::Value::deserialize(&mut builder.from_str(input)).unwrap_err();
+            assert!(matches!(err.code(), super::ErrorCode::ControlCharInString));
+            assert_eq!(err.position(), Some(position));
+            let err = serde::de::IgnoredAny::deserialize(&mut builder.from_str(input)).unwrap_err();
+            assert!(matches!(err.code(), super::ErrorCode::ControlCharInString));
+        }
+
+        let v = serde_json::Value::deserialize(&mut builder.from_str("'a b\x7f'")).unwrap();
+        assert_eq!(v, serde_json::json!("a b\x7f"));
+        let v: String = super::from_str("'a\nb'").unwrap();
+        assert_eq!(v, "a\nb");
+    }
+    #[test]
+    fn fail_deserialize_malformed_numbers() {
+        for (input, position) in [
+            ("-", 1),
+            ("--5", 1),
+            ("1e", 2),
+            ("!(1e)", 4),
+            ("1.2.3", 3),
+            ("1e5e6", 3),
+            ("1e+5", 2),
+            ("1E5", 1),
+            ("01", 1),
+            ("-1.", 3),
+            ("(a:1.e5)", 5),
+        ] {
+            let err = super::from_str::<serde_json::Value>(input).unwrap_err();
+            assert!(
+                matches!(err.code(), super::ErrorCode::InvalidNumber),
+                "{}",
+                input
+            );
+            assert_eq!(err.position(), Some(position), "{}", input);
+            let err = super::from_str::<serde::de::IgnoredAny>(input).unwrap_err();
+            assert!(
+                matches!(err.code(), super::ErrorCode::InvalidNumber),
+                "{}",
+                input
+            );
+        }
+
+        let v: Vec<f64> = super::from_str("!(0,-0,0.5,-10.25e-3,1e21)").unwrap();
+        assert_eq!(v, [0.0, 0.0, 0.5, -0.01025, 1e21]);
+    }
+    #[test]
+    fn fail_deserialize_ids_starting_like_numbers() {
+        for (input, position) in [("1x", 0), ("-a", 0), ("!(1,2abc)", 4), ("(a:1e5x)", 3)] {
+            let err = super::from_str::<serde_json::Value>(input).unwrap_err();
+            assert!(matches!(err.code(), super::ErrorCode::InvalidIdStart));
+            assert_eq!(err.position(), Some(position));
+            let err = super::from_str::<serde::de::IgnoredAny>(input).unwrap_err();
+            assert!(matches!(err.code(), super::ErrorCode::InvalidIdStart));
+        }
+
+        let err = super::from_str::<String>("1x").unwrap_err();
+        assert!(matches!(err.code(), super::ErrorCode::InvalidIdStart));
+        let v: Vec<String> = super::from_str("!('1x','-a')").unwrap();
+        assert_eq!(v, ["1x", "-a"]);
+        let v: std::collections::BTreeMap<String, u8> = super::from_str("(1x:1,-a:2)").unwrap();
+        assert_eq!(v.keys().collect::<Vec<_>>(), ["-a", "1x"]);
+    }
+    #[test]
+    fn deserialize_unit_and_newtype_structs() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Unit;
+        #[derive(serde::Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct UserId(String);
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Count(u32);
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Wrapper(Option<Vec<Count>>);
+
+        assert_eq!(super::from_str::<Unit>("!n").unwrap(), Unit);
+        assert_eq!(
+            super::from_str::<UserId>("u1").unwrap(),
+            UserId("u1".into())
+        );
+        assert_eq!(super::from_str::<Count>("12").unwrap(), Count(12));
+        assert_eq!(
+            super::from_str::<Wrapper>("!(1,2)").unwrap(),
+            Wrapper(Some(vec![Count(1), Count(2)]))
+        );
+        assert_eq!(super::from_str::<Wrapper>("!n").unwrap(), Wrapper(None));
+        let v: std::collections::BTreeMap<UserId, Option<UserId>> =
+            super::from_str("(a:b,c:!n)").unwrap();
+        assert_eq!(v[&UserId("a".into())], Some(UserId("b".into())));
+
+        // With `arbitrary_precision`, numbers are provided to string visitors
+        #[cfg(not(feature = "arbitrary_precision"))]
+        {
+            let err = super::from_str::<UserId>("12").unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "invalid type: integer `12`, expected a string at line 1 column 1"
+            );
+        }
+        let err = super::from_str::<Unit>("()").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid type: map, expected unit struct Unit at line 1 column 1"
+        );
+    }
+    #[test]
+    fn fail_deserialize_tuples_of_wrong_length() {
+        #[derive(serde::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Point(u8, u8);
+
+        // With the `path` feature, messages are prefixed by the path
+        let check = |result: super::Result<()>, message: &str, position| {
+            let err = result.unwrap_err();
+            assert!(err.to_string().ends_with(message), "{}", err);
+            assert_eq!(err.position(), Some(position));
+        };
+        check(
+            super::from_str::<(u8, u8)>("!(1,2,3,!(4))").map(drop),
+            "invalid length 4, expected a list of length 2 at line 1 column 1",
+            0,
+        );
+        check(
+            super::from_str::<(u8, u8)>("!(1)").map(drop),
+            "invalid length 1, expected a tuple of size 2 at line 1 column 1",
+            0,
+        );
+        check(
+            super::from_str::<Vec<[u8; 1]>>("!(!(1),!(2,3))").map(drop),
+            "invalid length 2, expected a list of length 1 at line 1 column 8",
+            7,
+        );
+        check(
+            super::from_str::<Option<Point>>("!(1,2,3)").map(drop),
+            "invalid length 3, expected tuple struct Point with 2 elements at line 1 column 1",
+            0,
+        );
+        check(
+            super::from_str::<(u8, u8)>("!(1,2,3").map(drop),
+            "unclosed list started at position 0 at line 1 column 8",
+            7,
+        );
+
+        let v: Option<(u8, u8)> = super::from_str("!(1,2)").unwrap();
+        assert_eq!(v, Some((1, 2)));
+        let builder = super::Deserializer::builder().allow_trailing_commas(true);
+        let v: (u8, u8) =
+            serde::Deserialize::deserialize(&mut builder.from_str("!(1,2,)")).unwrap();
+        assert_eq!(v, (1, 2));
+    }
+    #[test]
+    fn deserialize_small_strings() {
+        use compact_str::CompactString;
+
+        let input = "!(a,'b c','d!'e')";
+        let expected = ["a", "b c", "d'e"];
+        let v: Vec<CompactString> = super::from_str(input).unwrap();
+        assert_eq!(v, expected);
+        let v: Vec<CompactString> = super::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(v, expected);
+        assert!(v.iter().all(|s| !s.is_heap_allocated()));
+    }
+    #[test]
+    fn deserialize_map() {
+        let v: std::collections::HashMap<String, String> =
+            super::from_str("(a:hello,b:world)").unwrap();
+
+        let expected = vec![("a".into(), "hello".into()), ("b".into(), "world".into())]
+            .into_iter()
+            .collect();
+        assert_eq!(v, expected);
+    }
+    #[test]
+    fn deserialize_tuple() {
+        let v: (String, String) = super::from_str("!(hello,world)").unwrap();
+
+        assert_eq!(v, ("hello".into(), "world".into()));
+    }
+    #[test]
+    fn deserialize_value_string() {
+        let v: serde_json::Value = super::from_str("helloworld").unwrap();
+
+        assert_eq!(v, serde_json::Value::String("helloworld".into()));
+    }
+    #[test]
+    fn deserialize_value_map() {
+        let v: serde_json::Value = super::from_str("(hello:!(a,b,c),world:'it works')").unwrap();
+
+        assert_eq!(
+            v,
+            serde_json::json!({"hello": ["a", "b", "c"], "world": "it works"})
+        );
+    }
+    #[test]
+    fn deserialize_value_map_from_io() {
+        let v: serde_json::Value =
+            super::from_reader(b"(hello:!(a,b,c),world:'it works')" as &[_]).unwrap();
+
+        assert_eq!(
+            v,
+            serde_json::json!({"hello": ["a", "b", "c"], "world": "it works"})
+        );
+    }
+    #[test]
+    fn fail_deserialize_beyond_recursion_limit() {
+        let input = "!(".repeat(10_000);
+        let v: super::Result<serde_json::Value> = super::from_str(&input);
+
+        assert!(matches!(
+            v.unwrap_err().code(),
+            super::ErrorCode::RecursionLimitExceeded
+        ));
+    }
+    #[test]
+    fn deserialize_within_custom_recursion_limit() {
+        use serde::Deserialize;
+
+        let mut de = super::Deserializer::from_str("!(!(!()))");
+        de.set_recursion_limit(3);
+        let v = serde_json::Value::deserialize(&mut de).unwrap();
+
+        assert_eq!(v, serde_json::json!([[[]]]));
+
+        let mut de = super::Deserializer::from_str("!(!(!()))");
+        de.set_recursion_limit(2);
+        let v = serde_json::Value::deserialize(&mut de);
+
+        assert!(v.is_err());
+    }
+    #[test]
+    fn deserialize_with_builder_options() {
+        use serde::Deserialize;
+
+        let builder = super::Deserializer::builder().recursion_limit(1);
+
+        let mut de = builder.from_str("!(a,b)");
+        let v = serde_json::Value::deserialize(&mut de).unwrap();
+        assert_eq!(v, serde_json::json!(["a", "b"]));
+
+        let mut de = builder.from_slice(b"!(!())");
+        assert!(serde_json::Value::deserialize(&mut de).is_err());
+
+        let mut de = builder.from_reader(b"(a:(b:c))" as &[_]);
+        assert!(serde_json::Value::deserialize(&mut de).is_err());
+    }
+    #[test]
+    fn fail_deserialize_beyond_parse_limits() {
+        use serde::Deserialize;
+
+        fn code_with(limits: super::ParseLimits, input: &str) -> Option<super::ErrorCode> {
+            let builder = super::Deserializer::builder().limits(limits);
+
+            let slice = serde_json::Value::deserialize(&mut builder.from_str(input));
+            let io = serde_json::Value::deserialize(&mut builder.from_reader(input.as_bytes()));
+            assert_eq!(slice.is_ok(), io.is_ok(), "{}", input);
+            slice.err().map(|e| e.code)
+        }
+        let limits = super::ParseLimits::new()
+            .max_input_len(16)
+            .max_string_len(4)
+            .max_collection_len(3);
+
+        assert!(code_with(limits, "(a:!(1,2,3),bcd:e)").is_some());
+        assert!(code_with(limits, "(a:!(1,2,3),b:e)").is_none());
+        assert!(matches!(
+            code_with(limits, "!(a,b,c,d)"),
+            Some(super::ErrorCode::CollectionLimitExceeded)
+        ));
+        assert!(matches!(
+            code_with(limits, "(a:1,b:2,c:3,d:4)"),
+            Some(super::ErrorCode::CollectionLimitExceeded)
+        ));
+        assert!(matches!(
+            code_with(limits, "'abcde'"),
+            Some(super::ErrorCode::StringLimitExceeded)
+        ));
+        assert!(matches!(
+            code_with(limits, "(abcde:1)"),
+            Some(super::ErrorCode::StringLimitExceeded)
+        ));
+        assert!(matches!(
+            code_with(limits, "!(1111111111,22222222)"),
+            Some(super::ErrorCode::InputLimitExceeded)
+        ));
+
+        #[derive(serde::Deserialize, Debug)]
+        struct Known {}
+        let builder = super::Deserializer::builder().limits(limits);
+        let err = Known::deserialize(&mut builder.from_str("(x:!(1,2,3,4))")).unwrap_err();
+        assert!(matches!(
+            err.code,
+            super::ErrorCode::CollectionLimitExceeded
+        ));
+    }
+    #[test]
+    fn fail_deserialize_unexpected_type() {
+        #[derive(serde::Deserialize, Debug)]
+        struct Known {
+            #[allow(dead_code)]
+            a: u32,
+        }
+        fn message_with(input: &str) -> (String, Option<usize>) {
+            let err = super::from_str::<Known>(input).unwrap_err();
+            (err.code.to_string(), err.position)
+        }
+
+        assert_eq!(
+            message_with("(a:'it!'s')"),
+            ("invalid type: string 'it!'s', expected u32".into(), Some(3))
+        );
+        assert_eq!(
+            message_with("(a:!t)"),
+            ("invalid type: boolean `!t`, expected u32".into(), Some(3))
+        );
+        assert_eq!(
+            message_with("(a:-1)"),
+            ("invalid value: integer `-1`, expected u32".into(), Some(3))
+        );
+        assert_eq!(message_with("(b:1)"), ("missing field `a`".into(), Some(0)));
+    }
+    #[cfg(feature = "path")]
+    #[test]
+    fn fail_deserialize_with_path() {
+        use std::collections::BTreeMap;
+
+        type Nested = BTreeMap<String, BTreeMap<String, BTreeMap<String, Vec<i32>>>>;
+
+        let err = super::from_str::<Nested>("(a:(b:(c:!(1,x))))").unwrap_err();
+        assert_eq!(err.path().unwrap().to_string(), "a.b.c[1]");
+        assert!(err.to_string().starts_with("a.b.c[1]: invalid type"));
+
+        let err = super::from_str::<Vec<Nested>>("!((a:(b:(c:1))))").unwrap_err();
+        assert_eq!(err.path().unwrap().to_string(), "[0].a.b.c");
+
+        let err = super::from_str::<Nested>("(a:(b:(c:!(1,2)),'d e':(f:!(1,2,3,x))))").unwrap_err();
+        assert_eq!(err.path().unwrap().to_string(), "a.d e.f[3]");
+
+        let err = super::from_str::<Vec<i32>>("x").unwrap_err();
+        assert!(err.path().is_none());
+    }
+    #[test]
+    fn deserialize_untagged_enums() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        #[serde(untagged)]
+        enum Query {
+            Text(String),
+            Range { gte: i64, lt: Option<i64> },
+            Terms(Vec<Query>),
+            Flag(bool),
+            Number(f64),
+            Missing(()),
+        }
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        #[serde(untagged)]
+        enum Id {
+            Unsigned(u64),
+            Signed(i64),
+            Text(String),
+        }
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Filter {
+            query: Query,
+            id: Option<Id>,
+        }
+
+        for (input, expected) in [
+            (
+                "(query:x,id:12)",
+                Filter {
+                    query: Query::Text("x".into()),
+                    id: Some(Id::Unsigned(12)),
+                },
+            ),
+            (
+                "(query:(gte:1,lt:!n),id:-3)",
+                Filter {
+                    query: Query::Range { gte: 1, lt: None },
+                    id: Some(Id::Signed(-3)),
+                },
+            ),
+            (
+                "(query:!('it!'s',(gte:-1,lt:2),!t,1.5,!n),id:'12')",
+                Filter {
+                    query: Query::Terms(vec![
+                        Query::Text("it's".into()),
+                        Query::Range {
+                            gte: -1,
+                            lt: Some(2),
+                        },
+                        Query::Flag(true),
+                        Query::Number(1.5),
+                        Query::Missing(()),
+                    ]),
+                    id: Some(Id::Text("12".into())),
+                },
+            ),
+            (
+                "(query:3,id:!n)",
+                Filter {
+                    query: Query::Number(3.0),
+                    id: None,
+                },
+            ),
+        ] {
+            assert_eq!(super::from_str::<Filter>(input).unwrap(), expected);
+            assert_eq!(
+                super::from_reader::<Filter, _>(input.as_bytes()).unwrap(),
+                expected
+            );
+        }
 
-    de.end()?;
+        let err = super::from_str::<Filter>("(query:x,id:!(1))").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("data did not match any variant of untagged enum Id"));
+        assert_eq!((err.line(), err.column()), (Some(1), Some(13)));
+    }
+    #[test]
+    fn deserialize_enum_representations() {
+        macro_rules! enum_with_representation {
+            ($name:ident $(, $($attr:tt)*)?) => {
+                #[derive(Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+                $(#[serde($($attr)*)])?
+                enum $name {
+                    Unit,
+                    Newtype(Inner),
+                    Struct { a: u8, b: Option<bool> },
+                }
+            };
+        }
+        #[derive(Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+        struct Inner {
+            x: String,
+        }
+        enum_with_representation!(External);
+        enum_with_representation!(Internal, tag = "type");
+        enum_with_representation!(Adjacent, tag = "t", content = "c");
+        enum_with_representation!(Untagged, untagged);
+
+        fn check<T>(input: &str, expected: T)
+        where
+            T: std::fmt::Debug + PartialEq + serde::Serialize + serde::de::DeserializeOwned,
+        {
+            assert_eq!(super::from_str::<T>(input).unwrap(), expected);
+            assert_eq!(
+                super::from_reader::<T, _>(input.as_bytes()).unwrap(),
+                expected
+            );
+            assert_eq!(crate::to_string(&expected).unwrap(), input);
+        }
 
-    Ok(value)
-}
+        check("Unit", External::Unit);
+        check(
+            "(Newtype:(x:y))",
+            External::Newtype(Inner { x: "y".into() }),
+        );
+        check(
+            "(Struct:(a:1,b:!t))",
+            External::Struct {
+                a: 1,
+                b: Some(true),
+            },
+        );
+        check("(type:Unit)", Internal::Unit);
+        check(
+            "(type:Newtype,x:y)",
+            Internal::Newtype(Inner { x: "y".into() }),
+        );
+        check("(type:Struct,a:1,b:!n)", Internal::Struct { a: 1, b: None });
+        check("(t:Unit)", Adjacent::Unit);
+        check(
+            "(t:Newtype,c:(x:y))",
+            Adjacent::Newtype(Inner { x: "y".into() }),
+        );
+        check(
+            "(t:Struct,c:(a:1,b:!f))",
+            Adjacent::Struct {
+                a: 1,
+                b: Some(false),
+            },
+        );
+        check("!n", Untagged::Unit);
+        check("(x:y)", Untagged::Newtype(Inner { x: "y".into() }));
+        check(
+            "(a:1,b:!t)",
+            Untagged::Struct {
+                a: 1,
+                b: Some(true),
+            },
+        );
 
-/// Deserialize an instance of `T` from a byte slice of Rison
-pub fn from_slice<'a, T>(v: &'a [u8]) -> Result<T>
-where
-    T: serde::de::Deserialize<'a>,
-{
-    from_trait(read::SliceRead::new(v))
-}
+        // Tags need not come first, and may be quoted
+        assert_eq!(
+            super::from_str::<Internal>("(a:2,'type':Struct)").unwrap(),
+            Internal::Struct { a: 2, b: None }
+        );
+        assert_eq!(
+            super::from_str::<Adjacent>("(c:(x:'a b'),t:'Newtype')").unwrap(),
+            Adjacent::Newtype(Inner { x: "a b".into() })
+        );
+        assert_eq!(
+            super::from_str::<Vec<External>>("!('Unit',(Unit:!n))").unwrap(),
+            vec![External::Unit, External::Unit]
+        );
+        let v: std::collections::HashMap<External, u8> = super::from_str("(Unit:1)").unwrap();
+        assert_eq!(v.get(&External::Unit), Some(&1));
+    }
+    #[test]
+    fn report_start_of_unclosed_collections() {
+        use serde::Deserialize;
 
-/// Deserialize an instance of `T` from a string of Rison
-pub fn from_str<'a, T>(v: &'a str) -> Result<T>
-where
-    T: serde::de::Deserialize<'a>,
-{
-    from_trait(read::StrRead::new(v))
-}
+        use crate::error::{Error, ErrorCode};
+        use crate::Value;
 
-/// Deserialize an instance of `T` from an IO stream of Rison
-pub fn from_reader<'a, T, I>(v: I) -> Result<T>
-where
-    T: serde::de::Deserialize<'a>,
-    I: std::io::Read,
-{
-    from_trait(read::IoRead::new(v))
-}
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct S {
+            a: Vec<u8>,
+        }
 
-#[cfg(test)]
-mod test {
+        let code = |err: Error| match err.code() {
+            ErrorCode::UnclosedList(start) => (true, *start, err.position().unwrap()),
+            ErrorCode::UnclosedObject(start) => (false, *start, err.position().unwrap()),
+            code => panic!("{code}"),
+        };
+        let err = super::from_str::<Value>("(a:!(1,(b:x)),c:(d:!(").unwrap_err();
+        assert_eq!(code(err), (true, 19, 21));
+        let err = super::from_str::<S>("(a:!(1,2)").unwrap_err();
+        assert_eq!(code(err), (false, 0, 9));
+        let err = super::from_str::<Option<(u8, u8)>>("!(1,2").unwrap_err();
+        assert_eq!(code(err), (true, 0, 5));
+        let err = super::from_str::<S>("(z:(y:!(),a:!()").unwrap_err();
+        assert_eq!(code(err), (false, 3, 15));
+
+        let builder = super::Deserializer::builder().allow_whitespace(true);
+        let err = Value::deserialize(&mut builder.from_str(" !( 1 , ( ")).unwrap_err();
+        assert_eq!(code(err), (false, 8, 10));
+    }
     #[test]
-    fn deserialize_true() {
-        let v: bool = super::from_str("!t").unwrap();
+    fn fail_deserialize_enums() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        enum E {
+            Unit,
+            Newtype(u8),
+        }
 
-        assert!(v);
+        for (input, message, position) in [
+            ("(Newtype:1,Unit:!n)", "expected `)`", 10),
+            ("(Newtype:1", "unclosed object started at position 0", 10),
+            ("(Newtype(1))", "expected `:`", 8),
+            (
+                "Newtype",
+                "invalid type: unit variant, expected newtype variant",
+                0,
+            ),
+            ("(Unit:1)", "invalid type: integer `1`, expected unit", 6),
+            ("", "EoF while parsing a value", 0),
+        ] {
+            let err = super::from_str::<E>(input).unwrap_err();
+            assert_eq!(
+                (err.code().to_string(), err.position()),
+                (message.to_string(), Some(position)),
+                "{input}"
+            );
+        }
     }
     #[test]
-    fn deserialize_false() {
-        let v: bool = super::from_str("!f").unwrap();
+    fn locate_visitor_errors() {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        #[allow(dead_code)]
+        struct S {
+            a: u8,
+            e: Option<E>,
+        }
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize)]
+        enum E {
+            A,
+            B(u8),
+        }
 
-        assert!(!v);
+        fn position_of<T: serde::de::DeserializeOwned>(input: &str) -> Option<usize> {
+            super::from_str::<T>(input).err().unwrap().position()
+        }
+        assert_eq!(position_of::<S>("(a:1,zz:1)"), Some(5));
+        assert_eq!(position_of::<S>("(a:1,e:(C:1))"), Some(8));
+        assert_eq!(position_of::<S>("(a:1,e:(B:x))"), Some(10));
+        assert_eq!(position_of::<S>("(e:C,a:1)"), Some(3));
+        assert_eq!(
+            position_of::<std::collections::BTreeMap<E, u8>>("(A:1,C:2)"),
+            Some(5)
+        );
+        assert_eq!(
+            position_of::<std::collections::BTreeMap<u8, u8>>("(1:1,300:2)"),
+            Some(5)
+        );
+        assert_eq!(position_of::<std::net::IpAddr>("x"), Some(0));
     }
     #[test]
-    fn deserialize_integer() {
-        let v: u32 = super::from_str("12").unwrap();
+    fn hint_json_mistakes() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct S {
+            a: Option<bool>,
+            b: Vec<u8>,
+        }
 
-        assert_eq!(v, 12);
+        fn hint_for<T: serde::de::DeserializeOwned>(input: &str) -> Option<&'static str> {
+            super::from_str::<T>(input).err().unwrap().hint()
+        }
+        let object = hint_for::<S>(r#"{"a":!t,"b":!()}"#).unwrap();
+        assert!(object.contains("parentheses"), "{object}");
+        let object = hint_for::<serde_json::Value>(r#"{"a":1}"#).unwrap();
+        assert!(object.contains("parentheses"), "{object}");
+        let list = hint_for::<S>("(b:[1,2])").unwrap();
+        assert!(list.contains("!(1,2)"), "{list}");
+        let string = hint_for::<serde_json::Value>(r#"(a:"x y")"#).unwrap();
+        assert!(string.contains("quotes"), "{string}");
+        let boolean = hint_for::<S>("(a:true,b:!())").unwrap();
+        assert!(boolean.contains("!t"), "{boolean}");
+        let null = hint_for::<S>("(a:null,b:!())").unwrap();
+        assert!(null.contains("!n"), "{null}");
+
+        assert_eq!(hint_for::<S>("(a:!t,b:x)"), None);
+        assert_eq!(hint_for::<S>("(a:!t)"), None);
     }
     #[test]
-    fn fail_deserialize_nonintegral_as_integer() {
-        let v: super::Result<u32> = super::from_str("12.4");
+    fn locate_errors_in_streams() {
+        let input = "!('a\nb',x)";
 
-        assert!(matches!(v, Err(_)));
+        let err = super::from_reader::<(String, u8), _>(input.as_bytes()).unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(2), Some(4)));
+        let err = super::from_str::<(String, u8)>(input).unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(2), Some(4)));
+
+        // Earlier lines have been forgotten by the time the string fails
+        let err = super::from_reader::<Vec<u8>, _>("!(1,'a\nb')".as_bytes()).unwrap_err();
+        assert_eq!((err.position(), err.line()), (Some(4), None));
     }
     #[test]
-    fn deserialize_integral_float() {
-        let v: f64 = super::from_str("12").unwrap();
-
-        assert_eq!(v, 12.0);
+    fn locate_errors_in_characters() {
+        let input = "!('日本','a\né',ü,x)";
+
+        let err = super::from_str::<(String, String, String, u8)>(input).unwrap_err();
+        assert_eq!((err.position(), err.column()), (Some(21), Some(8)));
+        assert_eq!(err.char_position(input), Some(15));
+        assert_eq!(err.char_column(input), Some(6));
+
+        // The input limit may be crossed within a character, which the
+        // error is then reported at
+        let input = "!('éé',x)";
+        let builder =
+            super::Deserializer::builder().limits(super::ParseLimits::new().max_input_len(4));
+        let err =
+            <(String, String) as serde::Deserialize>::deserialize(&mut builder.from_str(input))
+                .unwrap_err();
+        assert_eq!(err.position(), Some(4));
+        assert_eq!(err.char_position(input), Some(3));
+        assert_eq!(err.char_column(input), Some(4));
+
+        let err = super::from_str::<u8>("x").unwrap_err();
+        assert_eq!(err.char_position(""), Some(0));
+        assert_eq!(err.char_column(""), Some(1));
     }
     #[test]
-    fn deserialize_float() {
-        let v: f64 = super::from_str("12.4").unwrap();
+    fn deserialize_flattened_struct() {
+        use std::collections::HashMap;
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Inner {
+            x: u32,
+            y: Option<String>,
+            z: bool,
+        }
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Outer {
+            a: i8,
+            #[serde(flatten)]
+            inner: Inner,
+            #[serde(flatten)]
+            extra: HashMap<String, crate::Value>,
+        }
 
-        assert_eq!(v, 12.4);
+        let v: Outer = super::from_str("(a:-1,x:2,y:!n,z:!t,q:(r:!(1,'s')),n:-1.5)").unwrap();
+        assert_eq!(
+            v,
+            Outer {
+                a: -1,
+                inner: Inner {
+                    x: 2,
+                    y: None,
+                    z: true
+                },
+                extra: [
+                    ("q".into(), crate::from_str("(r:!(1,'s'))").unwrap()),
+                    ("n".into(), crate::from_str("-1.5").unwrap()),
+                ]
+                .into_iter()
+                .collect(),
+            }
+        );
+
+        let v: Outer = super::from_str("(x:1,y:'x y',z:!f,a:3)").unwrap();
+        assert_eq!(v.inner.y.as_deref(), Some("x y"));
+        assert!(v.extra.is_empty());
+
+        let v: super::Result<Outer> = super::from_str("(a:1,x:2,z:maybe)");
+        assert!(v.is_err());
     }
     #[test]
-    fn deserialize_float_exp() {
-        let v: f64 = super::from_str("12.4e4").unwrap();
+    fn deserialize_partial_object() {
+        let input = "(a:hello)&other=1";
+        let (v, end): (serde_json::Value, _) = super::from_str_partial(input).unwrap();
 
-        assert_eq!(v, 12.4e4);
+        assert_eq!(v, serde_json::json!({"a": "hello"}));
+        assert_eq!(&input[end..], "&other=1");
     }
     #[test]
-    fn deserialize_float_neg_exp() {
-        let v: f64 = super::from_str("12.4e-4").unwrap();
+    fn deserialize_partial_ident() {
+        let (v, end): (String, _) = super::from_slice_partial(b"hello,world").unwrap();
 
-        assert_eq!(v, 12.4e-4);
+        assert_eq!(v, "hello");
+        assert_eq!(end, 5);
     }
     #[test]
-    fn fail_deserialize_bool_trailing() {
-        let v: super::Result<bool> = super::from_str("!ff");
+    fn deserialize_partial_values_before_query_params() {
+        let (v, end): (u32, _) = super::from_str_partial("12&other=x").unwrap();
+        assert_eq!((v, end), (12, 2));
+        let (v, end): (f64, _) = super::from_str_partial("-1.5#top").unwrap();
+        assert_eq!((v, end), (-1.5, 4));
+        let (v, end): (String, _) = super::from_str_partial("abc&other=x").unwrap();
+        assert_eq!((v.as_str(), end), ("abc", 3));
+        let (v, end): (String, _) = super::from_str_partial("'a&b=c'&other=x").unwrap();
+        assert_eq!((v.as_str(), end), ("a&b=c", 7));
+        let (v, end): (Vec<String>, _) = super::from_slice_partial(b"!(a,b)&other=x").unwrap();
+        assert_eq!((v, end), (vec!["a".to_owned(), "b".to_owned()], 6));
+
+        // Delimiters end unquoted strings within lists too
+        assert!(super::from_str_partial::<Vec<String>>("!(a&b)").is_err());
+        assert!(super::from_str::<Vec<String>>("!(a&b)").is_ok());
+    }
+    #[test]
+    fn deserialize_seed() {
+        let seed = std::marker::PhantomData::<Vec<u32>>;
+        let v = super::from_slice_seed(b"!(1,2)", seed).unwrap();
 
-        assert!(matches!(v, Err(_)));
+        assert_eq!(v, [1, 2]);
+        assert!(super::from_str_seed("!(1,2)&page=3", seed).is_err());
     }
     #[test]
-    fn deserialize_quoted_empty_string() {
-        let v: String = super::from_str("''").unwrap();
+    fn fail_deserialize_reports_line_and_column() {
+        let v: super::Result<serde_json::Value> = super::from_str("(a:1,\nb:2,\nc)");
+        let err = v.unwrap_err();
 
-        assert_eq!(v, "");
+        assert_eq!(err.position(), Some(12));
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.column(), Some(2));
     }
     #[test]
-    fn deserialize_quoted_string() {
-        let v: String = super::from_str("'hello, rison'").unwrap();
+    fn fail_deserialize_snippet_is_cropped() {
+        let source = format!("(a:'{}',b)", "x".repeat(100));
+        let err = super::from_str::<serde_json::Value>(&source).unwrap_err();
+
+        let expected = format!(
+            "expected `:` at line 1 column 108\n  |\n1 | ...{}',b)\n  | {}^",
+            "x".repeat(37),
+            " ".repeat(43)
+        );
+        assert_eq!(err.display_with_source(&source).to_string(), expected);
+    }
+    #[test]
+    fn fail_deserialize_snippet_within_multibyte_chars() {
+        let source = "'a!é'";
+        let err = super::from_str::<serde_json::Value>(source).unwrap_err();
+        assert_eq!(err.position(), Some(2));
+        let err = super::from_reader::<serde_json::Value, _>(source.as_bytes()).unwrap_err();
+        assert_eq!(err.position(), Some(2));
+        let mut bytes = source.as_bytes().to_vec();
+        let err = super::from_bytes_mut::<serde_json::Value>(&mut bytes).unwrap_err();
+        assert_eq!(err.position(), Some(2));
+        assert_eq!(
+            err.display_with_source(source).to_string(),
+            "invalid escape at line 1 column 3\n  |\n1 | 'a!é'\n  |   ^"
+        );
 
-        assert_eq!(v, "hello, rison");
+        // The input limit may be crossed within a character
+        let source = "!('éé',x)";
+        let builder =
+            super::Deserializer::builder().limits(super::ParseLimits::new().max_input_len(4));
+        let err = serde::Deserialize::deserialize(&mut builder.from_str(source))
+            .map(|_: serde_json::Value| ())
+            .unwrap_err();
+        assert_eq!(err.position(), Some(4));
+        assert_eq!(
+            err.display_with_source(source).to_string(),
+            "input length limit exceeded at line 1 column 4\n  |\n1 | !('éé',x)\n  |    ^"
+        );
     }
     #[test]
-    fn deserialize_quoted_string_with_escapes() {
-        let v: String = super::from_str("'hello, !'rison!'!!'").unwrap();
+    fn fail_deserialize_reports_error_code() {
+        let v: super::Result<serde_json::Value> = super::from_str("'hello!x'");
 
-        assert_eq!(v, "hello, 'rison'!");
+        assert!(matches!(
+            v.unwrap_err().code(),
+            super::ErrorCode::InvalidEscape
+        ));
     }
     #[test]
-    fn deserialize_ident_string() {
-        let v: String = super::from_str("hellorison").unwrap();
+    fn deserialize_bytes_from_base64() {
+        use serde::Deserialize;
+
+        struct Bytes(Vec<u8>);
+        impl<'de> Deserialize<'de> for Bytes {
+            fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                struct V;
+                impl serde::de::Visitor<'_> for V {
+                    type Value = Vec<u8>;
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("bytes")
+                    }
+                    fn visit_bytes<E>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+                        Ok(v.to_vec())
+                    }
+                }
+                d.deserialize_bytes(V).map(Bytes)
+            }
+        }
 
-        assert_eq!(v, "hellorison");
+        let v: Bytes = super::from_str("'-3Jpc29u_w'").unwrap();
+        assert_eq!(v.0, b"\xfbrison\xff");
+
+        let v: Bytes = super::from_str("cmlzb24=").unwrap();
+        assert_eq!(v.0, b"rison");
+
+        let v: super::Result<Bytes> = super::from_str("'not base64'");
+        assert!(matches!(
+            v.err().unwrap().code(),
+            super::ErrorCode::InvalidBase64
+        ));
     }
     #[test]
-    fn deserialize_none() {
-        let v: Option<String> = super::from_str("!n").unwrap();
+    fn deserialize_char() {
+        let v: Vec<char> = super::from_str("!(a,'b','!!','!'',' ','\u{e9}')").unwrap();
+        assert_eq!(v, ['a', 'b', '!', '\'', ' ', '\u{e9}']);
+
+        for input in ["ab", "''", "'xy'"] {
+            let err = super::from_str::<char>(input).unwrap_err();
+            assert!(
+                matches!(err.code(), super::ErrorCode::InvalidChar),
+                "{}",
+                input
+            );
+            assert_eq!(err.position(), Some(0));
+        }
+    }
+    #[test]
+    fn deserialize_numeric_keys() {
+        use std::collections::BTreeMap;
 
-        assert_eq!(v, None);
+        let v: BTreeMap<u64, String> =
+            super::from_str("(1:x,'2':y,18446744073709551615:z)").unwrap();
+        let expected = [(1, "x"), (2, "y"), (u64::MAX, "z")]
+            .into_iter()
+            .map(|(k, v)| (k, v.to_string()))
+            .collect();
+        assert_eq!(v, expected);
+
+        let v: BTreeMap<i8, f64> = super::from_str("(-1:1.5)").unwrap();
+        assert_eq!(v, [(-1, 1.5)].into_iter().collect());
+
+        let v: super::Result<BTreeMap<u8, String>> = super::from_str("(256:x)");
+        assert!(matches!(
+            v.unwrap_err().code(),
+            super::ErrorCode::InvalidNumber
+        ));
     }
     #[test]
-    fn deserialize_some_ident_string() {
-        let v: Option<String> = super::from_str("hellorison").unwrap();
+    fn deserialize_bool_keys() {
+        use std::collections::BTreeMap;
 
-        assert_eq!(v, Some("hellorison".into()));
+        let v: BTreeMap<bool, u32> = super::from_str("(!t:1,false:0)").unwrap();
+
+        assert_eq!(v, [(true, 1), (false, 0)].into_iter().collect());
     }
     #[test]
-    fn deserialize_empty_struct() {
-        #[derive(serde::Deserialize)]
-        struct Empty {}
-        let _v: Empty = super::from_str("()").unwrap();
+    fn deserialize_numeric_looking_string_keys() {
+        let v: std::collections::HashMap<String, String> = super::from_str("(1:x,-2.5:y)").unwrap();
+
+        let expected = [("1".into(), "x".into()), ("-2.5".into(), "y".into())]
+            .into_iter()
+            .collect();
+        assert_eq!(v, expected);
     }
     #[test]
-    fn deserialize_struct() {
+    fn deserialize_struct_with_numeric_looking_fields() {
         #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
-        struct Full {
-            a: String,
-            b: String,
+        struct Fields {
+            #[serde(rename = "1")]
+            one: String,
+            #[serde(rename = "-a")]
+            minus_a: String,
+            #[serde(rename = "b c")]
+            b_c: String,
         }
-        let v: Full = super::from_str("(a:hello,b:world)").unwrap();
+        let v: Fields = super::from_str("(1:x,-a:y,'b c':z)").unwrap();
 
         assert_eq!(
             v,
-            Full {
-                a: "hello".into(),
-                b: "world".into()
+            Fields {
+                one: "x".into(),
+                minus_a: "y".into(),
+                b_c: "z".into()
             }
         );
     }
     #[test]
-    fn deserialize_struct_with_optional_present() {
+    fn deserialize_struct_ignoring_unknown_fields() {
         #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
-        struct Full {
-            a: String,
-            b: Option<String>,
+        struct Known {
+            a: u32,
         }
-        let v: Full = super::from_str("(a:hello,b:world)").unwrap();
+        let input = "(x:(y:!(1,'two!'s',!t,!n,(z:-1.5e3))),a:1,'w w':zz,v:())";
 
+        let v: Known = super::from_str(input).unwrap();
+        assert_eq!(v, Known { a: 1 });
+
+        let v: Known = super::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(v, Known { a: 1 });
+    }
+    #[test]
+    fn fail_deserialize_malformed_ignored_field() {
+        #[derive(serde::Deserialize, Debug)]
+        struct Known {
+            #[allow(dead_code)]
+            a: u32,
+        }
+
+        for input in [
+            "(x:!(1 2),a:1)",
+            "(x:(y 1),a:1)",
+            "(x:'!x',a:1)",
+            "(x:!z,a:1)",
+            "(x:!(a",
+        ] {
+            let v: super::Result<Known> = super::from_str(input);
+            assert!(v.is_err(), "{}", input);
+        }
+    }
+    #[test]
+    fn deserialize_with_whitespace() {
+        use serde::Deserialize;
+
+        let input = " ( a : !( 1 ,\n\t'x y' ) ,\r\nb:c , e:(f:!t) ) ";
+        let builder = super::Deserializer::builder().allow_whitespace(true);
+
+        let mut de = builder.from_str(input);
+        let v = serde_json::Value::deserialize(&mut de).unwrap();
+        de.end().unwrap();
         assert_eq!(
             v,
-            Full {
-                a: "hello".into(),
-                b: Some("world".into())
-            }
+            serde_json::json!({"a": [1, "x y"], "b": "c", "e": {"f": true}})
         );
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Known {
+            a: u32,
+        }
+        let mut de = builder.from_reader(b"( x : ( y : !( 1 , 2 ) ) , a : 1 )" as &[_]);
+        let v = Known::deserialize(&mut de).unwrap();
+        assert_eq!(v, Known { a: 1 });
+
+        let v: super::Result<serde_json::Value> = super::from_str("(a: 1)");
+        assert!(v.is_err());
     }
     #[test]
-    fn deserialize_struct_with_optional_missing() {
-        #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
-        struct Full {
-            a: String,
-            b: Option<String>,
+    fn fail_deserialize_reserved_chars() {
+        for (input, position) in [
+            ("*", 0),
+            ("a@b", 1),
+            ("!(1,*)", 4),
+            ("(a:*)", 3),
+            ("(*:1)", 1),
+            ("(a:x*,b:1)", 4),
+        ] {
+            let err = super::from_str::<serde_json::Value>(input).unwrap_err();
+            assert!(
+                matches!(err.code, super::ErrorCode::ReservedChar),
+                "{}",
+                input
+            );
+            assert_eq!(err.position(), Some(position), "{}", input);
         }
-        let v: Full = super::from_str("(a:hello)").unwrap();
+        assert!(super::from_str::<serde_json::Value>("'a*b@c'").is_ok());
+    }
+    #[test]
+    fn deserialize_reserved_chars_when_allowed() {
+        use serde::Deserialize;
+
+        let builder = super::Deserializer::builder().allow_reserved_chars(true);
+        let v = serde_json::Value::deserialize(&mut builder.from_str("(to:a@b.c,*:!(**))"));
+        assert_eq!(v.unwrap(), serde_json::json!({"to": "a@b.c", "*": ["**"]}));
+    }
+    #[test]
+    fn deserialize_case_insensitive_markers() {
+        use serde::Deserialize;
+
+        let builder = super::Deserializer::builder().case_insensitive_markers(true);
+        let v = serde_json::Value::deserialize(&mut builder.from_str("!(!T,!F,!N,!t)")).unwrap();
+        assert_eq!(v, serde_json::json!([true, false, null, true]));
+        let v = <Option<bool>>::deserialize(&mut builder.from_str("!N")).unwrap();
+        assert_eq!(v, None);
+        serde::de::IgnoredAny::deserialize(&mut builder.from_str("(a:!F,b:!(!N))")).unwrap();
+
+        let err = serde_json::Value::deserialize(&mut builder.from_str("!X")).unwrap_err();
+        assert!(matches!(err.code(), super::ErrorCode::InvalidMarker));
+        let err = super::from_str::<bool>("!T").unwrap_err();
+        assert!(matches!(err.code(), super::ErrorCode::InvalidMarker));
+    }
+    #[test]
+    fn deserialize_json_literals() {
+        use serde::Deserialize;
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Known {
+            a: bool,
+            b: Option<u32>,
+            c: Option<String>,
+            d: Option<String>,
+            e: String,
+        }
+        let input = "(a:true,b:null,c:nope,d:'null',e:'true')";
+        let builder = super::Deserializer::builder().json_literals(true);
 
+        let v = Known::deserialize(&mut builder.from_str(input)).unwrap();
         assert_eq!(
             v,
-            Full {
-                a: "hello".into(),
-                b: None
+            Known {
+                a: true,
+                b: None,
+                c: Some("nope".into()),
+                d: Some("null".into()),
+                e: "true".into(),
             }
         );
+
+        let v =
+            serde_json::Value::deserialize(&mut builder.from_str("!(true,false,null)")).unwrap();
+        assert_eq!(v, serde_json::json!([true, false, null]));
+
+        let v: serde_json::Value = super::from_str("!(true,false,null)").unwrap();
+        assert_eq!(v, serde_json::json!(["true", "false", "null"]));
     }
     #[test]
-    fn deserialize_map() {
-        let v: std::collections::HashMap<String, String> =
-            super::from_str("(a:hello,b:world)").unwrap();
+    fn deserialize_from_bytes_mut() {
+        #[derive(serde::Deserialize)]
+        struct Borrowed<'a> {
+            a: &'a str,
+            b: Vec<&'a str>,
+            c: &'a crate::value::RawValue,
+        }
 
-        let expected = vec![("a".into(), "hello".into()), ("b".into(), "world".into())]
-            .into_iter()
-            .collect();
-        assert_eq!(v, expected);
+        let mut input = b"(a:'it!'s',b:!('!!!!',x,''),c:(d:'!'e!''))".to_vec();
+        let v: Borrowed = super::from_bytes_mut(&mut input).unwrap();
+        assert_eq!(
+            (v.a, &*v.b, v.c.get()),
+            ("it's", &["!!", "x", ""][..], "(d:'!'e!'')")
+        );
+
+        let inputs: [&[u8]; 4] = [b"'abc", b"'ab!", b"'a!b'", b"!(1,'a!'\xff')"];
+        for input in inputs {
+            let expected = super::from_slice::<serde_json::Value>(input).unwrap_err();
+            let err = super::from_bytes_mut::<serde_json::Value>(&mut input.to_vec()).unwrap_err();
+            assert_eq!(
+                (err.code().to_string(), err.position()),
+                (expected.code().to_string(), expected.position())
+            );
+        }
     }
     #[test]
-    fn deserialize_tuple() {
-        let v: (String, String) = super::from_str("!(hello,world)").unwrap();
+    fn deserialize_after_reset() {
+        use serde::Deserialize;
 
-        assert_eq!(v, ("hello".into(), "world".into()));
+        let mut de = super::Deserializer::from_str("!(!(!(!(1))))");
+        de.set_recursion_limit(3);
+        assert!(serde_json::Value::deserialize(&mut de).is_err());
+
+        for (input, expected) in [("'it!'s'", "it's"), ("'!!x'", "!x"), ("y", "y")] {
+            de.reset(super::read::StrRead::new(input));
+            assert_eq!(String::deserialize(&mut de).unwrap(), expected);
+            de.end().unwrap();
+        }
+        de.reset(super::read::StrRead::new("!(!(!(1)))"));
+        assert!(serde_json::Value::deserialize(&mut de).is_ok());
     }
     #[test]
-    fn deserialize_value_string() {
-        let v: serde_json::Value = super::from_str("helloworld").unwrap();
-
-        assert_eq!(v, serde_json::Value::String("helloworld".into()));
+    #[cfg_attr(miri, ignore)]
+    fn release_scratch_after_large_strings() {
+        use serde::Deserialize;
+
+        let input = format!("'{}'", "!!".repeat(10_000));
+        let builder = super::Deserializer::builder().max_retained_scratch(64);
+        let mut de = builder.from_str(&input);
+        assert_eq!(String::deserialize(&mut de).unwrap().len(), 10_000);
+        assert!(de.scratch.capacity() >= 10_000);
+        de.end().unwrap();
+        assert!(de.scratch.capacity() <= 64);
+
+        let mut de = super::Deserializer::from_str(&input);
+        String::deserialize(&mut de).unwrap();
+        de.reset(super::read::StrRead::new("'a!!'"));
+        assert!(de.scratch.capacity() >= 10_000);
+        let mut de = builder.from_str(&input);
+        String::deserialize(&mut de).unwrap();
+        de.reset(super::read::StrRead::new("'a!!'"));
+        assert!(de.scratch.capacity() <= 64);
+        assert_eq!(String::deserialize(&mut de).unwrap(), "a!");
     }
     #[test]
-    fn deserialize_value_map() {
-        let v: serde_json::Value = super::from_str("(hello:!(a,b,c),world:'it works')").unwrap();
+    fn deserialize_through_erased_serde() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Plugin {
+            name: String,
+            enabled: bool,
+            #[serde(default)]
+            args: Vec<f64>,
+        }
+
+        let stream: &[u8] = b"(enabled:!f,name:'a b')";
+        let sources: Vec<super::DynDeserializer> = vec![
+            super::Deserializer::from_str("(name:x,enabled:!t,args:!(1.5))").into_dyn(),
+            super::Deserializer::from_reader(stream).into_dyn(),
+            super::Deserializer::from_slice(b"(name:x,enabled:1)").into_dyn(),
+        ];
+        let plugins: Vec<_> = sources
+            .into_iter()
+            .map(|mut de| {
+                let erased = &mut <dyn erased_serde::Deserializer>::erase(&mut de);
+                erased_serde::deserialize::<Plugin>(erased).map_err(|err| err.to_string())
+            })
+            .collect();
 
         assert_eq!(
-            v,
-            serde_json::json!({"hello": ["a", "b", "c"], "world": "it works"})
+            plugins[..2],
+            [
+                Ok(Plugin {
+                    name: "x".into(),
+                    enabled: true,
+                    args: vec![1.5]
+                }),
+                Ok(Plugin {
+                    name: "a b".into(),
+                    enabled: false,
+                    args: vec![]
+                }),
+            ]
         );
+        assert!(plugins[2]
+            .as_ref()
+            .unwrap_err()
+            .contains("expected a boolean"));
+
+        let mut de = super::Deserializer::from_str("(a:!(1,'x'),b:!n)");
+        let erased = &mut <dyn erased_serde::Deserializer>::erase(&mut de);
+        let raw: Box<crate::value::RawValue> = erased_serde::deserialize(erased).unwrap();
+        assert_eq!(raw.get(), "(a:!(1,'x'),b:!n)");
     }
     #[test]
-    fn deserialize_value_map_from_io() {
-        let v: serde_json::Value =
-            super::from_reader(b"(hello:!(a,b,c),world:'it works')" as &[_]).unwrap();
+    fn classify_errors() {
+        fn err(input: &str) -> super::Error {
+            super::from_str::<Vec<u8>>(input).unwrap_err()
+        }
 
-        assert_eq!(
-            v,
-            serde_json::json!({"hello": ["a", "b", "c"], "world": "it works"})
-        );
+        assert!(err("!(1,2").is_eof());
+        assert!(err("!(1,'x").is_eof());
+        assert!(err("!(1,2)x").is_syntax());
+        assert!(err("!(1,-)").is_syntax());
+        assert!(err("!(1,x)").is_data());
+        assert!(err("!(1,256)").is_data());
+        assert!(!err("!(1,2").is_syntax());
+        assert!(!err("!(1,x)").is_io());
+    }
+    #[test]
+    fn deserialize_from_iter() {
+        use serde::Deserialize;
+
+        let input = "(a:!(1,'x y'),b:!t)";
+        let expected = serde_json::json!({"a": [1, "x y"], "b": true});
+
+        let v: serde_json::Value = super::from_iter(input.bytes()).unwrap();
+        assert_eq!(v, expected);
+        let v: serde_json::Value = super::from_fallible_iter(input.bytes().map(Ok)).unwrap();
+        assert_eq!(v, expected);
+
+        let failing = input
+            .bytes()
+            .take(5)
+            .map(Ok)
+            .chain([Err(std::io::ErrorKind::UnexpectedEof.into())]);
+        let err = super::from_fallible_iter::<serde_json::Value, _>(failing).unwrap_err();
+        assert!(err.is_io());
+        assert_eq!(err.position(), Some(5));
+
+        let builder =
+            super::Deserializer::builder().limits(super::ParseLimits::new().max_input_len(8));
+        let v = serde_json::Value::deserialize(&mut builder.from_iter(input.bytes()));
+        assert!(matches!(
+            v.unwrap_err().code,
+            super::ErrorCode::InputLimitExceeded
+        ));
+    }
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn visit_str_in_chunks() {
+        fn chunks<'de, R: super::Read<'de>>(
+            de: &mut super::Deserializer<R>,
+        ) -> super::Result<Vec<String>> {
+            let mut chunks = Vec::new();
+            de.visit_str_chunks(|chunk| {
+                chunks.push(chunk.to_owned());
+                Ok(())
+            })?;
+            de.end()?;
+            Ok(chunks)
+        }
+
+        let expected = "ab!é'".repeat(5_000);
+        let input = format!("'{}'", expected.replace('!', "!!").replace('\'', "!'"));
+
+        let streamed = chunks(&mut super::Deserializer::from_reader(input.as_bytes())).unwrap();
+        assert!(streamed.len() > 1);
+        assert!(streamed.iter().all(|chunk| chunk.len() <= 8 * 1024));
+        assert_eq!(streamed.concat(), expected);
+        let reader = std::io::BufReader::with_capacity(7, input.as_bytes());
+        let buffered = chunks(&mut super::Deserializer::from_bufread(reader)).unwrap();
+        assert!(buffered.len() > 1);
+        assert_eq!(buffered.concat(), expected);
+        let whole = chunks(&mut super::Deserializer::from_str(&input)).unwrap();
+        assert_eq!(whole, [expected]);
+
+        let ident = chunks(&mut super::Deserializer::from_reader(&b"abc"[..])).unwrap();
+        assert_eq!(ident, ["abc"]);
+        let err = chunks(&mut super::Deserializer::from_str("!t")).unwrap_err();
+        assert!(err.is_data());
+
+        let builder = super::Deserializer::builder().reject_control_chars(true);
+        let input = format!("'{}\n'", "!!".repeat(10_000));
+        let err = chunks(&mut builder.from_reader(input.as_bytes())).unwrap_err();
+        assert_eq!(err.position(), Some(20_001));
+        let limits = super::ParseLimits::new().max_string_len(9_999);
+        let builder = super::Deserializer::builder().limits(limits);
+        let err = chunks(&mut builder.from_reader(input.as_bytes())).unwrap_err();
+        assert!(matches!(err.code, super::ErrorCode::StringLimitExceeded));
+    }
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn deserialize_from_async_reader() {
+        let (mut tx, rx) = tokio::io::duplex(4);
+        let write = async {
+            use tokio::io::AsyncWriteExt;
+
+            tx.write_all(b"(a:!(1,'x y'),b:!t)").await.unwrap();
+            drop(tx);
+        };
+        let read = super::from_async_reader::<serde_json::Value, _>(rx);
+
+        let ((), v) = tokio::join!(write, read);
+        assert_eq!(v.unwrap(), serde_json::json!({"a": [1, "x y"], "b": true}));
+
+        let err = super::from_async_reader::<serde_json::Value, _>(&b"(a:\n!x)"[..])
+            .await
+            .unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(2), Some(1)));
+    }
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn fail_deserialize_oversized_async_body() {
+        use tokio::io::AsyncReadExt;
+
+        let builder =
+            super::Deserializer::builder().limits(super::ParseLimits::new().max_input_len(16));
+        let v: super::Result<serde_json::Value> =
+            super::from_async_reader_with(&builder, &b"'0123456789abcd'"[..]).await;
+        assert_eq!(v.unwrap(), "0123456789abcd");
+
+        let body = (&b"'"[..]).chain(tokio::io::repeat(b'x'));
+        let err = super::from_async_reader_with::<serde_json::Value, _>(&builder, body)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.code, super::ErrorCode::InputLimitExceeded));
+        assert_eq!(err.position(), Some(16));
+    }
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn stop_reading_async_body_after_value() {
+        use tokio::io::AsyncReadExt;
+
+        // Input following the value fails without the rest being read
+        for value in ["(a:'x)y',b:!(1,'!'('))", "!(!())", "'it!'s'"] {
+            let body = value.as_bytes().chain(tokio::io::repeat(b'x'));
+            let err = super::from_async_reader::<serde_json::Value, _>(body)
+                .await
+                .unwrap_err();
+            assert!(matches!(err.code, super::ErrorCode::TrailingChars));
+            assert_eq!(err.position(), Some(value.len()));
+        }
+
+        let builder = super::Deserializer::builder().allow_whitespace(true);
+        let body = (&b"(a:1)\n \n"[..]).chain(&b"!t"[..]);
+        let err = super::from_async_reader_with::<serde_json::Value, _>(&builder, body)
+            .await
+            .unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(3), Some(1)));
     }
 }
@@ -0,0 +1,98 @@
+//! Reading the text of a value from an async stream, a chunk at a time
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::WHITESPACE;
+use crate::error::{Error, ErrorCode, Result};
+
+/// The number of bytes requested from the stream at a time
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// Read the text of a single value from `reader` into a buffer
+///
+/// Reading stops at the end of the stream, as soon as more than `max_len`
+/// bytes have arrived, or at the first character following a complete
+/// list, object or quoted string which cannot be trailing whitespace. An
+/// overlong body or one with trailing input is therefore rejected without
+/// being read in full. Any such trailing character is kept at the end of
+/// the buffer, for the deserializer to report.
+pub(crate) async fn read_value<R>(
+    mut reader: R,
+    max_len: usize,
+    allow_whitespace: bool,
+) -> Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0; CHUNK_LEN];
+    let mut scan = Scan::default();
+    loop {
+        let n = reader.read(&mut chunk).await.map_err(Error::io)?;
+        if n == 0 {
+            return Ok(buffer);
+        }
+        let chunk = &chunk[..n];
+        let end = chunk
+            .iter()
+            .position(|&byte| !scan.next(byte, allow_whitespace))
+            .map_or(n, |index| index + 1);
+        if buffer.len() + end > max_len {
+            return Err(Error::new(ErrorCode::InputLimitExceeded, max_len.into()));
+        }
+        buffer.extend_from_slice(&chunk[..end]);
+        if scan.trailing {
+            return Ok(buffer);
+        }
+    }
+}
+
+/// Tracks the nesting of lists, objects and quoted strings, to find where
+/// a value ends without parsing it
+///
+/// Numbers, unquoted strings and markers at the top level can only end at
+/// the end of the stream, so are not tracked. As `(`, `)` and `'` cannot
+/// appear in unquoted strings, the end is found exactly for valid input.
+/// For invalid input it may be found early, but only ever ends reading at
+/// an invalid character.
+#[derive(Default)]
+struct Scan {
+    depth: usize,
+    quoted: bool,
+    escaped: bool,
+    complete: bool,
+    /// Whether a character has been found following a complete value
+    trailing: bool,
+}
+
+impl Scan {
+    /// Advance over `byte`, returning `false` if it follows a complete value
+    /// and is not whitespace which may trail it
+    fn next(&mut self, byte: u8, allow_whitespace: bool) -> bool {
+        if self.complete {
+            self.trailing = !(allow_whitespace && WHITESPACE.contains(&byte));
+            return !self.trailing;
+        }
+        if self.quoted {
+            if self.escaped {
+                self.escaped = false;
+            } else if byte == b'!' {
+                self.escaped = true;
+            } else if byte == b'\'' {
+                self.quoted = false;
+                self.complete = self.depth == 0;
+            }
+            return true;
+        }
+        match byte {
+            b'\'' => self.quoted = true,
+            b'(' => self.depth += 1,
+            b')' => {
+                self.depth = self.depth.saturating_sub(1);
+                self.complete = self.depth == 0;
+            }
+            _ => {}
+        }
+        true
+    }
+}
@@ -0,0 +1,433 @@
+use std::sync::Arc;
+
+use super::intern::{KeyInterner, SharedInterner};
+use super::observe::{ParseEvent, ParseObserver, SharedObserver, ValueKind};
+use super::{read, Deserializer};
+use crate::Profile;
+
+/// The default maximum nesting depth of lists and objects
+pub const DEFAULT_RECURSION_LIMIT: usize = 128;
+
+/// Limits on the size of input, for parsing untrusted Rison
+///
+/// Each limit is disabled by default. Exceeding a limit fails with a syntax
+/// error at the position where the limit was crossed. Nesting depth is
+/// limited separately, by [`DeserializerBuilder::recursion_limit`].
+///
+/// ```
+/// use rison::de::ParseLimits;
+///
+/// let limits = ParseLimits::new()
+///     .max_input_len(2048)
+///     .max_string_len(256)
+///     .max_collection_len(100);
+/// let mut de = rison::Deserializer::builder().limits(limits).from_str("!(a,b)");
+/// # let _ = &mut de;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseLimits {
+    max_input_len: Option<usize>,
+    max_string_len: Option<usize>,
+    max_collection_len: Option<usize>,
+}
+
+impl ParseLimits {
+    /// Create a set of limits with every limit disabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the total length of the input in bytes
+    ///
+    /// When deserializing from an IO stream, no more than this many bytes
+    /// are read.
+    pub fn max_input_len(mut self, len: usize) -> Self {
+        self.max_input_len = Some(len);
+        self
+    }
+
+    /// Limit the length in bytes of each string, quoted or unquoted,
+    /// including object keys, after unescaping
+    pub fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = Some(len);
+        self
+    }
+
+    /// Limit the number of elements in each list, and of entries in each
+    /// object
+    pub fn max_collection_len(mut self, len: usize) -> Self {
+        self.max_collection_len = Some(len);
+        self
+    }
+
+    pub(crate) fn input_len(&self) -> usize {
+        self.max_input_len.unwrap_or(usize::MAX)
+    }
+
+    pub(crate) fn string_len(&self) -> usize {
+        self.max_string_len.unwrap_or(usize::MAX)
+    }
+
+    pub(crate) fn collection_len(&self) -> usize {
+        self.max_collection_len.unwrap_or(usize::MAX)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Options {
+    pub(crate) recursion_limit: usize,
+    pub(crate) allow_whitespace: bool,
+    pub(crate) json_literals: bool,
+    pub(crate) case_insensitive_markers: bool,
+    pub(crate) allow_reserved_chars: bool,
+    pub(crate) empty_string_as_none: bool,
+    pub(crate) allow_trailing_commas: bool,
+    pub(crate) reject_control_chars: bool,
+    pub(crate) profile: Profile,
+    pub(crate) limits: ParseLimits,
+    pub(crate) key_interner: Option<SharedInterner>,
+    pub(crate) max_scratch_capacity: Option<usize>,
+    pub(crate) observer: Option<SharedObserver>,
+    /// Whether the value is parsed from the start of a larger input, whose
+    /// delimiters end numbers and unquoted strings
+    pub(crate) partial: bool,
+    #[cfg(feature = "unbounded_depth")]
+    pub(crate) disable_recursion_limit: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            allow_whitespace: false,
+            json_literals: false,
+            case_insensitive_markers: false,
+            allow_reserved_chars: false,
+            empty_string_as_none: false,
+            allow_trailing_commas: false,
+            reject_control_chars: false,
+            profile: Profile::Strict,
+            limits: ParseLimits::default(),
+            key_interner: None,
+            max_scratch_capacity: None,
+            observer: None,
+            partial: false,
+            #[cfg(feature = "unbounded_depth")]
+            disable_recursion_limit: false,
+        }
+    }
+}
+
+impl Options {
+    /// Characters which terminate an unquoted string
+    pub(crate) fn not_id_chars(&self) -> Box<read::ByteSet> {
+        let mut chars = read::NOT_ID_CHARS.to_vec();
+        if self.allow_reserved_chars || self.profile.reserved_chars_in_ids() {
+            chars.retain(|c| !read::RESERVED_CHARS.contains(c));
+        }
+        if self.allow_whitespace {
+            chars.extend_from_slice(super::WHITESPACE);
+        }
+        if self.partial {
+            chars.extend_from_slice(super::PARTIAL_DELIMITERS);
+        }
+        Box::new(read::ByteSet::new(&chars))
+    }
+
+    /// Report a parsed value to the configured observer, if any
+    pub(crate) fn observe(&self, kind: ValueKind, depth: usize, len: Option<usize>) {
+        if let Some(observer) = &self.observer {
+            observer.0.observe(&ParseEvent { kind, depth, len });
+        }
+    }
+
+    pub(crate) fn recursion_limit_disabled(&self) -> bool {
+        #[cfg(feature = "unbounded_depth")]
+        {
+            self.disable_recursion_limit
+        }
+        #[cfg(not(feature = "unbounded_depth"))]
+        {
+            false
+        }
+    }
+}
+
+/// A builder for configured [`Deserializer`]s
+///
+/// Created by [`Deserializer::builder`]. The same builder may be used to
+/// construct any number of deserializers sharing one configuration.
+#[derive(Clone, Debug, Default)]
+pub struct DeserializerBuilder {
+    pub(super) options: Options,
+}
+
+impl DeserializerBuilder {
+    /// Create a builder with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum nesting depth of lists and objects
+    ///
+    /// Defaults to [`DEFAULT_RECURSION_LIMIT`].
+    pub fn recursion_limit(mut self, limit: usize) -> Self {
+        self.options.recursion_limit = limit;
+        self
+    }
+
+    /// Parse arbitrarily deeply nested input without a recursion limit
+    ///
+    /// See [`Deserializer::disable_recursion_limit`].
+    #[cfg(feature = "unbounded_depth")]
+    pub fn disable_recursion_limit(mut self) -> Self {
+        self.options.disable_recursion_limit = true;
+        self
+    }
+
+    /// Skip spaces, tabs, and newlines between tokens
+    ///
+    /// Whitespace is not permitted by the Rison grammar, which is designed
+    /// for embedding in URLs, but is convenient in hand-written Rison such as
+    /// configuration files. Whitespace within quoted strings is always
+    /// preserved, and whitespace terminates unquoted strings. Disabled by
+    /// default.
+    pub fn allow_whitespace(mut self, allow: bool) -> Self {
+        self.options.allow_whitespace = allow;
+        self
+    }
+
+    /// Accept the unquoted strings `true`, `false`, and `null` as aliases of
+    /// `!t`, `!f`, and `!n`
+    ///
+    /// This is for compatibility with producers which write JSON literals,
+    /// such as hand-written URLs. Object keys and quoted strings are
+    /// unaffected, and the literals can no longer be read as strings unless
+    /// quoted. Disabled by default, as the Rison grammar requires.
+    pub fn json_literals(mut self, accept: bool) -> Self {
+        self.options.json_literals = accept;
+        self
+    }
+
+    /// Accept the uppercase markers `!T`, `!F`, and `!N` as aliases of `!t`,
+    /// `!f`, and `!n`
+    ///
+    /// This is for hand-written input, whose authors sometimes capitalize
+    /// them. Other markers still fail with
+    /// [`ErrorCode::InvalidMarker`](crate::error::ErrorCode::InvalidMarker).
+    /// Disabled by default, as the Rison grammar requires.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// let builder = rison::Deserializer::builder().case_insensitive_markers(true);
+    /// let v = <(bool, Option<u8>)>::deserialize(&mut builder.from_str("!(!T,!N)")).unwrap();
+    /// assert_eq!(v, (true, None));
+    /// ```
+    pub fn case_insensitive_markers(mut self, accept: bool) -> Self {
+        self.options.case_insensitive_markers = accept;
+        self
+    }
+
+    /// Accept the reserved characters `*` and `@` within unquoted strings
+    ///
+    /// The Rison grammar reserves them, so by default they fail with
+    /// [`ErrorCode::ReservedChar`](crate::error::ErrorCode::ReservedChar)
+    /// wherever they appear outside quoted strings. Some producers write
+    /// them unquoted regardless, as in `user@example.com`, which this
+    /// accepts as a string. The serializer always quotes them. Disabled by
+    /// default.
+    pub fn allow_reserved_chars(mut self, allow: bool) -> Self {
+        self.options.allow_reserved_chars = allow;
+        self
+    }
+
+    /// Accept a `,` after the last element of a list or object, as in
+    /// `!(1,2,)` or `(a:1,b:2,)`
+    ///
+    /// Such input is typically produced by naive string concatenation. The
+    /// Rison grammar forbids it, so by default it fails with
+    /// [`ErrorCode::TrailingComma`](crate::error::ErrorCode::TrailingComma),
+    /// rather than being read as ending with an empty unquoted string.
+    /// Disabled by default.
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.options.allow_trailing_commas = allow;
+        self
+    }
+
+    /// Reject C0 control characters, `\0` to `\x1f`, within quoted strings
+    ///
+    /// They are never legal in URLs, so their presence means the input was
+    /// mangled or hand-crafted, but the Rison grammar permits them and by
+    /// default they are read as written. This fails with
+    /// [`ErrorCode::ControlCharInString`](crate::error::ErrorCode::ControlCharInString)
+    /// at the offending character instead, in quoted keys and in the
+    /// strings of ignored values too. Unquoted strings are unaffected.
+    /// Disabled by default.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// let builder = rison::Deserializer::builder().reject_control_chars(true);
+    /// let err = String::deserialize(&mut builder.from_str("'a\tb'")).unwrap_err();
+    /// assert_eq!(err.position(), Some(2));
+    /// ```
+    pub fn reject_control_chars(mut self, reject: bool) -> Self {
+        self.options.reject_control_chars = reject;
+        self
+    }
+
+    /// Read an empty quoted string `''` as `None` where an `Option` is
+    /// expected
+    ///
+    /// Some URL builders write `field:''` to mean that a field is unset.
+    /// This reads such fields as `None` rather than `Some("")`, or rather
+    /// than failing for options of non-string types, without a wrapper type
+    /// in every consumer. Empty strings elsewhere are unaffected. Disabled
+    /// by default.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Search {
+    ///     q: Option<String>,
+    ///     page: Option<u32>,
+    /// }
+    ///
+    /// let builder = rison::Deserializer::builder().empty_string_as_none(true);
+    /// let search = Search::deserialize(&mut builder.from_str("(q:'',page:'')")).unwrap();
+    /// assert_eq!((search.q, search.page), (None, None));
+    /// ```
+    pub fn empty_string_as_none(mut self, enable: bool) -> Self {
+        self.options.empty_string_as_none = enable;
+        self
+    }
+
+    /// Set the dialect of Rison to read, for a particular peer
+    /// implementation
+    ///
+    /// See [`Profile`]. Defaults to [`Profile::Strict`].
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.options.profile = profile;
+        self
+    }
+
+    /// Limit the size of input, for parsing untrusted Rison
+    ///
+    /// See [`ParseLimits`]. No limits are applied by default.
+    pub fn limits(mut self, limits: ParseLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Intern object keys and identifiers which would otherwise be copied,
+    /// so that they may be borrowed, and keys deserialized as
+    /// [`SharedKey`](super::SharedKey)s, so that they share storage
+    ///
+    /// See [`KeyInterner`]. Keys of other types borrowed from the input are
+    /// unaffected. No interner is used by default.
+    pub fn key_interner(mut self, interner: impl KeyInterner + 'static) -> Self {
+        self.options.key_interner = Some(SharedInterner(Arc::new(interner)));
+        self
+    }
+
+    /// Release memory held by the deserializer's scratch buffer beyond
+    /// `capacity` bytes whenever it is [`reset`](Deserializer::reset) or
+    /// [`end`](Deserializer::end) is called
+    ///
+    /// The scratch buffer grows to hold the longest string copied from the
+    /// input, and by default keeps its capacity for reuse. A deserializer
+    /// kept for the life of a service would otherwise hold on to the memory
+    /// of the largest string it has ever read. Strings longer than
+    /// `capacity` may still be read; only the memory retained afterwards is
+    /// limited.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// let builder = rison::Deserializer::builder().max_retained_scratch(4096);
+    /// let input = format!("'{}'", "a".repeat(1 << 20));
+    /// let mut de = builder.from_reader(input.as_bytes());
+    /// let s = String::deserialize(&mut de).unwrap();
+    /// de.end().unwrap();
+    /// # assert_eq!(s.len(), 1 << 20);
+    /// ```
+    pub fn max_retained_scratch(mut self, capacity: usize) -> Self {
+        self.options.max_scratch_capacity = Some(capacity);
+        self
+    }
+
+    /// Report each value parsed to `observer`, for instrumentation
+    ///
+    /// See [`ParseObserver`]. No observer is used by default.
+    pub fn observer(mut self, observer: impl ParseObserver + 'static) -> Self {
+        self.options.observer = Some(SharedObserver(Arc::new(observer)));
+        self
+    }
+
+    /// Create a configured Rison deserializer from any [`read::Read`]
+    ///
+    /// [`ParseLimits::max_input_len`] is checked by the deserializer against
+    /// [`Read::position`](read::Read::position) between tokens, so applies
+    /// to any source. The sources created by this builder's other methods
+    /// check it as well as they read, so that no more is read from a stream
+    /// than the limit, even within one long string.
+    pub fn build<'de, R: read::Read<'de>>(&self, read: R) -> Deserializer<R> {
+        Deserializer::with_options(read, self.options.clone())
+    }
+
+    /// Create a configured Rison deserializer from an `io::Read`
+    pub fn from_reader<R: std::io::Read>(&self, reader: R) -> Deserializer<read::IoRead<R>> {
+        let read = read::IoRead::with_max_len(read::bytes(reader), self.options.limits.input_len());
+        Deserializer::with_options(read, self.options.clone())
+    }
+
+    /// Create a configured Rison deserializer from an `io::BufRead`, reading
+    /// a buffered chunk at a time
+    pub fn from_bufread<R: std::io::BufRead>(&self, reader: R) -> Deserializer<read::BufIoRead<R>> {
+        let read = read::BufIoRead::with_max_len(reader, self.options.limits.input_len());
+        Deserializer::with_options(read, self.options.clone())
+    }
+
+    /// Create a configured Rison deserializer from an iterator of bytes
+    pub fn from_iter<I>(
+        &self,
+        iter: I,
+    ) -> Deserializer<read::IterRead<read::InfallibleIter<I::IntoIter>>>
+    where
+        I: IntoIterator<Item = u8>,
+    {
+        let iter = read::infallible(iter.into_iter());
+        let read = read::IterRead::with_max_len(iter, self.options.limits.input_len());
+        Deserializer::with_options(read, self.options.clone())
+    }
+
+    /// Create a configured Rison deserializer from an iterator of bytes,
+    /// each of which may instead be an IO error
+    pub fn from_fallible_iter<I>(&self, iter: I) -> Deserializer<read::IterRead<I::IntoIter>>
+    where
+        I: IntoIterator<Item = std::io::Result<u8>>,
+    {
+        let read = read::IterRead::with_max_len(iter.into_iter(), self.options.limits.input_len());
+        Deserializer::with_options(read, self.options.clone())
+    }
+
+    /// Create a configured Rison deserializer from a `&[u8]`
+    pub fn from_slice<'a>(&self, slice: &'a [u8]) -> Deserializer<read::SliceRead<'a>> {
+        Deserializer::with_options(read::SliceRead::new(slice), self.options.clone())
+    }
+
+    /// Create a configured Rison deserializer from a `&mut [u8]`, unescaping
+    /// quoted strings in place
+    pub fn from_mut_slice<'a>(&self, slice: &'a mut [u8]) -> Deserializer<read::MutSliceRead<'a>> {
+        let read = read::MutSliceRead::new(slice);
+        Deserializer::with_options(read, self.options.clone())
+    }
+
+    /// Create a configured Rison deserializer from a `&str`
+    #[allow(clippy::wrong_self_convention, clippy::should_implement_trait)]
+    pub fn from_str<'a>(&self, s: &'a str) -> Deserializer<read::StrRead<'a>> {
+        Deserializer::with_options(read::StrRead::new(s), self.options.clone())
+    }
+}
@@ -0,0 +1,395 @@
+//! A pull parser reading Rison as a stream of events, without serde
+
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+use super::read::{self, Read};
+use super::{Deserializer, Spanned, ValueKind};
+use crate::error::{Error, ErrorCode, Result};
+
+/// A single token of structure or data read by a [`Parser`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event<'de> {
+    /// `!n`
+    Null,
+    /// `!t` or `!f`
+    Bool(bool),
+    /// The text of a number, exactly as written unless read under a
+    /// [`Profile`](crate::Profile) permitting forms outside the Rison
+    /// grammar, which are rewritten within it
+    Number(Cow<'de, str>),
+    /// A string, quoted or not, with any escapes removed
+    String(Cow<'de, str>),
+    /// The `!(` opening a list
+    StartList,
+    /// The `(` opening an object
+    StartObject,
+    /// The key of an object entry, quoted or not, which is followed by its
+    /// value
+    Key(Cow<'de, str>),
+    /// The `)` closing the list or object most recently started
+    End,
+}
+
+/// The list or object an event is nested in
+#[derive(Clone, Copy)]
+struct Container {
+    object: bool,
+    /// The position of the `!(` or `(` opening it
+    start: usize,
+    /// The number of elements or entries read so far
+    len: usize,
+}
+
+/// A pull parser, reading Rison from a [`Deserializer`] as an iterator of
+/// [`Event`]s, each with the span of input it was read from
+///
+/// Events are produced one at a time as the iterator is advanced, with no
+/// recursion and without building any values, so syntax highlighters,
+/// linters and filters can process input of any size or depth. Strings are
+/// borrowed from the input where possible. The deserializer's options apply,
+/// except for its recursion limit.
+///
+/// Iteration ends after the events of a single value, once the rest of the
+/// input has been checked to be empty, or after the first error.
+///
+/// ```
+/// use rison::de::{Event, Parser};
+///
+/// let events: Vec<_> = Parser::from_str("(a:!(1,x),b:!n)")
+///     .map(|event| event.map(|e| (e.start(), e.into_inner())))
+///     .collect::<rison::Result<_>>()
+///     .unwrap();
+///
+/// assert_eq!(
+///     events,
+///     [
+///         (0, Event::StartObject),
+///         (1, Event::Key("a".into())),
+///         (3, Event::StartList),
+///         (5, Event::Number("1".into())),
+///         (7, Event::String("x".into())),
+///         (8, Event::End),
+///         (10, Event::Key("b".into())),
+///         (12, Event::Null),
+///         (14, Event::End),
+///     ],
+/// );
+/// ```
+pub struct Parser<'de, R> {
+    de: Deserializer<R>,
+    /// The lists and objects which have been started and not yet ended,
+    /// innermost last
+    stack: Vec<Container>,
+    /// Whether the next event is a value following an object key
+    after_key: bool,
+    /// Whether the top-level value has been started
+    started: bool,
+    /// Whether iteration has ended, after the end of the input or an error
+    finished: bool,
+    lifetime: PhantomData<&'de ()>,
+}
+
+impl<'a> Parser<'a, read::SliceRead<'a>> {
+    /// Create a parser reading from a slice of bytes
+    pub fn from_slice(slice: &'a [u8]) -> Self {
+        Self::new(Deserializer::from_slice(slice))
+    }
+}
+
+impl<'a> Parser<'a, read::StrRead<'a>> {
+    /// Create a parser reading from a `&str`
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'a str) -> Self {
+        Self::new(Deserializer::from_str(s))
+    }
+}
+
+impl<'de, R: Read<'de>> Parser<'de, R> {
+    /// Create a parser reading from a deserializer, which may have been
+    /// configured with a [`DeserializerBuilder`](super::DeserializerBuilder)
+    pub fn new(de: Deserializer<R>) -> Self {
+        Self {
+            de,
+            stack: Vec::new(),
+            after_key: false,
+            started: false,
+            finished: false,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// The number of lists and objects the next event is nested in
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Read the next event, or `None` once the input has been read in full
+    fn next_event(&mut self) -> Result<Option<Spanned<Event<'de>>>> {
+        if !self.started {
+            self.started = true;
+            return self.parse_value().map(Some);
+        }
+        if self.after_key {
+            self.after_key = false;
+            return self.parse_value().map(Some);
+        }
+        let Some(&Container { object, start, len }) = self.stack.last() else {
+            self.de.end()?;
+            return Ok(None);
+        };
+        match self.de.parse_whitespace()? {
+            Some(b')') => {
+                let start = self.de.read.position();
+                self.de.eat_char();
+                self.stack.pop();
+                self.de.depth -= 1;
+                return Ok(Some(self.spanned(start, Event::End)));
+            }
+            Some(b',') if len > 0 => {
+                if !self.de.eat_separator()? {
+                    return self.next_event();
+                }
+            }
+            Some(_) if len == 0 => {}
+            Some(_) if object => {
+                return Err(self.de.unexpected_char(ErrorCode::ExpectedObjectSepOrEnd))
+            }
+            Some(_) => return Err(self.de.unexpected_char(ErrorCode::ExpectedListSepOrEnd)),
+            None if object => return Err(self.de.unclosed(ErrorCode::UnclosedObject(start))),
+            None => return Err(self.de.unclosed(ErrorCode::UnclosedList(start))),
+        }
+        let container = self.stack.last_mut().expect("within a container");
+        container.len += 1;
+        let len = container.len;
+        self.de.check_collection_len(len)?;
+        if object {
+            self.parse_key().map(Some)
+        } else {
+            self.parse_value().map(Some)
+        }
+    }
+
+    /// Read an object key and the `:` following it
+    fn parse_key(&mut self) -> Result<Spanned<Event<'de>>> {
+        self.de.parse_whitespace()?;
+        let start = self.de.read.position();
+        let key = to_cow(self.de.parse_key(ValueKind::Key)?);
+        let key = self.spanned(start, Event::Key(key));
+        if self.de.parse_whitespace()? != Some(b':') {
+            return Err(self.de.unexpected_char(ErrorCode::ExpectedColon));
+        }
+        self.de.eat_char();
+        self.after_key = true;
+        Ok(key)
+    }
+
+    /// Read a scalar value, or the opening of a list or object
+    fn parse_value(&mut self) -> Result<Spanned<Event<'de>>> {
+        let peek = self.de.parse_whitespace()?;
+        let start = self.de.read.position();
+        let event = match peek {
+            Some(b'!') => {
+                self.de.eat_char();
+                let marker = self.de.peek_marker()?.ok_or(Error::new(
+                    ErrorCode::EofMarker,
+                    self.de.read.position().into(),
+                ))?;
+                let event = match marker {
+                    b'n' => {
+                        self.de.observe(ValueKind::Null, None);
+                        Event::Null
+                    }
+                    b't' | b'f' => {
+                        self.de.observe(ValueKind::Bool, None);
+                        Event::Bool(marker == b't')
+                    }
+                    b'(' => self.start(false, start),
+                    _ => {
+                        return Err(Error::new(
+                            ErrorCode::InvalidMarker,
+                            self.de.read.position().into(),
+                        ))
+                    }
+                };
+                self.de.eat_char();
+                event
+            }
+            Some(b'(') => {
+                self.de.eat_char();
+                self.start(true, start)
+            }
+            Some(b'\'') => {
+                self.de.eat_char();
+                Event::String(to_cow(self.de.parse_str()?))
+            }
+            Some(b'-' | b'0'..=b'9') => match self.de.parse_numeric()? {
+                super::Numeric::Number(number) => Event::Number(Cow::Owned(number)),
+                super::Numeric::Ident(s) => Event::String(to_cow(s)),
+            },
+            Some(_) => {
+                let json_literals = self.de.options.json_literals;
+                match to_cow(self.de.parse_ident()?) {
+                    s if json_literals && s == "true" => Event::Bool(true),
+                    s if json_literals && s == "false" => Event::Bool(false),
+                    s if json_literals && s == "null" => Event::Null,
+                    s => Event::String(s),
+                }
+            }
+            None => {
+                return Err(Error::new(
+                    ErrorCode::EofValue,
+                    self.de.read.position().into(),
+                ))
+            }
+        };
+        Ok(self.spanned(start, event))
+    }
+
+    /// Enter a list or object opened at `start`, returning the event which
+    /// opens it
+    fn start(&mut self, object: bool, start: usize) -> Event<'de> {
+        self.stack.push(Container {
+            object,
+            start,
+            len: 0,
+        });
+        let (kind, event) = if object {
+            (ValueKind::Object, Event::StartObject)
+        } else {
+            (ValueKind::List, Event::StartList)
+        };
+        self.de.observe(kind, None);
+        self.de.depth += 1;
+        event
+    }
+
+    fn spanned(&mut self, start: usize, event: Event<'de>) -> Spanned<Event<'de>> {
+        Spanned::new(start..self.de.read.position(), event)
+    }
+}
+
+fn to_cow<'de>(s: read::Reference<'de, '_, str>) -> Cow<'de, str> {
+    match s {
+        read::Reference::Borrowed(borrowed) => Cow::Borrowed(borrowed),
+        read::Reference::Copied(copied) => Cow::Owned(copied.to_owned()),
+    }
+}
+
+impl<'de, R: Read<'de>> Iterator for Parser<'de, R> {
+    type Item = Result<Spanned<Event<'de>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match self.next_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(err) => {
+                self.finished = true;
+                Some(Err(self.de.locate(err)))
+            }
+        }
+    }
+}
+
+impl<'de, R: Read<'de>> std::iter::FusedIterator for Parser<'de, R> {}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::{Event, Parser};
+    use crate::error::ErrorCode;
+
+    fn events(input: &str) -> Vec<(std::ops::Range<usize>, Event<'_>)> {
+        Parser::from_str(input)
+            .map(|event| event.map(|e| (e.span(), e.into_inner())))
+            .collect::<crate::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_events() {
+        let input = "(a:!(1.5e3,-2,!t,!f),'b c':'it!'s',d:())";
+
+        assert_eq!(
+            events(input),
+            [
+                (0..1, Event::StartObject),
+                (1..2, Event::Key("a".into())),
+                (3..5, Event::StartList),
+                (5..10, Event::Number("1.5e3".into())),
+                (11..13, Event::Number("-2".into())),
+                (14..16, Event::Bool(true)),
+                (17..19, Event::Bool(false)),
+                (19..20, Event::End),
+                (21..26, Event::Key("b c".into())),
+                (27..34, Event::String("it's".into())),
+                (35..36, Event::Key("d".into())),
+                (37..38, Event::StartObject),
+                (38..39, Event::End),
+                (39..40, Event::End),
+            ],
+        );
+        assert_eq!(events("x"), [(0..1, Event::String("x".into()))]);
+        assert_eq!(
+            events("!()"),
+            [(0..2, Event::StartList), (2..3, Event::End)]
+        );
+    }
+    #[test]
+    fn parse_events_borrowing_strings() {
+        let events = events("(a:x,b:'y z',c:'!!')");
+
+        assert!(matches!(events[2].1, Event::String(Cow::Borrowed("x"))));
+        assert!(matches!(events[4].1, Event::String(Cow::Borrowed("y z"))));
+        assert!(matches!(&events[6].1, Event::String(Cow::Owned(s)) if s == "!"));
+    }
+    #[test]
+    fn parse_events_with_options() {
+        let de = crate::Deserializer::builder()
+            .allow_whitespace(true)
+            .allow_trailing_commas(true)
+            .from_str(" !( 1 , ( a : b , ) , ) ");
+        let events: Vec<_> = super::Parser::new(de).map(|e| e.unwrap().span()).collect();
+
+        assert_eq!(events, [1..3, 4..5, 8..9, 10..11, 14..15, 18..19, 22..23]);
+    }
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn parse_deeply_nested_events() {
+        let input = "!(".repeat(10_000) + &")".repeat(10_000);
+
+        assert_eq!(Parser::from_str(&input).count(), 20_000);
+    }
+    #[test]
+    fn fail_parse_events() {
+        for (input, code, position) in [
+            ("(a:1", "unclosed object started at position 0", 4),
+            ("!(1,(a:!(", "unclosed list started at position 7", 9),
+            ("!(1 2)", "expected `,` or `)`", 3),
+            ("(a 1)", "expected `:`", 2),
+            ("!x", "invalid marker", 1),
+            ("!(1,)", "trailing comma", 3),
+            ("(a:1))", "trailing characters", 5),
+            ("01", "invalid number", 1),
+        ] {
+            let mut parser = Parser::from_str(input);
+            let err = parser.find_map(Result::err).unwrap();
+            assert_eq!(
+                (err.code().to_string(), err.position()),
+                (code.to_string(), Some(position)),
+                "{input}"
+            );
+            assert!(parser.next().is_none());
+        }
+        let err = Parser::from_str("(a:*)").find_map(Result::err).unwrap();
+        assert!(matches!(err.code(), ErrorCode::ReservedChar));
+    }
+}
@@ -0,0 +1,343 @@
+//! Interning of object keys and identifiers
+
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// The name of the newtype struct through which a [`SharedKey`] is
+/// requested from this crate's deserializer
+pub(crate) const TOKEN: &str = "$rison::private::SharedKey";
+
+thread_local! {
+    /// The interned copy of the key being visited as a [`SharedKey`], which
+    /// cannot be passed to a visitor directly
+    static SHARED: Cell<Option<Arc<str>>> = const { Cell::new(None) };
+}
+
+/// Supplies long-lived copies of object keys, so that they may be borrowed
+/// or shared where they would otherwise be copied
+///
+/// Keys which cannot be borrowed from the input, because they are read
+/// from a stream or contain escapes, are copied into the deserializer's
+/// scratch buffer and provided to visitors through `visit_str`, so keys of
+/// types which borrow, such as `&str`, fail to deserialize. An interner
+/// which recognizes such a key through [`intern`](Self::intern) lets it be
+/// provided through `visit_borrowed_str` instead. Keys deserialized as
+/// [`SharedKey`]s share the copy returned by
+/// [`intern_shared`](Self::intern_shared), rather than each being
+/// allocated, as keys of other types which own their strings, such as
+/// `String`, are.
+///
+/// Configured with
+/// [`DeserializerBuilder::key_interner`](super::DeserializerBuilder::key_interner).
+/// Implemented for closures, which only provide borrowed keys, and by
+/// [`KnownKeys`].
+pub trait KeyInterner: Send + Sync {
+    /// A string equal to `key` which lives forever, or `None` to leave the
+    /// key to be copied
+    fn intern(&self, key: &str) -> Option<&'static str>;
+
+    /// A shared string equal to `key`, or `None` to leave the key to be
+    /// allocated
+    ///
+    /// As the string is reference counted, an interner may add keys as it
+    /// sees them, up to some limit, without leaking them. Provides nothing
+    /// by default.
+    fn intern_shared(&self, key: &str) -> Option<Arc<str>> {
+        let _ = key;
+        None
+    }
+}
+
+impl<F> KeyInterner for F
+where
+    F: Fn(&str) -> Option<&'static str> + Send + Sync,
+{
+    fn intern(&self, key: &str) -> Option<&'static str> {
+        self(key)
+    }
+}
+
+/// An interner of a fixed set of keys, such as those of a known schema
+///
+/// Other keys are left to be copied, so untrusted input cannot grow it.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use rison::de::KnownKeys;
+/// use serde::Deserialize;
+///
+/// let input = "(columns:!(host),query:x,sort:!())";
+///
+/// // Keys read from a stream can only be borrowed once interned
+/// let mut de = rison::Deserializer::from_reader(input.as_bytes());
+/// assert!(HashMap::<&str, rison::Value>::deserialize(&mut de).is_err());
+///
+/// let builder = rison::Deserializer::builder()
+///     .key_interner(KnownKeys::new(["columns", "query", "sort"]));
+/// let mut de = builder.from_reader(input.as_bytes());
+/// let state = HashMap::<&str, rison::Value>::deserialize(&mut de).unwrap();
+/// assert_eq!(state["query"], rison::Value::String("x".into()));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KnownKeys {
+    keys: HashMap<&'static str, Arc<str>, BuildHasherDefault<Fnv>>,
+}
+
+impl KnownKeys {
+    /// Create an interner of the given keys
+    pub fn new(keys: impl IntoIterator<Item = &'static str>) -> Self {
+        KnownKeys {
+            keys: keys.into_iter().map(|key| (key, Arc::from(key))).collect(),
+        }
+    }
+}
+
+impl KeyInterner for KnownKeys {
+    fn intern(&self, key: &str) -> Option<&'static str> {
+        self.keys.get_key_value(key).map(|(key, _)| *key)
+    }
+
+    fn intern_shared(&self, key: &str) -> Option<Arc<str>> {
+        self.keys.get(key).cloned()
+    }
+}
+
+/// The FNV-1a hash, which is quicker than the default for short keys
+///
+/// Keys are only looked up in a [`KnownKeys`], never inserted from input, so
+/// its lack of resistance to collisions chosen by an attacker is no concern.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// An object key sharing its storage with equal keys, as provided by a
+/// [`KeyInterner`]
+///
+/// Deserializing a map with `String` keys allocates every key, however
+/// often it repeats. A `SharedKey` deserialized by a deserializer whose
+/// interner provides it through
+/// [`intern_shared`](KeyInterner::intern_shared) is instead a
+/// reference-counted clone of the interner's copy. Other keys, and those
+/// from other formats, are allocated as a `String` would be. A
+/// `SharedKey` borrows as a `str`, so maps keyed by them may be indexed by
+/// `&str`.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use rison::de::{KnownKeys, SharedKey};
+/// use serde::Deserialize;
+///
+/// let builder = rison::Deserializer::builder().key_interner(KnownKeys::new(["x", "y"]));
+/// let input = "!((x:1,y:2),(x:3,y:4,z:5))";
+/// let points = Vec::<HashMap<SharedKey, i32>>::deserialize(&mut builder.from_str(input)).unwrap();
+///
+/// assert_eq!(points[1]["z"], 5);
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedKey(Arc<str>);
+
+impl SharedKey {
+    /// The text of the key
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for SharedKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for SharedKey {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SharedKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for SharedKey {
+    fn from(key: &str) -> Self {
+        SharedKey(Arc::from(key))
+    }
+}
+
+impl From<Arc<str>> for SharedKey {
+    fn from(key: Arc<str>) -> Self {
+        SharedKey(key)
+    }
+}
+
+impl From<SharedKey> for Arc<str> {
+    fn from(key: SharedKey) -> Self {
+        key.0
+    }
+}
+
+impl fmt::Debug for SharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for SharedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for SharedKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(TOKEN, SharedKeyVisitor)
+    }
+}
+
+struct SharedKeyVisitor;
+
+impl<'de> Visitor<'de> for SharedKeyVisitor {
+    type Value = SharedKey;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<SharedKey, E> {
+        // Take the interned copy left by `visit_shared`, if it is of this key
+        let shared = SHARED.with(Cell::take);
+        Ok(SharedKey(
+            shared
+                .filter(|shared| **shared == *v)
+                .unwrap_or_else(|| Arc::from(v)),
+        ))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<SharedKey, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+/// Visit `key`, requested as a [`SharedKey`], so that it is given
+/// `shared`, the interned copy of it
+pub(crate) fn visit_shared<'de, V, E>(
+    key: &str,
+    shared: Arc<str>,
+    visitor: V,
+) -> Result<V::Value, E>
+where
+    V: Visitor<'de>,
+    E: de::Error,
+{
+    SHARED.with(|slot| slot.set(Some(shared)));
+    let result = visitor.visit_str(key);
+    SHARED.with(Cell::take);
+    result
+}
+
+/// A shared [`KeyInterner`], held by the options of deserializers built
+/// from the same builder
+#[derive(Clone)]
+pub(crate) struct SharedInterner(pub(crate) Arc<dyn KeyInterner>);
+
+impl fmt::Debug for SharedInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyInterner")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Arc;
+
+    use serde::Deserialize;
+
+    use super::{KnownKeys, SharedKey};
+
+    #[test]
+    fn intern_copied_keys() {
+        let builder = crate::Deserializer::builder()
+            .key_interner(|key: &str| ["width", "it's"].into_iter().find(|k| *k == key));
+
+        let input = "(width:1,'it!'s':2)";
+        let v: BTreeMap<&str, u32> =
+            Deserialize::deserialize(&mut builder.from_str(input)).unwrap();
+        assert_eq!(v, BTreeMap::from([("width", 1), ("it's", 2)]));
+        let v: BTreeMap<&str, u32> =
+            Deserialize::deserialize(&mut builder.from_reader(input.as_bytes())).unwrap();
+        assert_eq!(v, BTreeMap::from([("width", 1), ("it's", 2)]));
+        let v: super::super::Result<BTreeMap<&str, u32>> =
+            Deserialize::deserialize(&mut crate::Deserializer::from_str(input));
+        assert!(v.is_err());
+
+        let input = "(width:1,height:2)";
+        let v: super::super::Result<BTreeMap<&str, u32>> =
+            Deserialize::deserialize(&mut builder.from_reader(input.as_bytes()));
+        assert!(v.is_err());
+    }
+    #[test]
+    fn share_interned_keys() {
+        let builder = crate::Deserializer::builder().key_interner(KnownKeys::new(["a"]));
+        let input = "!((a:1,b:2),('a':3,'b':4))";
+
+        for v in [
+            Vec::<HashMap<SharedKey, u32>>::deserialize(&mut builder.from_str(input)).unwrap(),
+            Vec::<HashMap<SharedKey, u32>>::deserialize(&mut builder.from_reader(input.as_bytes()))
+                .unwrap(),
+        ] {
+            let a: Vec<&SharedKey> = v.iter().map(|m| m.get_key_value("a").unwrap().0).collect();
+            let b: Vec<&SharedKey> = v.iter().map(|m| m.get_key_value("b").unwrap().0).collect();
+            assert!(Arc::ptr_eq(&a[0].0, &a[1].0));
+            assert!(!Arc::ptr_eq(&b[0].0, &b[1].0));
+            assert_eq!((v[0]["a"], v[1]["b"]), (1, 4));
+        }
+
+        let v: HashMap<SharedKey, u32> = crate::from_str("(a:1)").unwrap();
+        assert_eq!(v["a"], 1);
+        let v: Vec<SharedKey> = crate::from_str("!(a,'b c')").unwrap();
+        assert_eq!(v, [SharedKey::from("a"), SharedKey::from("b c")]);
+        let v: SharedKey = serde_json::from_str("\"a\"").unwrap();
+        assert_eq!(v.as_str(), "a");
+    }
+}
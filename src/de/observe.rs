@@ -0,0 +1,219 @@
+//! Observation of the values a deserializer parses, for instrumentation
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Receives an event for each value a deserializer parses, such as to
+/// profile the shape of the Rison a service receives
+///
+/// Configured with
+/// [`DeserializerBuilder::observer`](super::DeserializerBuilder::observer).
+/// Events are reported as each value is read, before it is passed to the
+/// type being deserialized, including for values which are ignored, and
+/// for those read by a [`Parser`](super::Parser). Lists and objects are
+/// reported as they are opened. Implemented for closures, which may record
+/// events with metrics, logs or `tracing` as they see fit.
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// use rison::de::{ParseEvent, ValueKind};
+/// use serde::Deserialize;
+///
+/// let max_depth = Arc::new(AtomicUsize::new(0));
+/// let longest_string = Arc::new(AtomicUsize::new(0));
+/// let builder = rison::Deserializer::builder().observer({
+///     let (max_depth, longest_string) = (max_depth.clone(), longest_string.clone());
+///     move |event: &ParseEvent| {
+///         max_depth.fetch_max(event.depth(), Ordering::Relaxed);
+///         if event.kind() == ValueKind::String {
+///             longest_string.fetch_max(event.byte_len().unwrap_or(0), Ordering::Relaxed);
+///         }
+///     }
+/// });
+///
+/// let input = "(filters:!((field:status,value:'in progress')),limit:20)";
+/// let value = rison::Value::deserialize(&mut builder.from_str(input)).unwrap();
+/// # let _ = value;
+/// assert_eq!(max_depth.load(Ordering::Relaxed), 3);
+/// assert_eq!(longest_string.load(Ordering::Relaxed), 11);
+/// ```
+pub trait ParseObserver: Send + Sync {
+    /// Record that a value has been parsed
+    fn observe(&self, event: &ParseEvent);
+}
+
+impl<F> ParseObserver for F
+where
+    F: Fn(&ParseEvent) + Send + Sync,
+{
+    fn observe(&self, event: &ParseEvent) {
+        self(event)
+    }
+}
+
+/// The kind of a value reported to a [`ParseObserver`], as it is written
+/// in Rison
+///
+/// New kinds may be added, so matches on this enum should include a
+/// wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ValueKind {
+    /// `!n`
+    Null,
+    /// `!t` or `!f`
+    Bool,
+    /// A number
+    Number,
+    /// A string, quoted or not, other than an object key
+    String,
+    /// An object key, quoted or not
+    Key,
+    /// A list, reported as its `!(` is read
+    List,
+    /// An object, reported as its `(` is read
+    Object,
+}
+
+/// A value parsed by a deserializer, reported to a [`ParseObserver`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseEvent {
+    pub(crate) kind: ValueKind,
+    pub(crate) depth: usize,
+    pub(crate) len: Option<usize>,
+}
+
+impl ParseEvent {
+    /// The kind of value parsed
+    pub fn kind(&self) -> ValueKind {
+        self.kind
+    }
+
+    /// The number of lists and objects the value is nested in, which is
+    /// zero for the value at the top level
+    ///
+    /// The key and value of an object entry are both nested in the object.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The length in bytes of a string or key, after unescaping, or of the
+    /// text of a number
+    ///
+    /// This is `None` for other kinds of value, and for quoted strings which
+    /// were skipped without being unescaped, as those of ignored values
+    /// may be.
+    pub fn byte_len(&self) -> Option<usize> {
+        self.len
+    }
+}
+
+/// A shared [`ParseObserver`], held by the options of deserializers built
+/// from the same builder
+#[derive(Clone)]
+pub(crate) struct SharedObserver(pub(crate) Arc<dyn ParseObserver>);
+
+impl fmt::Debug for SharedObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ParseObserver")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use serde::Deserialize;
+
+    use super::{ParseEvent, ValueKind};
+    use crate::de::{DeserializerBuilder, Parser};
+
+    /// A builder recording every event into the returned list
+    fn recording() -> (DeserializerBuilder, Arc<Mutex<Vec<ParseEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let builder = crate::Deserializer::builder().observer({
+            let events = events.clone();
+            move |event: &ParseEvent| events.lock().unwrap().push(*event)
+        });
+        (builder, events)
+    }
+
+    fn event(kind: ValueKind, depth: usize, len: Option<usize>) -> ParseEvent {
+        ParseEvent { kind, depth, len }
+    }
+
+    #[test]
+    fn observe_parsed_values() {
+        use ValueKind::*;
+
+        let input = "(a:!(12,'x!'y',!n),'b c':!t)";
+        let expected = [
+            event(Object, 0, None),
+            event(Key, 1, Some(1)),
+            event(List, 1, None),
+            event(Number, 2, Some(2)),
+            event(String, 2, Some(3)),
+            event(Null, 2, None),
+            event(Key, 1, Some(3)),
+            event(Bool, 1, None),
+        ];
+
+        let (builder, events) = recording();
+        crate::Value::deserialize(&mut builder.from_str(input)).unwrap();
+        assert_eq!(*events.lock().unwrap(), expected);
+
+        let (builder, events) = recording();
+        for event in Parser::new(builder.from_reader(input.as_bytes())) {
+            event.unwrap();
+        }
+        assert_eq!(*events.lock().unwrap(), expected);
+    }
+    #[test]
+    fn observe_ignored_values() {
+        use ValueKind::*;
+
+        #[derive(Deserialize)]
+        struct S {
+            #[allow(dead_code)]
+            a: Option<u8>,
+        }
+
+        let (builder, events) = recording();
+        S::deserialize(&mut builder.from_str("(a:!n,b:!(xyz,'it!'s',(c:-1.5)))")).unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                event(Object, 0, None),
+                event(Key, 1, Some(1)),
+                event(Null, 1, None),
+                event(Key, 1, Some(1)),
+                event(List, 1, None),
+                event(String, 2, Some(3)),
+                event(String, 2, None),
+                event(Object, 2, None),
+                event(Key, 3, Some(1)),
+                event(Number, 3, Some(4)),
+            ]
+        );
+    }
+    #[test]
+    fn observe_json_literals_and_lenient_numbers() {
+        use ValueKind::*;
+
+        let (builder, events) = recording();
+        let builder = builder.json_literals(true).profile(crate::Profile::Lenient);
+        crate::Value::deserialize(&mut builder.from_str("!(true,null,007,2024-01-01)")).unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                event(List, 0, None),
+                event(Bool, 1, None),
+                event(Null, 1, None),
+                event(Number, 1, Some(3)),
+                event(String, 1, Some(10)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use serde::de::{Deserialize, DeserializeOwned, IgnoredAny};
+
+use super::read::Read;
+use super::{DeserializerBuilder, WHITESPACE};
+use crate::error::{Error, ErrorCode, Result};
+
+/// Deserializes a sequence of values from input arriving in pieces, as
+/// frames over a socket
+///
+/// Bytes are appended as they arrive with [`extend`](Self::extend), and
+/// values taken from the front of the buffer with
+/// [`next_value`](Self::next_value), which returns `None` until a complete
+/// value has been buffered. Values may be separated by whitespace, such as
+/// newlines, and must be separated by something when they would otherwise
+/// run together, as numbers and unquoted strings do. Unquoted strings only
+/// end at a space, not at other whitespace, unless
+/// [`allow_whitespace`](DeserializerBuilder::allow_whitespace) is enabled.
+///
+/// ```
+/// let mut partial = rison::de::PartialDeserializer::new();
+///
+/// partial.extend(b"(a:!(1,");
+/// assert_eq!(partial.next_value::<rison::Value>().unwrap(), None);
+///
+/// partial.extend(b"2))\n(a:!())\n");
+/// assert_eq!(partial.next_value::<rison::Value>().unwrap().unwrap().to_string(), "(a:!(1,2))");
+/// assert_eq!(partial.next_value::<rison::Value>().unwrap().unwrap().to_string(), "(a:!())");
+/// assert_eq!(partial.next_value::<rison::Value>().unwrap(), None);
+/// ```
+///
+/// Each call parses the buffered value from its beginning, so values are
+/// best kept small relative to the pieces in which they arrive.
+#[derive(Clone, Debug, Default)]
+pub struct PartialDeserializer {
+    buffer: Vec<u8>,
+    builder: DeserializerBuilder,
+}
+
+impl PartialDeserializer {
+    /// Create a partial deserializer with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a partial deserializer configured by `builder`
+    ///
+    /// [`ParseLimits::max_input_len`](super::ParseLimits::max_input_len)
+    /// limits the length of each value, so bounds the buffer while waiting
+    /// for one to complete.
+    pub fn with_builder(builder: DeserializerBuilder) -> Self {
+        Self {
+            buffer: Vec::new(),
+            builder,
+        }
+    }
+
+    /// Append newly arrived input to the buffer
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The input buffered but not yet deserialized
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Discard all buffered input, as to recover from a syntax error
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Deserialize the next value from the buffer, or return `None` if more
+    /// input is needed to complete it
+    ///
+    /// If the value is valid Rison but not a valid `T`, it is removed from
+    /// the buffer and the error returned, so that following values can
+    /// still be read. If the buffer does not begin with valid Rison, the
+    /// error is returned and the buffer left untouched; the only way on is
+    /// to [`clear`](Self::clear) it. Error positions are relative to the
+    /// start of the value.
+    pub fn next_value<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        let start = self
+            .buffer
+            .iter()
+            .position(|b| !WHITESPACE.contains(b))
+            .unwrap_or(self.buffer.len());
+        self.buffer.drain(..start);
+        let input = &self.buffer[..];
+
+        // Find the extent of the value before deserializing it, to tell
+        // incomplete input apart from invalid values
+        let mut de = self.builder.from_slice(input);
+        match IgnoredAny::deserialize(&mut de) {
+            Ok(_) => {}
+            Err(err) if err.is_eof() => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        let len = de.read.position();
+        if len == 0 {
+            return Err(Error::new(ErrorCode::TrailingChars, Some(0)));
+        }
+        if len == input.len() && !is_delimited(input) {
+            // A number or unquoted string may continue in later input
+            return Ok(None);
+        }
+
+        let value = &input[..len];
+        let result =
+            T::deserialize(&mut self.builder.from_slice(value)).map_err(|err| err.locate(value));
+        self.buffer.drain(..len);
+        result.map(Some)
+    }
+}
+
+/// Whether a complete value ends in a way that cannot be continued
+fn is_delimited(value: &[u8]) -> bool {
+    matches!(value, [.., b')' | b'\''] | [b'!', _])
+}
+
+#[cfg(test)]
+mod test {
+    use super::PartialDeserializer;
+    use crate::Value;
+
+    fn next(partial: &mut PartialDeserializer) -> Option<String> {
+        partial
+            .next_value::<Value>()
+            .unwrap()
+            .map(|value| value.to_string())
+    }
+
+    #[cfg(any(feature = "preserve_order", not(feature = "unordered_map")))]
+    #[test]
+    fn deserialize_values_in_pieces() {
+        let mut partial = PartialDeserializer::new();
+        let input = b"(a:'x!'y',b:!(1,2))\n!t\n42 abc ";
+        let mut values = Vec::new();
+        for piece in input.chunks(3) {
+            partial.extend(piece);
+            while let Some(value) = next(&mut partial) {
+                values.push(value);
+            }
+        }
+
+        assert_eq!(values, ["(a:'x!'y',b:!(1,2))", "!t", "42", "abc"]);
+        assert!(partial.buffered().is_empty());
+    }
+    #[test]
+    fn wait_for_undelimited_values() {
+        let mut partial = PartialDeserializer::new();
+
+        partial.extend(b"12");
+        assert_eq!(next(&mut partial), None);
+        partial.extend(b"3 ");
+        assert_eq!(next(&mut partial).as_deref(), Some("123"));
+        partial.extend(b"!n");
+        assert_eq!(next(&mut partial).as_deref(), Some("!n"));
+    }
+    #[test]
+    fn fail_deserialize_values_in_pieces() {
+        let mut partial = PartialDeserializer::new();
+
+        partial.extend(b"(a:x) !(1,2) ");
+        assert!(partial.next_value::<Vec<u8>>().unwrap_err().is_data());
+        assert_eq!(partial.next_value::<Vec<u8>>().unwrap(), Some(vec![1, 2]));
+
+        partial.extend(b"!x");
+        assert!(partial.next_value::<Value>().unwrap_err().is_syntax());
+        assert_eq!(partial.buffered(), b"!x");
+        partial.clear();
+        assert_eq!(next(&mut partial), None);
+    }
+}
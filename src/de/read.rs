@@ -1,9 +1,57 @@
-use crate::error::{Code, Error, Result};
+//! Sources of input for a [`Deserializer`](super::Deserializer)
+//!
+//! The deserializer reads through the [`Read`] trait, implemented here for
+//! slices, strings, `io::BufRead`s, and iterators of bytes (which cover
+//! other `io::Read`s and URL query components). Other sources, such as memory-mapped files, ring
+//! buffers or decrypting streams, may implement it themselves and be read
+//! with [`Deserializer::new`](super::Deserializer::new).
+//!
+//! # Stability
+//!
+//! `Read` is more closely tied to the workings of the parser than the rest
+//! of the API. Methods may be added to it in minor releases as the parser
+//! gains features, though where possible they will have default
+//! implementations.
 
-const NOT_ID_CHARS: &[u8] = b" '!:(),*@$";
+use crate::error::{Error, ErrorCode, Location, Result};
 
+/// Characters which may not appear in an unquoted string
+pub(crate) const NOT_ID_CHARS: &[u8] = b" '!:(),*@$";
+
+/// Characters reserved by the Rison grammar, which have no meaning of their
+/// own but may not appear in an unquoted string
+pub(crate) const RESERVED_CHARS: &[u8] = b"*@";
+
+/// A set of bytes, tested in constant time
+///
+/// Passed to [`Read::parse_ident`] and [`Read::ignore_ident`] as the bytes
+/// which terminate an unquoted string.
+#[derive(Clone, Debug)]
+pub struct ByteSet([bool; 256]);
+
+impl ByteSet {
+    pub(crate) fn new(bytes: &[u8]) -> Self {
+        let mut set = [false; 256];
+        for &b in bytes {
+            set[usize::from(b)] = true;
+        }
+        ByteSet(set)
+    }
+
+    /// Whether `b` is in the set
+    #[inline]
+    pub fn contains(&self, b: u8) -> bool {
+        self.0[usize::from(b)]
+    }
+}
+
+/// A string or slice returned by a [`Read`], either borrowed from the input
+/// itself or copied into the deserializer's scratch buffer
 pub enum Reference<'b, 'c, T: ?Sized> {
+    /// Borrowed from the input for its whole lifetime `'b`, allowing
+    /// zero-copy deserialization
     Borrowed(&'b T),
+    /// Copied into the scratch buffer, and valid only until it is next used
     Copied(&'c T),
 }
 
@@ -25,7 +73,32 @@ impl<'b, 'c, T: ?Sized> Reference<'b, 'c, T> {
     }
 }
 
+impl<T: ?Sized> std::ops::Deref for Reference<'_, '_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match *self {
+            Reference::Borrowed(b) => b,
+            Reference::Copied(c) => c,
+        }
+    }
+}
+
+/// A source of input for a [`Deserializer`](super::Deserializer)
+///
+/// Input is consumed a byte at a time through [`peek`](Read::peek) and
+/// [`discard`](Read::discard), except for strings, which are consumed whole
+/// so that sources holding their entire input may borrow from it. Errors
+/// should be created with [`Error::at`], at the position where they were
+/// detected.
+///
+/// It is implemented for references and boxes of sources too, so that a
+/// `Box<dyn Read<'de>>` may stand in for any source, as in a
+/// [`DynDeserializer`](super::DynDeserializer).
+///
+/// See the [module documentation](self) for a note on stability.
 pub trait Read<'de> {
+    /// Consume and return the next byte, or `None` at the end of the input
     fn next(&mut self) -> Result<Option<u8>> {
         let next = self.peek()?;
         if next.is_some() {
@@ -33,25 +106,159 @@ pub trait Read<'de> {
         }
         Ok(next)
     }
+    /// Return the next byte without consuming it, or `None` at the end of
+    /// the input
     fn peek(&mut self) -> Result<Option<u8>>;
+    /// Consume the byte most recently returned by [`peek`](Read::peek)
     fn discard(&mut self);
-    // TODO: scratch and zero-copy optimisations
+    /// Parse a quoted string, following its opening `'`, up to and
+    /// consuming its closing `'`
+    ///
+    /// `!!` and `!'` are unescaped; `!` followed by anything else fails with
+    /// [`ErrorCode::InvalidEscape`] at the `!`, and the input ending before the closing
+    /// `'` with [`ErrorCode::EofString`]. Sources which cannot borrow the
+    /// string from their input should copy it into `scratch`, which is empty
+    /// on entry.
     fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>>;
-    // TODO: scratch and zero-copy optimisations
-    fn parse_ident<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>>;
+    /// Parse a quoted string as [`parse_str`](Read::parse_str) does, passing
+    /// it to `visit` in one or more chunks rather than returning it whole
+    ///
+    /// The chunks are valid utf-8, and together make up the unescaped
+    /// string. Sources which would copy a long string into `scratch` may
+    /// instead pass it on as they read it, keeping only part of it at a
+    /// time. By default, the result of `parse_str` is passed as one chunk.
+    fn parse_str_visit(
+        &mut self,
+        scratch: &mut Vec<u8>,
+        visit: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<()> {
+        let s = self.parse_str(scratch)?;
+        visit(&s)
+    }
+    /// Parse an unquoted string, terminated by any of `not_id_chars` or the
+    /// end of the input, which are not consumed
+    ///
+    /// As with [`parse_str`](Read::parse_str), `scratch` is empty on entry.
+    fn parse_ident<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        not_id_chars: &ByteSet,
+    ) -> Result<Reference<'de, 's, str>>;
+    /// The number of bytes consumed so far
     fn position(&mut self) -> usize;
+    /// The line and column of the byte at `position`, which has been read
+    /// or peeked, for reporting errors
+    ///
+    /// Sources holding their entire input may compute this on demand, while
+    /// streaming sources may count lines as they read and locate only
+    /// positions on the current line. Returns `None` unless implemented.
+    fn location(&mut self, position: usize) -> Option<Location> {
+        let _ = position;
+        None
+    }
+    /// The entire input, for sources which hold it in memory unmodified
+    ///
+    /// Errors use it to suggest fixes for common mistakes, reported by
+    /// [`Error::hint`]. Returns `None` unless implemented.
+    fn input(&self) -> Option<&[u8]> {
+        None
+    }
+    /// Consume a quoted string, following its opening `'`, without
+    /// allocating or validating its contents as utf-8
+    fn ignore_str(&mut self) -> Result<()>;
+    /// Consume an unquoted string, terminated by any of `not_id_chars`,
+    /// without allocating or validating it as utf-8
+    fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()>;
+    /// Begin recording consumed input, for capturing a [`RawValue`]
+    ///
+    /// [`RawValue`]: crate::value::RawValue
+    fn begin_raw_buffering(&mut self);
+    /// Stop recording consumed input and return the input consumed since
+    /// the matching call to [`Read::begin_raw_buffering`]
+    fn end_raw_buffering(&mut self) -> Result<Reference<'de, '_, str>>;
 }
 
+/// Forward every method of [`Read`], including those with defaults, to the
+/// source a reference or box points to
+macro_rules! forward_read {
+    ($($ty:ty),*) => {
+        $(
+            impl<'de, R: ?Sized + Read<'de>> Read<'de> for $ty {
+                fn next(&mut self) -> Result<Option<u8>> {
+                    (**self).next()
+                }
+                fn peek(&mut self) -> Result<Option<u8>> {
+                    (**self).peek()
+                }
+                fn discard(&mut self) {
+                    (**self).discard()
+                }
+                fn parse_str<'s>(
+                    &'s mut self,
+                    scratch: &'s mut Vec<u8>,
+                ) -> Result<Reference<'de, 's, str>> {
+                    (**self).parse_str(scratch)
+                }
+                fn parse_str_visit(
+                    &mut self,
+                    scratch: &mut Vec<u8>,
+                    visit: &mut dyn FnMut(&str) -> Result<()>,
+                ) -> Result<()> {
+                    (**self).parse_str_visit(scratch, visit)
+                }
+                fn parse_ident<'s>(
+                    &'s mut self,
+                    scratch: &'s mut Vec<u8>,
+                    not_id_chars: &ByteSet,
+                ) -> Result<Reference<'de, 's, str>> {
+                    (**self).parse_ident(scratch, not_id_chars)
+                }
+                fn position(&mut self) -> usize {
+                    (**self).position()
+                }
+                fn location(&mut self, position: usize) -> Option<Location> {
+                    (**self).location(position)
+                }
+                fn input(&self) -> Option<&[u8]> {
+                    (**self).input()
+                }
+                fn ignore_str(&mut self) -> Result<()> {
+                    (**self).ignore_str()
+                }
+                fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()> {
+                    (**self).ignore_ident(not_id_chars)
+                }
+                fn begin_raw_buffering(&mut self) {
+                    (**self).begin_raw_buffering()
+                }
+                fn end_raw_buffering(&mut self) -> Result<Reference<'de, '_, str>> {
+                    (**self).end_raw_buffering()
+                }
+            }
+        )*
+    };
+}
+
+forward_read!(&mut R, Box<R>);
+
+/// Reads input from a slice of bytes, borrowing strings from it where
+/// possible
 pub struct SliceRead<'a> {
     slice: &'a [u8],
     /// Index of the *next* byte that will be returned by next() or peek().
     index: usize,
+    /// Index of the first byte of the raw value being captured
+    raw_buffering_start_index: usize,
 }
 
 impl<'a> SliceRead<'a> {
-    /// Create a JSON input source to read from a slice of bytes.
+    /// Create a Rison input source to read from a slice of bytes
     pub fn new(slice: &'a [u8]) -> Self {
-        SliceRead { slice, index: 0 }
+        SliceRead {
+            slice,
+            index: 0,
+            raw_buffering_start_index: 0,
+        }
     }
 
     /// Parse a string from the input until a close-string delimiter
@@ -66,11 +273,12 @@ impl<'a> SliceRead<'a> {
     ) -> Result<Reference<'a, 's, [u8]>> {
         let mut start = self.index;
         loop {
-            if self.index == self.slice.len() {
-                return Err(Error {
-                    code: Code::EofString,
-                    position: self.position().into(),
-                });
+            match memchr::memchr2(b'\'', b'!', &self.slice[self.index..]) {
+                Some(offset) => self.index += offset,
+                None => {
+                    self.index = self.slice.len();
+                    return Err(Error::new(ErrorCode::EofString, self.position().into()));
+                }
             }
             match self.slice[self.index] {
                 b'\'' => {
@@ -84,28 +292,26 @@ impl<'a> SliceRead<'a> {
                         return Ok(Reference::Copied(scratch));
                     }
                 }
-                b'!' => {
+                // An escape, as the only other character searched for
+                _ => {
                     scratch.extend_from_slice(&self.slice[start..self.index]);
                     self.index += 1;
                     scratch.push(
-                        match self.next()?.ok_or(Error {
-                            code: Code::EofString,
-                            position: self.position().into(),
-                        })? {
+                        match self
+                            .next()?
+                            .ok_or(Error::new(ErrorCode::EofString, self.position().into()))?
+                        {
                             c @ (b'!' | b'\'') => c,
                             _ => {
-                                return Err(Error {
-                                    code: Code::InvalidEscape,
-                                    position: self.position().into(),
-                                })
+                                return Err(Error::new(
+                                    ErrorCode::InvalidEscape,
+                                    (self.position() - 2).into(),
+                                ))
                             }
                         },
                     );
                     start = self.index;
                 }
-                _ => {
-                    self.index += 1;
-                }
             }
         }
     }
@@ -116,14 +322,50 @@ impl<'a> SliceRead<'a> {
     /// safety elsewhere relies on the guarantee provided by this method that
     /// it will not transform the input stream such that valid utf-8 in the
     /// input becomes invalid in the output.
-    fn parse_ident_bytes(&mut self) -> Result<&'a [u8]> {
+    fn parse_ident_bytes(&mut self, not_id_chars: &ByteSet) -> Result<&'a [u8]> {
         let start = self.index;
-        while self.index < self.slice.len() && !NOT_ID_CHARS.contains(&self.slice[self.index]) {
-            self.index += 1;
-        }
+        let len = self.slice[start..]
+            .iter()
+            .position(|&b| not_id_chars.contains(b))
+            .unwrap_or(self.slice.len() - start);
+        self.index += len;
 
         Ok(&self.slice[start..self.index])
     }
+
+    fn ignore_str_bytes(&mut self) -> Result<()> {
+        loop {
+            let rest = &self.slice[self.index..];
+            self.index += memchr::memchr2(b'\'', b'!', rest).unwrap_or(rest.len());
+            match self.slice.get(self.index) {
+                None => {
+                    return Err(Error::new(ErrorCode::EofString, self.position().into()));
+                }
+                Some(b'\'') => {
+                    self.index += 1;
+                    return Ok(());
+                }
+                Some(b'!') => {
+                    self.index += 1;
+                    match self.next()? {
+                        Some(b'!' | b'\'') => {}
+                        Some(_) => {
+                            return Err(Error::new(
+                                ErrorCode::InvalidEscape,
+                                (self.position() - 2).into(),
+                            ))
+                        }
+                        None => {
+                            return Err(Error::new(ErrorCode::EofString, self.position().into()))
+                        }
+                    }
+                }
+                Some(_) => {
+                    self.index += 1;
+                }
+            }
+        }
+    }
 }
 
 impl<'a> Read<'a> for SliceRead<'a> {
@@ -143,19 +385,27 @@ impl<'a> Read<'a> for SliceRead<'a> {
     fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'a, 's, str>> {
         let start_position = self.position();
         let bytes = self.parse_str_bytes(scratch)?;
-        bytes.try_map(std::str::from_utf8).map_err(|e| Error {
-            code: Code::InvalidUnicode,
-            position: (start_position + e.valid_up_to()).into(),
+        bytes.try_map(std::str::from_utf8).map_err(|e| {
+            Error::new(
+                ErrorCode::InvalidUnicode,
+                (start_position + e.valid_up_to()).into(),
+            )
         })
     }
-    fn parse_ident<'s>(&'s mut self, _scratch: &'s mut Vec<u8>) -> Result<Reference<'a, 's, str>> {
+    fn parse_ident<'s>(
+        &'s mut self,
+        _scratch: &'s mut Vec<u8>,
+        not_id_chars: &ByteSet,
+    ) -> Result<Reference<'a, 's, str>> {
         let start_position = self.position();
-        let bytes = self.parse_ident_bytes()?;
+        let bytes = self.parse_ident_bytes(not_id_chars)?;
 
         std::str::from_utf8(bytes)
-            .map_err(|e| Error {
-                code: Code::InvalidUnicode,
-                position: (start_position + e.valid_up_to()).into(),
+            .map_err(|e| {
+                Error::new(
+                    ErrorCode::InvalidUnicode,
+                    (start_position + e.valid_up_to()).into(),
+                )
             })
             .map(Reference::Copied)
     }
@@ -163,14 +413,48 @@ impl<'a> Read<'a> for SliceRead<'a> {
     fn position(&mut self) -> usize {
         self.index
     }
+
+    fn location(&mut self, position: usize) -> Option<Location> {
+        Some(Location::of(self.slice, position))
+    }
+
+    fn input(&self) -> Option<&[u8]> {
+        Some(self.slice)
+    }
+
+    fn ignore_str(&mut self) -> Result<()> {
+        self.ignore_str_bytes()
+    }
+
+    fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()> {
+        self.parse_ident_bytes(not_id_chars).map(|_| ())
+    }
+
+    fn begin_raw_buffering(&mut self) {
+        self.raw_buffering_start_index = self.index;
+    }
+
+    fn end_raw_buffering(&mut self) -> Result<Reference<'a, '_, str>> {
+        let start_position = self.raw_buffering_start_index;
+        let raw = &self.slice[start_position..self.index];
+        std::str::from_utf8(raw)
+            .map_err(|e| {
+                Error::new(
+                    ErrorCode::InvalidUnicode,
+                    (start_position + e.valid_up_to()).into(),
+                )
+            })
+            .map(Reference::Borrowed)
+    }
 }
 
+/// Reads input from a string, borrowing strings from it where possible
 pub struct StrRead<'a> {
     delegate: SliceRead<'a>,
 }
 
 impl<'a> StrRead<'a> {
-    /// Create a JSON input source to read from a slice of bytes.
+    /// Create a Rison input source to read from a string
     pub fn new(s: &'a str) -> Self {
         StrRead {
             delegate: SliceRead::new(s.as_bytes()),
@@ -195,57 +479,404 @@ impl<'a> Read<'a> for StrRead<'a> {
         // input such that valid utf-8 becomes invalid. StrRead's buffer
         // is guaranteed to be valid utf-8 by construction. The resulting
         // buffer is therefore valid utf-8, satisfying the safety preconditions
-        // of `String::from_utf8_unchecked`
-        Ok(bytes.map(|b| unsafe { std::str::from_utf8_unchecked(b) }))
+        // of `str_from_valid_utf8`
+        Ok(bytes.map(str_from_valid_utf8))
     }
-    fn parse_ident<'s>(&'s mut self, _scratch: &'s mut Vec<u8>) -> Result<Reference<'a, 's, str>> {
-        let bytes = self.delegate.parse_ident_bytes()?;
+    fn parse_ident<'s>(
+        &'s mut self,
+        _scratch: &'s mut Vec<u8>,
+        not_id_chars: &ByteSet,
+    ) -> Result<Reference<'a, 's, str>> {
+        let bytes = self.delegate.parse_ident_bytes(not_id_chars)?;
 
         // # Safety
         // `parse_ident_bytes` guarantees it will not transform
         // input such that valid utf-8 becomes invalid. StrRead's buffer
         // is guaranteed to be valid utf-8 by construction. The resulting
         // buffer is therefore valid utf-8, satisfying the safety preconditions
-        // of `String::from_utf8_unchecked`.
-        Ok(Reference::Borrowed(unsafe {
-            std::str::from_utf8_unchecked(bytes)
-        }))
+        // of `str_from_valid_utf8`.
+        Ok(Reference::Borrowed(str_from_valid_utf8(bytes)))
     }
 
     fn position(&mut self) -> usize {
         self.delegate.position()
     }
+
+    fn location(&mut self, position: usize) -> Option<Location> {
+        self.delegate.location(position)
+    }
+
+    fn input(&self) -> Option<&[u8]> {
+        self.delegate.input()
+    }
+
+    fn ignore_str(&mut self) -> Result<()> {
+        self.delegate.ignore_str()
+    }
+
+    fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()> {
+        self.delegate.ignore_ident(not_id_chars)
+    }
+
+    fn begin_raw_buffering(&mut self) {
+        self.delegate.begin_raw_buffering()
+    }
+
+    fn end_raw_buffering(&mut self) -> Result<Reference<'a, '_, str>> {
+        let raw = &self.delegate.slice[self.delegate.raw_buffering_start_index..self.position()];
+
+        // # Safety
+        // StrRead's buffer is guaranteed to be valid utf-8 by construction, and
+        // raw values begin and end at ASCII delimiters or the ends of the input,
+        // which are char boundaries.
+        Ok(Reference::Borrowed(str_from_valid_utf8(raw)))
+    }
+}
+
+/// Convert part of the input of a [`StrRead`] to a `str`
+///
+/// `bytes` must be valid utf-8. Parts of a `StrRead`'s input split at ASCII
+/// characters, and unescaped only by removing ASCII characters, always are.
+/// Without the `unchecked_utf8` feature this is checked, and panics instead.
+#[cfg(feature = "unchecked_utf8")]
+fn str_from_valid_utf8(bytes: &[u8]) -> &str {
+    unsafe { std::str::from_utf8_unchecked(bytes) }
+}
+
+#[cfg(not(feature = "unchecked_utf8"))]
+fn str_from_valid_utf8(bytes: &[u8]) -> &str {
+    std::str::from_utf8(bytes).expect("`StrRead` input is valid utf-8")
+}
+
+/// Reads input from a mutable slice of bytes, unescaping quoted strings in
+/// place so that they can always be borrowed from it
+///
+/// The contents of the slice after reading are unspecified.
+pub struct MutSliceRead<'a> {
+    /// The input not yet consumed, preceded while capturing a raw value by
+    /// the input consumed since it began
+    slice: &'a mut [u8],
+    /// The length of the raw value being captured at the start of `slice`
+    index: usize,
+    position: usize,
+    /// Whether a raw value is being captured, so the input must be left as
+    /// it is
+    raw_buffering: bool,
+}
+
+impl<'a> MutSliceRead<'a> {
+    /// Create a Rison input source to read from a mutable slice of bytes
+    pub fn new(slice: &'a mut [u8]) -> Self {
+        MutSliceRead {
+            slice,
+            index: 0,
+            position: 0,
+            raw_buffering: false,
+        }
+    }
+
+    fn rest(&self) -> &[u8] {
+        &self.slice[self.index..]
+    }
+
+    /// Consume the next `len` bytes of input, returning them unless they
+    /// are part of a raw value being captured
+    fn consume(&mut self, len: usize) -> Option<&'a mut [u8]> {
+        self.position += len;
+        if self.raw_buffering {
+            self.index += len;
+            return None;
+        }
+        let (consumed, rest) = std::mem::take(&mut self.slice).split_at_mut(len);
+        self.slice = rest;
+        Some(consumed)
+    }
+
+    /// Find the closing `'` of a quoted string, following its opening `'`,
+    /// checking its escapes
+    fn find_str_end(&self) -> Result<usize> {
+        let rest = self.rest();
+        let mut index = 0;
+        loop {
+            match memchr::memchr2(b'\'', b'!', &rest[index..]) {
+                Some(offset) => index += offset,
+                None => {
+                    return Err(Error::new(
+                        ErrorCode::EofString,
+                        (self.position + rest.len()).into(),
+                    ))
+                }
+            }
+            if rest[index] == b'\'' {
+                return Ok(index);
+            }
+            // An escape, as the only other character searched for
+            match rest.get(index + 1) {
+                Some(b'!' | b'\'') => index += 2,
+                Some(_) => {
+                    return Err(Error::new(
+                        ErrorCode::InvalidEscape,
+                        (self.position + index).into(),
+                    ))
+                }
+                None => {
+                    return Err(Error::new(
+                        ErrorCode::EofString,
+                        (self.position + index + 1).into(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// The length of the unquoted string at the start of the input
+    fn ident_len(&self, not_id_chars: &ByteSet) -> usize {
+        let rest = self.rest();
+        rest.iter()
+            .position(|&b| not_id_chars.contains(b))
+            .unwrap_or(rest.len())
+    }
 }
 
-pub struct IoRead<I> {
-    io: std::io::Bytes<I>,
+/// Remove the escaping `!`s from a quoted string whose escapes have been
+/// checked, returning the length of the unescaped string
+fn unescape_in_place(bytes: &mut [u8]) -> usize {
+    let mut read = 0;
+    let mut write = 0;
+    while let Some(offset) = memchr::memchr(b'!', &bytes[read..]) {
+        bytes.copy_within(read..read + offset, write);
+        write += offset;
+        bytes[write] = bytes[read + offset + 1];
+        write += 1;
+        read += offset + 2;
+    }
+    bytes.copy_within(read.., write);
+    write + bytes.len() - read
+}
+
+/// Interpret bytes read from `position` as utf-8
+fn from_utf8(bytes: &[u8], position: usize) -> Result<&str> {
+    std::str::from_utf8(bytes).map_err(|e| {
+        Error::new(
+            ErrorCode::InvalidUnicode,
+            (position + e.valid_up_to()).into(),
+        )
+    })
+}
+
+impl<'a> Read<'a> for MutSliceRead<'a> {
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.rest().first().copied())
+    }
+
+    fn discard(&mut self) {
+        if !self.rest().is_empty() {
+            self.consume(1);
+        }
+    }
+
+    fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'a, 's, str>> {
+        let start_position = self.position;
+        let len = self.find_str_end()?;
+        if self.raw_buffering {
+            scratch.extend_from_slice(&self.rest()[..len]);
+            let unescaped = unescape_in_place(scratch);
+            scratch.truncate(unescaped);
+            self.consume(len + 1);
+            return from_utf8(scratch, start_position).map(Reference::Copied);
+        }
+
+        let consumed = self.consume(len + 1).expect("not capturing a raw value");
+        let string = &mut consumed[..len];
+        let unescaped = unescape_in_place(string);
+        let string: &'a [u8] = &string[..unescaped];
+        from_utf8(string, start_position).map(Reference::Borrowed)
+    }
+
+    fn parse_ident<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        not_id_chars: &ByteSet,
+    ) -> Result<Reference<'a, 's, str>> {
+        let start_position = self.position;
+        let len = self.ident_len(not_id_chars);
+        if self.raw_buffering {
+            scratch.extend_from_slice(&self.rest()[..len]);
+            self.consume(len);
+            return from_utf8(scratch, start_position).map(Reference::Copied);
+        }
+
+        let ident: &'a [u8] = self.consume(len).expect("not capturing a raw value");
+        from_utf8(ident, start_position).map(Reference::Borrowed)
+    }
+
+    fn position(&mut self) -> usize {
+        self.position
+    }
+
+    fn ignore_str(&mut self) -> Result<()> {
+        let len = self.find_str_end()?;
+        self.consume(len + 1);
+        Ok(())
+    }
+
+    fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()> {
+        self.consume(self.ident_len(not_id_chars));
+        Ok(())
+    }
+
+    fn begin_raw_buffering(&mut self) {
+        self.raw_buffering = true;
+    }
+
+    fn end_raw_buffering(&mut self) -> Result<Reference<'a, '_, str>> {
+        self.raw_buffering = false;
+        let (raw, rest) = std::mem::take(&mut self.slice).split_at_mut(self.index);
+        self.slice = rest;
+        let start_position = self.position - self.index;
+        self.index = 0;
+        let raw: &'a [u8] = raw;
+        from_utf8(raw, start_position).map(Reference::Borrowed)
+    }
+}
+
+/// Reads input from an iterator of bytes, each of which may be an IO error
+pub struct IterRead<I> {
+    iter: I,
     peeked: Option<u8>,
     position: usize,
+    /// The one-based number of the line being read
+    line: usize,
+    /// The position at which the line being read began
+    line_start: usize,
+    /// The number of bytes after which reading fails rather than continuing
+    max_len: usize,
+    /// Whether consumed input is being captured into `raw_buffer`
+    raw_buffering: bool,
+    /// Input consumed while capturing a raw value
+    raw_buffer: Vec<u8>,
+}
+
+/// Reads input from an `io::Read`, byte by byte
+pub type IoRead<R> = IterRead<std::io::Bytes<R>>;
+
+/// An iterator of bytes which cannot fail, adapted for [`IterRead`]
+pub type InfallibleIter<I> = std::iter::Map<I, fn(u8) -> std::io::Result<u8>>;
+
+/// Iterate over the bytes of a reader
+// Unbuffered readers are the caller's responsibility to wrap, as with
+// `serde_json::from_reader`
+#[allow(clippy::unbuffered_bytes)]
+pub(crate) fn bytes<R: std::io::Read>(reader: R) -> std::io::Bytes<R> {
+    reader.bytes()
 }
 
-impl<I: std::io::Read> IoRead<I> {
-    pub fn new(reader: I) -> Self {
-        IoRead {
-            io: reader.bytes(),
+/// Adapt an iterator of bytes which cannot fail for [`IterRead`]
+pub(crate) fn infallible<I: Iterator<Item = u8>>(iter: I) -> InfallibleIter<I> {
+    iter.map(Ok)
+}
+
+impl<I> IterRead<I>
+where
+    I: Iterator<Item = std::io::Result<u8>>,
+{
+    /// Create a Rison input source to read from an iterator of bytes
+    pub fn new(iter: I) -> Self {
+        Self::with_max_len(iter, usize::MAX)
+    }
+
+    /// Create a reader which fails rather than read more than `max_len` bytes
+    pub(crate) fn with_max_len(iter: I, max_len: usize) -> Self {
+        IterRead {
+            iter,
             peeked: None,
             position: 0,
+            line: 1,
+            line_start: 0,
+            max_len,
+            raw_buffering: false,
+            raw_buffer: Vec::new(),
         }
     }
+
+    /// Fail if any input remains beyond the maximum length
+    #[cold]
+    fn peek_beyond_max_len(&mut self) -> Result<Option<u8>> {
+        match self.iter.next() {
+            None => Ok(None),
+            Some(Err(e)) => Err(Error::new(ErrorCode::Io(e), self.position.into())),
+            Some(Ok(_)) => Err(Error::new(
+                ErrorCode::InputLimitExceeded,
+                self.position.into(),
+            )),
+        }
+    }
+}
+
+/// Reads input from a component of a URL query string, decoding it as
+/// `application/x-www-form-urlencoded`
+pub type UrlEncodedRead<'a> = IterRead<UrlDecode<'a>>;
+
+/// Decodes `+` as a space, and `%` followed by two hex digits as the byte
+/// they encode
+///
+/// Other `%` characters are passed through, as by the WHATWG URL standard.
+/// Decoding cannot fail, but yields `io::Result`s for use by [`IterRead`].
+pub struct UrlDecode<'a> {
+    bytes: std::slice::Iter<'a, u8>,
 }
 
-impl<'de, I> Read<'de> for IoRead<I>
+impl<'a> UrlDecode<'a> {
+    pub(crate) fn new(component: &'a str) -> Self {
+        UrlDecode {
+            bytes: component.as_bytes().iter(),
+        }
+    }
+}
+
+impl Iterator for UrlDecode<'_> {
+    type Item = std::io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        fn hex(b: &u8) -> Option<u8> {
+            char::from(*b).to_digit(16).map(|d| d as u8)
+        }
+
+        let decoded = match *self.bytes.next()? {
+            b'+' => b' ',
+            b'%' => {
+                let rest = self.bytes.as_slice();
+                match (rest.first().and_then(hex), rest.get(1).and_then(hex)) {
+                    (Some(high), Some(low)) => {
+                        self.bytes.nth(1);
+                        high << 4 | low
+                    }
+                    _ => b'%',
+                }
+            }
+            b => b,
+        };
+        Some(Ok(decoded))
+    }
+}
+
+impl<'de, I> Read<'de> for IterRead<I>
 where
-    I: std::io::Read,
+    I: Iterator<Item = std::io::Result<u8>>,
 {
     fn peek(&mut self) -> Result<Option<u8>> {
         if let Some(ch) = self.peeked {
             return Ok(Some(ch));
         }
+        if self.position >= self.max_len {
+            return self.peek_beyond_max_len();
+        }
 
-        let ch = self.io.next().transpose().map_err(|e| Error {
-            code: Code::Io(e),
-            position: self.position().into(),
-        })?;
+        let ch = self
+            .iter
+            .next()
+            .transpose()
+            .map_err(|e| Error::new(ErrorCode::Io(e), self.position().into()))?;
 
         self.peeked = ch;
 
@@ -253,74 +884,652 @@ where
     }
 
     fn discard(&mut self) {
+        if let (true, Some(ch)) = (self.raw_buffering, self.peeked) {
+            self.raw_buffer.push(ch);
+        }
+        if self.peeked == Some(b'\n') {
+            self.line += 1;
+            self.line_start = self.position + 1;
+        }
         self.peeked = None;
         self.position += 1;
     }
 
     fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
-        let start_position = self.position();
-        loop {
-            let Some(ch) = self.peek()? else {
-                return Err(Error {
-                    code: Code::EofString,
-                    position: self.position().into(),
+        copy_str(self, scratch).map(Reference::Copied)
+    }
+
+    fn parse_str_visit(
+        &mut self,
+        scratch: &mut Vec<u8>,
+        visit: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<()> {
+        visit_str(self, scratch, visit)
+    }
+
+    fn parse_ident<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        not_id_chars: &ByteSet,
+    ) -> Result<Reference<'de, 's, str>> {
+        copy_ident(self, scratch, not_id_chars).map(Reference::Copied)
+    }
+
+    fn position(&mut self) -> usize {
+        self.position
+    }
+
+    /// Lines are counted as they are read, so only positions on the current
+    /// line can be located
+    fn location(&mut self, position: usize) -> Option<Location> {
+        (self.line_start..=self.position)
+            .contains(&position)
+            .then(|| Location::new(self.line, position - self.line_start + 1))
+    }
+
+    fn ignore_str(&mut self) -> Result<()> {
+        skip_str(self)
+    }
+
+    fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()> {
+        skip_ident(self, not_id_chars)
+    }
+
+    fn begin_raw_buffering(&mut self) {
+        self.raw_buffering = true;
+        self.raw_buffer.clear();
+    }
+
+    fn end_raw_buffering(&mut self) -> Result<Reference<'de, '_, str>> {
+        self.raw_buffering = false;
+        let start_position = self.position - self.raw_buffer.len();
+        std::str::from_utf8(&self.raw_buffer)
+            .map_err(|e| {
+                Error::new(
+                    ErrorCode::InvalidUnicode,
+                    (start_position + e.valid_up_to()).into(),
+                )
+            })
+            .map(Reference::Copied)
+    }
+}
+
+/// Parse a quoted string a byte at a time, following its opening `'`,
+/// copying it into `scratch`
+fn copy_str<'de, 's, R>(read: &mut R, scratch: &'s mut Vec<u8>) -> Result<&'s str>
+where
+    R: ?Sized + Read<'de>,
+{
+    let start_position = read.position();
+    loop {
+        let Some(ch) = read.peek()? else {
+            return Err(Error::new(ErrorCode::EofString, read.position().into()));
+        };
+
+        match ch {
+            b'\'' => {
+                read.discard();
+                return std::str::from_utf8(scratch).map_err(|e| {
+                    Error::new(
+                        ErrorCode::InvalidUnicode,
+                        (start_position + e.valid_up_to()).into(),
+                    )
                 });
-            };
+            }
+            b'!' => {
+                read.discard();
+                scratch.push(
+                    match read
+                        .next()?
+                        .ok_or(Error::new(ErrorCode::EofString, read.position().into()))?
+                    {
+                        c @ (b'!' | b'\'') => c,
+                        _ => {
+                            return Err(Error::new(
+                                ErrorCode::InvalidEscape,
+                                (read.position() - 2).into(),
+                            ))
+                        }
+                    },
+                );
+            }
+            _ => {
+                scratch.push(ch);
+                read.discard();
+            }
+        }
+    }
+}
 
-            match ch {
-                b'\'' => {
-                    self.discard();
-                    return std::str::from_utf8(scratch)
-                        .map_err(|e| Error {
-                            code: Code::InvalidUnicode,
-                            position: (start_position + e.valid_up_to()).into(),
-                        })
-                        .map(Reference::Copied);
-                }
-                b'!' => {
-                    self.discard();
-                    scratch.push(
-                        match self.next()?.ok_or(Error {
-                            code: Code::EofString,
-                            position: self.position().into(),
-                        })? {
-                            c @ (b'!' | b'\'') => c,
-                            _ => {
-                                return Err(Error {
-                                    code: Code::InvalidMarker,
-                                    position: self.position().into(),
-                                })
-                            }
-                        },
-                    );
-                }
-                _ => {
-                    scratch.push(ch);
-                    self.discard();
-                }
+/// The length of unescaped string at which [`visit_str`] passes on what it
+/// has read
+const STR_CHUNK_LEN: usize = 8 * 1024;
+
+/// Parse a quoted string a byte at a time, following its opening `'`,
+/// passing it to `visit` a chunk at a time as it fills `scratch`
+fn visit_str<'de, R>(
+    read: &mut R,
+    scratch: &mut Vec<u8>,
+    visit: &mut dyn FnMut(&str) -> Result<()>,
+) -> Result<()>
+where
+    R: ?Sized + Read<'de>,
+{
+    let start_position = read.position();
+    // The length of the string already passed to `visit`
+    let mut visited = 0;
+    loop {
+        let Some(ch) = read.peek()? else {
+            return Err(Error::new(ErrorCode::EofString, read.position().into()));
+        };
+
+        match ch {
+            b'\'' => {
+                read.discard();
+                return visit_chunk(scratch, start_position + visited, visit, true).map(drop);
             }
+            b'!' => {
+                read.discard();
+                scratch.push(
+                    match read
+                        .next()?
+                        .ok_or(Error::new(ErrorCode::EofString, read.position().into()))?
+                    {
+                        c @ (b'!' | b'\'') => c,
+                        _ => {
+                            return Err(Error::new(
+                                ErrorCode::InvalidEscape,
+                                (read.position() - 2).into(),
+                            ))
+                        }
+                    },
+                );
+            }
+            _ => {
+                scratch.push(ch);
+                read.discard();
+            }
+        }
+        if scratch.len() >= STR_CHUNK_LEN {
+            visited += visit_chunk(scratch, start_position + visited, visit, false)?;
         }
     }
+}
 
-    fn parse_ident<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
-        let start_position = self.position();
-        while let Some(ch) = self.peek()? {
-            if NOT_ID_CHARS.contains(&ch) {
-                break;
+/// Pass the contents of `scratch` to `visit` and remove them, returning
+/// their length
+///
+/// Unless this is the `last` chunk of the string, a character split across
+/// the end of `scratch` is kept for the next.
+fn visit_chunk(
+    scratch: &mut Vec<u8>,
+    start_position: usize,
+    visit: &mut dyn FnMut(&str) -> Result<()>,
+    last: bool,
+) -> Result<usize> {
+    let len = match std::str::from_utf8(scratch) {
+        Ok(chunk) => {
+            visit(chunk)?;
+            chunk.len()
+        }
+        Err(e) if !last && e.error_len().is_none() => {
+            let chunk = &scratch[..e.valid_up_to()];
+            visit(std::str::from_utf8(chunk).expect("valid up to the split character"))?;
+            chunk.len()
+        }
+        Err(e) => {
+            return Err(Error::new(
+                ErrorCode::InvalidUnicode,
+                (start_position + e.valid_up_to()).into(),
+            ))
+        }
+    };
+    scratch.drain(..len);
+    Ok(len)
+}
+
+/// Parse an unquoted string a byte at a time, copying it into `scratch`
+fn copy_ident<'de, 's, R>(
+    read: &mut R,
+    scratch: &'s mut Vec<u8>,
+    not_id_chars: &ByteSet,
+) -> Result<&'s str>
+where
+    R: ?Sized + Read<'de>,
+{
+    let start_position = read.position();
+    while let Some(ch) = read.peek()? {
+        if not_id_chars.contains(ch) {
+            break;
+        }
+        scratch.push(ch);
+        read.discard();
+    }
+
+    std::str::from_utf8(scratch).map_err(|e| {
+        Error::new(
+            ErrorCode::InvalidUnicode,
+            (start_position + e.valid_up_to()).into(),
+        )
+    })
+}
+
+/// Consume a quoted string a byte at a time, following its opening `'`
+fn skip_str<'de, R>(read: &mut R) -> Result<()>
+where
+    R: ?Sized + Read<'de>,
+{
+    loop {
+        match read.next()? {
+            None => return Err(Error::new(ErrorCode::EofString, read.position().into())),
+            Some(b'\'') => return Ok(()),
+            Some(b'!') => match read.next()? {
+                Some(b'!' | b'\'') => {}
+                Some(_) => {
+                    return Err(Error::new(
+                        ErrorCode::InvalidEscape,
+                        (read.position() - 2).into(),
+                    ))
+                }
+                None => return Err(Error::new(ErrorCode::EofString, read.position().into())),
+            },
+            Some(_) => {}
+        }
+    }
+}
+
+/// Consume an unquoted string a byte at a time
+fn skip_ident<'de, R>(read: &mut R, not_id_chars: &ByteSet) -> Result<()>
+where
+    R: ?Sized + Read<'de>,
+{
+    while let Some(ch) = read.peek()? {
+        if not_id_chars.contains(ch) {
+            break;
+        }
+        read.discard();
+    }
+    Ok(())
+}
+
+/// Reads input from an `io::BufRead`, a buffered chunk at a time
+///
+/// Quoted and unquoted strings which lie within a single chunk of the
+/// reader's buffer, without escapes, are returned from the buffer directly
+/// rather than copied into the deserializer's scratch buffer a byte at a
+/// time, as [`IoRead`] must. Strings crossing the end of a chunk are copied.
+pub struct BufIoRead<R> {
+    reader: R,
+    /// The number of bytes of the reader's buffer already read, which are
+    /// released to the reader only once the whole buffer has been read, so
+    /// that strings may be returned from it
+    index: usize,
+    peeked: Option<u8>,
+    position: usize,
+    /// The one-based number of the line being read
+    line: usize,
+    /// The position at which the line being read began
+    line_start: usize,
+    /// The number of bytes after which reading fails rather than continuing
+    max_len: usize,
+    /// Whether consumed input is being captured into `raw_buffer`
+    raw_buffering: bool,
+    /// Input consumed while capturing a raw value
+    raw_buffer: Vec<u8>,
+}
+
+impl<R: std::io::BufRead> BufIoRead<R> {
+    /// Create a Rison input source to read from an `io::BufRead`
+    pub fn new(reader: R) -> Self {
+        Self::with_max_len(reader, usize::MAX)
+    }
+
+    /// Create a reader which fails rather than read more than `max_len` bytes
+    pub(crate) fn with_max_len(reader: R, max_len: usize) -> Self {
+        BufIoRead {
+            reader,
+            index: 0,
+            peeked: None,
+            position: 0,
+            line: 1,
+            line_start: 0,
+            max_len,
+            raw_buffering: false,
+            raw_buffer: Vec::new(),
+        }
+    }
+
+    /// The unread remainder of the reader's buffer, up to the maximum
+    /// length, which must not be empty
+    ///
+    /// Only called after a byte has been peeked, so the buffer is not
+    /// refilled.
+    fn buffered(&mut self) -> Result<&[u8]> {
+        let position = self.position;
+        let buf = self
+            .reader
+            .fill_buf()
+            .map_err(|e| Error::new(ErrorCode::Io(e), position.into()))?;
+        let len = (buf.len() - self.index).min(self.max_len - position);
+        Ok(&buf[self.index..self.index + len])
+    }
+
+    /// Consume the first `len` bytes of [`buffered`](Self::buffered)
+    fn advance(&mut self, len: usize) -> Result<()> {
+        let position = self.position;
+        let buf = self
+            .reader
+            .fill_buf()
+            .map_err(|e| Error::new(ErrorCode::Io(e), position.into()))?;
+        let consumed = &buf[self.index..self.index + len];
+        if self.raw_buffering {
+            self.raw_buffer.extend_from_slice(consumed);
+        }
+        if let Some(last) = memchr::memrchr(b'\n', consumed) {
+            self.line += memchr::memchr_iter(b'\n', consumed).count();
+            self.line_start = position + last + 1;
+        }
+        self.index += len;
+        self.position += len;
+        self.peeked = None;
+        Ok(())
+    }
+
+    /// Consume the first `consume` bytes of [`buffered`](Self::buffered),
+    /// returning the first `len` of them as a string
+    fn take_str(&mut self, len: usize, consume: usize) -> Result<&str> {
+        let start_index = self.index;
+        let start_position = self.position;
+        self.advance(consume)?;
+        let buf = self
+            .reader
+            .fill_buf()
+            .map_err(|e| Error::new(ErrorCode::Io(e), start_position.into()))?;
+        from_utf8(&buf[start_index..start_index + len], start_position)
+    }
+
+    /// The length of the quoted string being read, if it ends within the
+    /// reader's buffer without escapes
+    ///
+    /// Strings with escapes are unescaped into the scratch buffer instead.
+    fn str_end_in_buffer(&mut self) -> Result<Option<usize>> {
+        if self.peek()?.is_none() {
+            return Ok(None);
+        }
+        let buf = self.buffered()?;
+        Ok(memchr::memchr2(b'\'', b'!', buf).filter(|&end| buf[end] == b'\''))
+    }
+
+    /// Fail if any input remains beyond the maximum length
+    #[cold]
+    fn peek_beyond_max_len(&mut self) -> Result<Option<u8>> {
+        let position = self.position;
+        let buf = self
+            .reader
+            .fill_buf()
+            .map_err(|e| Error::new(ErrorCode::Io(e), position.into()))?;
+        if buf.len() > self.index {
+            return Err(Error::new(ErrorCode::InputLimitExceeded, position.into()));
+        }
+        let len = buf.len();
+        self.reader.consume(len);
+        self.index = 0;
+        match self.reader.fill_buf() {
+            Ok([]) => Ok(None),
+            Ok(_) => Err(Error::new(ErrorCode::InputLimitExceeded, position.into())),
+            Err(e) => Err(Error::new(ErrorCode::Io(e), position.into())),
+        }
+    }
+}
+
+impl<'de, R: std::io::BufRead> Read<'de> for BufIoRead<R> {
+    fn peek(&mut self) -> Result<Option<u8>> {
+        if let Some(ch) = self.peeked {
+            return Ok(Some(ch));
+        }
+        if self.position >= self.max_len {
+            return self.peek_beyond_max_len();
+        }
+
+        let position = self.position;
+        let io = |e| Error::new(ErrorCode::Io(e), position.into());
+        let buf = self.reader.fill_buf().map_err(io)?;
+        let ch = match buf.get(self.index) {
+            Some(&ch) => Some(ch),
+            None => {
+                let len = buf.len();
+                self.reader.consume(len);
+                self.index = 0;
+                self.reader.fill_buf().map_err(io)?.first().copied()
             }
-            scratch.push(ch);
-            self.discard();
+        };
+
+        self.peeked = ch;
+
+        Ok(ch)
+    }
+
+    fn discard(&mut self) {
+        if let (true, Some(ch)) = (self.raw_buffering, self.peeked) {
+            self.raw_buffer.push(ch);
+        }
+        if self.peeked == Some(b'\n') {
+            self.line += 1;
+            self.line_start = self.position + 1;
+        }
+        if self.peeked.take().is_some() {
+            self.index += 1;
+            self.position += 1;
         }
+    }
 
-        std::str::from_utf8(scratch)
-            .map_err(|e| Error {
-                code: Code::InvalidUnicode,
-                position: (start_position + e.valid_up_to()).into(),
-            })
-            .map(Reference::Copied)
+    fn parse_str<'s>(&'s mut self, scratch: &'s mut Vec<u8>) -> Result<Reference<'de, 's, str>> {
+        if let Some(end) = self.str_end_in_buffer()? {
+            // Consume the closing `'` too
+            return self.take_str(end, end + 1).map(Reference::Copied);
+        }
+        copy_str(self, scratch).map(Reference::Copied)
+    }
+
+    fn parse_str_visit(
+        &mut self,
+        scratch: &mut Vec<u8>,
+        visit: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<()> {
+        if let Some(end) = self.str_end_in_buffer()? {
+            return visit(self.take_str(end, end + 1)?);
+        }
+        visit_str(self, scratch, visit)
+    }
+
+    fn parse_ident<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        not_id_chars: &ByteSet,
+    ) -> Result<Reference<'de, 's, str>> {
+        if self.peek()?.is_some() {
+            let end = self
+                .buffered()?
+                .iter()
+                .position(|&b| not_id_chars.contains(b));
+            if let Some(end) = end {
+                return self.take_str(end, end).map(Reference::Copied);
+            }
+        }
+        copy_ident(self, scratch, not_id_chars).map(Reference::Copied)
     }
 
     fn position(&mut self) -> usize {
         self.position
     }
+
+    /// Lines are counted as they are read, so only positions on the current
+    /// line can be located
+    fn location(&mut self, position: usize) -> Option<Location> {
+        (self.line_start..=self.position)
+            .contains(&position)
+            .then(|| Location::new(self.line, position - self.line_start + 1))
+    }
+
+    fn ignore_str(&mut self) -> Result<()> {
+        skip_str(self)
+    }
+
+    fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()> {
+        skip_ident(self, not_id_chars)
+    }
+
+    fn begin_raw_buffering(&mut self) {
+        self.raw_buffering = true;
+        self.raw_buffer.clear();
+    }
+
+    fn end_raw_buffering(&mut self) -> Result<Reference<'de, '_, str>> {
+        self.raw_buffering = false;
+        let start_position = self.position - self.raw_buffer.len();
+        from_utf8(&self.raw_buffer, start_position).map(Reference::Copied)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::{ByteSet, Read, Reference, StrRead};
+    use crate::error::Result;
+    use crate::Deserializer;
+
+    /// A custom source, counting the bytes peeked from another
+    struct CountingRead<R> {
+        inner: R,
+        peeks: usize,
+    }
+
+    impl<'de, R: Read<'de>> Read<'de> for CountingRead<R> {
+        fn peek(&mut self) -> Result<Option<u8>> {
+            self.peeks += 1;
+            self.inner.peek()
+        }
+        fn discard(&mut self) {
+            self.inner.discard()
+        }
+        fn parse_str<'s>(
+            &'s mut self,
+            scratch: &'s mut Vec<u8>,
+        ) -> Result<Reference<'de, 's, str>> {
+            self.inner.parse_str(scratch)
+        }
+        fn parse_ident<'s>(
+            &'s mut self,
+            scratch: &'s mut Vec<u8>,
+            not_id_chars: &ByteSet,
+        ) -> Result<Reference<'de, 's, str>> {
+            self.inner.parse_ident(scratch, not_id_chars)
+        }
+        fn position(&mut self) -> usize {
+            self.inner.position()
+        }
+        fn ignore_str(&mut self) -> Result<()> {
+            self.inner.ignore_str()
+        }
+        fn ignore_ident(&mut self, not_id_chars: &ByteSet) -> Result<()> {
+            self.inner.ignore_ident(not_id_chars)
+        }
+        fn begin_raw_buffering(&mut self) {
+            self.inner.begin_raw_buffering()
+        }
+        fn end_raw_buffering(&mut self) -> Result<Reference<'de, '_, str>> {
+            self.inner.end_raw_buffering()
+        }
+    }
+
+    #[test]
+    fn deserialize_from_custom_read() {
+        let read = CountingRead {
+            inner: StrRead::new("(a:!(1,2),b:'it!'s')"),
+            peeks: 0,
+        };
+        let mut de = Deserializer::new(read);
+        let value: crate::Value = serde::Deserialize::deserialize(&mut de).unwrap();
+        de.end().unwrap();
+
+        assert_eq!(
+            value,
+            "(a:!(1,2),b:'it!'s')".parse::<crate::Value>().unwrap()
+        );
+        assert!(de.read.peeks > 0);
+    }
+    #[test]
+    fn borrow_multibyte_strings_from_str() {
+        #[derive(serde::Deserialize)]
+        struct S<'a> {
+            #[serde(borrow)]
+            ident: &'a str,
+            quoted: String,
+            #[serde(borrow)]
+            raw: &'a crate::value::RawValue,
+        }
+
+        let s: S = crate::from_str("(ident:café,quoted:'naïve !'é!'',raw:!(ü,'ß!!'))").unwrap();
+        assert_eq!(s.ident, "café");
+        assert_eq!(s.quoted, "naïve 'é'");
+        assert_eq!(s.raw.get(), "!(ü,'ß!!')");
+    }
+    #[test]
+    fn deserialize_from_bufread_chunks() {
+        use std::io::BufReader;
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct S {
+            a: Vec<crate::Value>,
+            raw: Box<crate::value::RawValue>,
+            #[serde(rename = "long key")]
+            long: String,
+        }
+
+        let inputs = [
+            "(a:!(1,-2.5,!t,!n,x,'y z','it!'s',()),raw:(b:'!!',c:!(d)),'long key':'é ü ß')",
+            "(a:!(),raw:!n,'long key':abcdefghijklmnopqrstuvwxyz)",
+            "(a:!(!(!('')),café),raw:'',long:x,'long key':'')",
+        ];
+        for input in inputs {
+            let expected = format!("{:?}", crate::from_str::<S>(input).unwrap());
+            for capacity in [1, 2, 3, 5, 8, 13, 1024] {
+                let reader = BufReader::with_capacity(capacity, input.as_bytes());
+                let s: S = crate::from_bufread(reader).unwrap();
+                assert_eq!(
+                    format!("{s:?}"),
+                    expected,
+                    "{input} in chunks of {capacity}"
+                );
+            }
+        }
+    }
+    #[test]
+    fn fail_from_bufread_chunks() {
+        use std::io::BufReader;
+
+        let builder = Deserializer::builder().allow_whitespace(true);
+        for input in ["(a:'x\ny',\n b:'é", "!(1,\n 2 3)", "(a:'!x')", "(a:b c)"] {
+            let expected = crate::Value::deserialize(&mut builder.from_str(input)).unwrap_err();
+            for capacity in [1, 2, 4, 1024] {
+                let reader = BufReader::with_capacity(capacity, input.as_bytes());
+                let mut de = builder.from_bufread(reader);
+                let err = crate::Value::deserialize(&mut de).unwrap_err();
+                assert_eq!(err.to_string(), expected.to_string(), "{input:?}");
+            }
+        }
+
+        let limits = crate::de::ParseLimits::new().max_input_len(6);
+        let builder = Deserializer::builder().limits(limits);
+        for (input, ok) in [("!(a,b)", true), ("!(a,bc)", false), ("'abcdefg'", false)] {
+            for capacity in [1, 3, 1024] {
+                let reader = BufReader::with_capacity(capacity, input.as_bytes());
+                let mut de = builder.from_bufread(reader);
+                let value = crate::Value::deserialize(&mut de).and_then(|_| de.end());
+                assert_eq!(value.is_ok(), ok, "{input} in chunks of {capacity}");
+            }
+        }
+    }
 }
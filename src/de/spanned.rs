@@ -0,0 +1,251 @@
+use std::fmt;
+use std::ops::Range;
+
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use super::read::Read;
+use super::Deserializer;
+use crate::error::{Error, Result};
+
+/// The name of the newtype struct through which a [`Spanned`] is requested
+/// from this crate's deserializer
+pub(crate) const TOKEN: &str = "$rison::private::Spanned";
+
+/// The keys of the map through which the deserializer provides the parts of
+/// a [`Spanned`], in the order in which it provides them
+const START: &str = "$rison::private::Spanned::start";
+const VALUE: &str = "$rison::private::Spanned::value";
+const END: &str = "$rison::private::Spanned::end";
+
+/// A value along with the range of input it was deserialized from
+///
+/// The span is the range of byte offsets from the first character of the
+/// value to just past its last, excluding any surrounding whitespace, as
+/// reported by [`Error::position`](crate::Error::position). Validators of
+/// user-supplied input can use it to point at the offending part long
+/// after deserialization has succeeded.
+///
+/// Spans are only recorded by this crate's [`Deserializer`], and only for
+/// values rather than object keys. Deserializing a `Spanned` from any other
+/// deserializer, or from input buffered by `#[serde(flatten)]` or untagged
+/// enums, fails. It is serialized as the value alone.
+///
+/// ```
+/// use rison::de::Spanned;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Filter {
+///     field: Spanned<String>,
+///     value: Spanned<u32>,
+/// }
+///
+/// let input = "(field:status,value:404)";
+/// let filter: Filter = rison::from_str(input).unwrap();
+///
+/// assert_eq!(filter.field.span(), 7..13);
+/// assert_eq!(&input[filter.value.span()], "404");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Spanned<T> {
+    span: Range<usize>,
+    value: T,
+}
+
+impl<T> Spanned<T> {
+    /// Wrap a value with the span it was found at
+    pub fn new(span: Range<usize>, value: T) -> Self {
+        Self { span, value }
+    }
+
+    /// The range of byte offsets of the value within the input
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The byte offset of the first character of the value
+    pub fn start(&self) -> usize {
+        self.span.start
+    }
+
+    /// The byte offset just past the last character of the value
+    pub fn end(&self) -> usize {
+        self.span.end
+    }
+
+    /// A reference to the value
+    pub fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    /// A mutable reference to the value
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Unwrap the value, discarding its span
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// Spans are ignored, so that values compare equal wherever they were found
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Spanned<T> {}
+
+impl<T: Serialize> Serialize for Spanned<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Spanned<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct SpannedVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for SpannedVisitor<T> {
+            type Value = Spanned<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a spanned value")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let start = next_part(&mut map, START)?;
+                let value = next_part(&mut map, VALUE)?;
+                let end = next_part(&mut map, END)?;
+                Ok(Spanned::new(start..end, value))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(TOKEN, SpannedVisitor(std::marker::PhantomData))
+    }
+}
+
+/// Read the entry with `key` from the map of the parts of a [`Spanned`]
+fn next_part<'de, A, T>(map: &mut A, key: &'static str) -> std::result::Result<T, A::Error>
+where
+    A: MapAccess<'de>,
+    T: Deserialize<'de>,
+{
+    match map.next_key::<&str>()? {
+        Some(found) if found == key => map.next_value(),
+        _ => Err(de::Error::custom("expected a spanned value")),
+    }
+}
+
+/// Provides the parts of a [`Spanned`] to its visitor, deserializing the
+/// value itself from the underlying deserializer
+pub(crate) struct SpannedDeserializer<'a, R> {
+    de: &'a mut Deserializer<R>,
+    start: usize,
+    end: usize,
+    /// The index into [`START`], [`VALUE`] and [`END`] of the next part
+    part: usize,
+}
+
+impl<'a, R> SpannedDeserializer<'a, R> {
+    pub(crate) fn new(de: &'a mut Deserializer<R>, start: usize) -> Self {
+        Self {
+            de,
+            start,
+            end: start,
+            part: 0,
+        }
+    }
+}
+
+impl<'de, R: Read<'de>> MapAccess<'de> for SpannedDeserializer<'_, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match [START, VALUE, END].get(self.part) {
+            Some(&key) => seed
+                .deserialize(de::value::BorrowedStrDeserializer::new(key))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.part += 1;
+        match self.part {
+            1 => seed.deserialize(self.start.into_deserializer()),
+            2 => {
+                let value = seed.deserialize(&mut *self.de)?;
+                self.end = self.de.read.position();
+                Ok(value)
+            }
+            3 => seed.deserialize(self.end.into_deserializer()),
+            _ => Err(de::Error::custom("spanned value already taken")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Spanned;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Filter {
+        field: Spanned<String>,
+        values: Spanned<Vec<Spanned<u32>>>,
+        negate: Option<Spanned<bool>>,
+    }
+
+    #[test]
+    fn deserialize_spans() {
+        let input = "(field:'status code',negate:!t,values:!(404,500))";
+        let filter: Filter = crate::from_str(input).unwrap();
+
+        assert_eq!(&input[filter.field.span()], "'status code'");
+        assert_eq!(&input[filter.values.span()], "!(404,500)");
+        let spans: Vec<_> = filter.values.get_ref().iter().map(|v| v.span()).collect();
+        assert_eq!(spans, [40..43, 44..47]);
+        assert_eq!(&input[filter.negate.unwrap().span()], "!t");
+        assert_eq!(filter.field.into_inner(), "status code");
+    }
+    #[test]
+    fn deserialize_spans_around_whitespace() {
+        use serde::Deserialize;
+
+        let input = "( field : x , values : !( 1 ) )";
+        let builder = crate::Deserializer::builder().allow_whitespace(true);
+        let filter = Filter::deserialize(&mut builder.from_str(input)).unwrap();
+
+        assert_eq!(filter.field.span(), 10..11);
+        assert_eq!(&input[filter.values.span()], "!( 1 )");
+    }
+    #[test]
+    fn serialize_spanned_as_value() {
+        let v = Spanned::new(3..5, vec![1, 2]);
+
+        assert_eq!(crate::to_string(&v).unwrap(), "!(1,2)");
+    }
+    #[test]
+    fn fail_spanned_from_other_deserializers() {
+        let v: serde_json::Result<Spanned<u32>> = serde_json::from_str("1");
+
+        assert!(v.is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Helpers for serializing decimals as exact numbers
+//!
+//! Decimal libraries serialize their values as strings by default, and
+//! deserialize numbers by way of `f64`, so `1.10` becomes `'1.10'` in Rison
+//! and `0.1` may not parse back as exactly `0.1`. Each supported library has
+//! a module for use with `#[serde(with = "...")]` which instead writes the
+//! exact decimal as an unquoted number, and parses it from the number's text:
+//!
+//! - [`rust_decimal`], with the `rust_decimal` feature, for
+//!   `rust_decimal::Decimal`
+//! - [`bigdecimal`], with the `bigdecimal` feature, for
+//!   `bigdecimal::BigDecimal`
+//!
+//! Quoted strings holding a number are accepted too, so values written with
+//! the libraries' own serde support still parse. These helpers rely on the
+//! raw values of this crate's serializers and deserializers, including those
+//! of [`Value`](crate::Value), and may not work with other formats. A `Value`
+//! only keeps the exact text of its numbers with the `arbitrary_precision`
+//! feature.
+
+#[cfg(feature = "bigdecimal")]
+pub mod bigdecimal;
+#[cfg(feature = "rust_decimal")]
+pub mod rust_decimal;
+
+/// Serialize the text of a decimal as an unquoted number, in the form Rison
+/// expects: with a lowercase exponent and no `+` sign
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+fn serialize_number<S>(number: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let number = number.to_ascii_lowercase().replace("e+", "e");
+    serializer.serialize_newtype_struct(crate::value::raw::TOKEN, &number)
+}
+
+/// Deserialize the text of a number, or of the contents of a quoted string
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+fn deserialize_number<'de, D, T>(
+    deserializer: D,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::{Deserialize, Error, Unexpected};
+
+    let raw = Box::<crate::value::RawValue>::deserialize(deserializer)?;
+    let text = raw.get();
+    let number = match text.as_bytes().first() {
+        Some(b'-' | b'0'..=b'9') => Some(text.to_owned()),
+        Some(b'\'') => crate::from_str::<String>(text).ok(),
+        _ => None,
+    };
+    number
+        .as_deref()
+        .and_then(parse)
+        .ok_or_else(|| D::Error::invalid_value(Unexpected::Other(text), &"a decimal number"))
+}
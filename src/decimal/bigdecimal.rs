@@ -0,0 +1,85 @@
+//! Serialization of `bigdecimal::BigDecimal` as an exact number, such as
+//! `19.99`
+//!
+//! Decimals of any precision are written in full, or with an exponent when
+//! that is much shorter, and parse back unchanged.
+//!
+//! ```
+//! use std::str::FromStr;
+//!
+//! use bigdecimal::BigDecimal;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "rison::decimal::bigdecimal")]
+//!     min_price: BigDecimal,
+//! }
+//!
+//! let filter: Filter = rison::from_str("(min_price:0.1000000000000000000000000000001)").unwrap();
+//!
+//! assert_eq!(
+//!     filter.min_price,
+//!     BigDecimal::from_str("0.1000000000000000000000000000001").unwrap(),
+//! );
+//! ```
+
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use serde::de::Deserializer;
+use serde::ser::Serializer;
+
+/// Serialize a decimal as an unquoted number
+pub fn serialize<S>(value: &BigDecimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    super::serialize_number(&value.to_string(), serializer)
+}
+
+/// Deserialize a decimal from the exact text of a number
+pub fn deserialize<'de, D>(deserializer: D) -> Result<BigDecimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    super::deserialize_number(deserializer, |s| BigDecimal::from_str(s).ok())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Price(#[serde(with = "super")] BigDecimal);
+
+    fn price(s: &str) -> Price {
+        Price(BigDecimal::from_str(s).unwrap())
+    }
+
+    #[test]
+    fn serialize_decimals() {
+        for text in [
+            "19.99",
+            "-0.5",
+            "123456789012345678901234567890.123456789",
+            "1e-40",
+        ] {
+            let rison = crate::to_string(&price(text)).unwrap();
+            assert_eq!(
+                crate::from_str::<Price>(&rison).unwrap(),
+                price(text),
+                "{rison}"
+            );
+        }
+        assert_eq!(crate::to_string(&price("19.99")).unwrap(), "19.99");
+        assert_eq!(crate::to_string(&price("1e400")).unwrap(), "1e400");
+    }
+    #[test]
+    fn deserialize_decimals() {
+        assert_eq!(crate::from_str::<Price>("1.5e3").unwrap(), price("1500"));
+        assert_eq!(crate::from_str::<Price>("'0.30'").unwrap(), price("0.30"));
+        assert!(crate::from_str::<Price>("x").is_err());
+    }
+}
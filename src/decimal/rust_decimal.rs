@@ -0,0 +1,96 @@
+//! Serialization of `rust_decimal::Decimal` as an exact number, such as
+//! `19.99`
+//!
+//! The scale of a decimal is kept, so `1.10` is written as `1.10`. Numbers
+//! with an exponent, such as `1.5e3`, are accepted when deserializing.
+//! Numbers which cannot be represented exactly, with more than 28 decimal
+//! places or too large a magnitude, are rejected rather than rounded.
+//!
+//! ```
+//! use rust_decimal::Decimal;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "rison::decimal::rust_decimal")]
+//!     min_price: Decimal,
+//! }
+//!
+//! let filter: Filter = rison::from_str("(min_price:0.10)").unwrap();
+//!
+//! assert_eq!(filter.min_price, Decimal::new(10, 2));
+//! assert_eq!(rison::to_string(&filter).unwrap(), "(min_price:0.10)");
+//! ```
+
+use rust_decimal::Decimal;
+use serde::de::Deserializer;
+use serde::ser::Serializer;
+
+/// Serialize a decimal as an unquoted number
+pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    super::serialize_number(&value.to_string(), serializer)
+}
+
+/// Deserialize a decimal from the exact text of a number
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    super::deserialize_number(deserializer, |s| {
+        if s.contains('e') {
+            Decimal::from_scientific(s).ok()
+        } else {
+            Decimal::from_str_exact(s).ok()
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use rust_decimal::Decimal;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Price(#[serde(with = "super")] Decimal);
+
+    #[test]
+    fn serialize_decimals() {
+        for (decimal, rison) in [
+            (Decimal::new(1999, 2), "19.99"),
+            (Decimal::new(110, 2), "1.10"),
+            (Decimal::new(-5, 0), "-5"),
+            (Decimal::MAX, "79228162514264337593543950335"),
+        ] {
+            assert_eq!(crate::to_string(&Price(decimal)).unwrap(), rison);
+            assert_eq!(crate::from_str::<Price>(rison).unwrap(), Price(decimal));
+        }
+    }
+    #[test]
+    fn deserialize_decimals() {
+        for (rison, decimal) in [
+            ("0.1", Decimal::new(1, 1)),
+            ("1.5e3", Decimal::new(1500, 0)),
+            ("25e-1", Decimal::new(25, 1)),
+            ("'0.30'", Decimal::new(30, 2)),
+        ] {
+            assert_eq!(crate::from_str::<Price>(rison).unwrap(), Price(decimal));
+        }
+        for rison in [
+            "abc",
+            "'x'",
+            "!t",
+            "0.12345678901234567890123456789",
+            "1e40",
+        ] {
+            assert!(crate::from_str::<Price>(rison).is_err(), "{rison}");
+        }
+    }
+    #[test]
+    fn deserialize_decimals_within_values() {
+        let value: crate::Value = "12.5".parse().unwrap();
+        let price: Price = crate::value::from_value_ref(&value).unwrap();
+
+        assert_eq!(price, Price(Decimal::new(125, 1)));
+    }
+}
@@ -3,7 +3,7 @@
 use std::fmt::Write;
 
 /// Categorizes an [`Error`]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Category {
     /// failed to read or write bytes on an IO stream
     Io,
@@ -15,79 +15,590 @@ pub enum Category {
     Eof,
 }
 
+/// The specific kind of an [`Error`]
+///
+/// New variants may be added as the parser is extended, so matches on this
+/// enum should include a wildcard arm.
 #[derive(Debug)]
-pub(crate) enum Code {
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// A custom error message, typically produced by a `Deserialize` or
+    /// `Serialize` implementation
     Message(String),
+    /// An IO error on the underlying stream
     Io(std::io::Error),
+    /// The input ended where a value was expected
     EofValue,
-    EofList,
-    EofObject,
+    /// The input ended within a list, before the `)` closing the list
+    /// opened by the `!(` at the given position
+    UnclosedList(usize),
+    /// The input ended within an object, before the `)` closing the object
+    /// opened by the `(` at the given position
+    UnclosedObject(usize),
+    /// The input ended within a quoted string
     EofString,
+    /// The input ended immediately after a `!`
     EofMarker,
+    /// An object key was not followed by `:`
     ExpectedColon,
+    /// A list element was not followed by `,` or `)`
     ExpectedListSepOrEnd,
+    /// An object entry was not followed by `,` or `)`
     ExpectedObjectSepOrEnd,
+    /// The single entry of an object holding an enum variant was not
+    /// followed by `)`
+    ExpectedObjectEnd,
+    /// A `!` was followed by a character that does not form a valid marker
     InvalidMarker,
+    /// A `!` within a quoted string was not followed by `!` or `'`
     InvalidEscape,
+    /// A quoted string contained a C0 control character, while configured
+    /// to reject them with
+    /// [`DeserializerBuilder::reject_control_chars`](crate::de::DeserializerBuilder::reject_control_chars)
+    ControlCharInString,
+    /// A number was malformed
     InvalidNumber,
+    /// A number was too large in magnitude to be represented as an `f64`,
+    /// as in `1e400`
+    NumberOutOfRange,
+    /// An unquoted string started with a digit or `-`, as in `1x` or `-a`,
+    /// which the Rison grammar reserves for numbers
+    InvalidIdStart,
+    /// An object key was requested as a bool, but was not `!t`, `!f`, `true`
+    /// or `false`
+    InvalidBool,
+    /// A string was not valid UTF-8
     InvalidUnicode,
+    /// Lists and objects were nested more deeply than the recursion limit
+    RecursionLimitExceeded,
+    /// Input continued beyond [`ParseLimits::max_input_len`](crate::de::ParseLimits::max_input_len)
+    InputLimitExceeded,
+    /// A string was longer than [`ParseLimits::max_string_len`](crate::de::ParseLimits::max_string_len)
+    StringLimitExceeded,
+    /// A list or object had more elements than
+    /// [`ParseLimits::max_collection_len`](crate::de::ParseLimits::max_collection_len)
+    CollectionLimitExceeded,
+    /// Input continued after a complete value
     TrailingChars,
+    /// A list or object ended with a `,` before its closing `)`
+    TrailingComma,
+    /// A `*` or `@`, which are reserved by the Rison grammar, appeared
+    /// outside a quoted string
+    ReservedChar,
+    /// An object key was serialized from a value other than a string
+    KeyMustBeAString,
+    /// A string was requested as bytes, but was not valid URL-safe base64
+    InvalidBase64,
+    /// A character was requested, but the string did not contain exactly one
+    InvalidChar,
+    /// A NaN or infinite float was serialized, while configured to reject
+    /// them with [`NonFiniteFloats::Error`](crate::ser::NonFiniteFloats::Error)
+    NonFiniteFloat,
+    /// Output continued beyond
+    /// [`SerializerBuilder::max_output_len`](crate::ser::SerializerBuilder::max_output_len)
+    OutputLimitExceeded,
+    /// Output contained a character which must be percent-encoded in the
+    /// part of a URI it was written for, by
+    /// [`to_uri_string_strict`](crate::ser::to_uri_string_strict)
+    CharNeedsEncoding(char),
+    /// A value other than an object was serialized as O-Rison, by
+    /// [`to_string_orison`](crate::ser::to_string_orison)
+    ExpectedObject,
+    /// A value other than a list was serialized as A-Rison, by
+    /// [`to_string_arison`](crate::ser::to_string_arison)
+    ExpectedList,
 }
 
 /// An error that can occur while serializing or deserializing Rison
 pub struct Error {
-    pub(crate) code: Code,
+    pub(crate) code: ErrorCode,
     pub(crate) position: Option<usize>,
+    pub(crate) location: Option<Location>,
+    pub(crate) hint: Option<&'static str>,
+    #[cfg(feature = "path")]
+    pub(crate) path: Path,
+}
+
+/// The location of a value within nested objects and lists, such as `a.b[1]`
+///
+/// Produced by [`Error::path`]. Its `Display` implementation separates keys
+/// with `.` and writes list indices in brackets.
+#[cfg(feature = "path")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Path {
+    /// Segments are recorded as an error propagates outwards, so are stored
+    /// in reverse
+    innermost_first: Vec<Segment>,
+}
+
+#[cfg(feature = "path")]
+impl Path {
+    /// Iterate over the segments of the path, outermost first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Segment> + ExactSizeIterator {
+        self.innermost_first.iter().rev()
+    }
+}
+
+#[cfg(feature = "path")]
+impl std::fmt::Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.iter().enumerate() {
+            match segment {
+                Segment::Key(key) if i == 0 => f.write_str(key)?,
+                Segment::Key(key) => write!(f, ".{}", key)?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One step of a [`Path`]
+#[cfg(feature = "path")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// The value of the object entry with this key
+    Key(String),
+    /// The list element at this zero-based index
+    Index(usize),
+}
+
+/// One-based line and column of an error within its input
+///
+/// Produced by a [`Read`](crate::de::read::Read) source for the positions of
+/// errors, and reported by [`Error::line`] and [`Error::column`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl Location {
+    /// Create a location from a one-based line and column
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+
+    /// One-based line number
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// One-based column, in bytes
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Compute the location of the byte at `position` in `source`
+    pub(crate) fn of(source: &[u8], position: usize) -> Self {
+        let before = &source[..position.min(source.len())];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        Self {
+            line: before.iter().filter(|&&b| b == b'\n').count() + 1,
+            column: before.len() - line_start + 1,
+        }
+    }
+}
+
+/// The last character boundary in `source` at or before `position`, or
+/// its end if `position` is past it
+fn floor_char_boundary(source: &str, position: usize) -> usize {
+    let mut position = position.min(source.len());
+    while !source.is_char_boundary(position) {
+        position -= 1;
+    }
+    position
 }
 
 impl Error {
+    pub(crate) fn new(code: ErrorCode, position: Option<usize>) -> Self {
+        Self {
+            code,
+            position,
+            location: None,
+            hint: None,
+            #[cfg(feature = "path")]
+            path: Path::default(),
+        }
+    }
+
+    /// Create an error detected at the zero-based `position` in the input,
+    /// as by a custom [`Read`](crate::de::read::Read)
+    pub fn at(code: ErrorCode, position: usize) -> Self {
+        Self::new(code, Some(position))
+    }
+
+    pub(crate) fn io(error: std::io::Error) -> Self {
+        Self::new(ErrorCode::Io(error), None)
+    }
+
+    /// Attribute this error to `position`, unless it already has a position
+    ///
+    /// Errors raised by visitors have no position of their own, so are given
+    /// that of the value they rejected.
+    pub(crate) fn fix_position(mut self, position: usize) -> Self {
+        if self.position.is_none() {
+            self.position = Some(position);
+        }
+        self
+    }
+
+    /// Resolve the line and column of this error within the input it was
+    /// produced from
+    pub(crate) fn locate(self, source: &[u8]) -> Self {
+        self.locate_with(|position| Some(Location::of(source, position)))
+            .suggest(source)
+    }
+
+    /// Resolve the line and column of this error with `locate`, unless it
+    /// has no position or has already been located
+    pub(crate) fn locate_with(mut self, locate: impl FnOnce(usize) -> Option<Location>) -> Self {
+        if let (Some(position), None) = (self.position, self.location) {
+            self.location = locate(position);
+        }
+        self
+    }
+
+    /// Suggest a fix if the input at this error's position looks like JSON
+    /// rather than Rison, unless it has no position or already has a hint
+    pub(crate) fn suggest(mut self, source: &[u8]) -> Self {
+        if let (Some(position), None) = (self.position, self.hint) {
+            self.hint = hint_for(source, position);
+        }
+        self
+    }
+
+    /// Record that this error occurred within the value at `segment` of its
+    /// parent, as it propagates out of that parent
+    #[cfg(feature = "path")]
+    pub(crate) fn within(mut self, segment: Segment) -> Self {
+        self.path.innermost_first.push(segment);
+        self
+    }
+
     /// Categorizes this error
     pub fn classify(&self) -> Category {
         match self.code {
-            Code::Message(_) => Category::Data,
-            Code::Io(_) => Category::Io,
-            Code::EofValue
-            | Code::EofList
-            | Code::EofObject
-            | Code::EofString
-            | Code::EofMarker => Category::Eof,
-            Code::ExpectedColon
-            | Code::ExpectedListSepOrEnd
-            | Code::ExpectedObjectSepOrEnd
-            | Code::InvalidMarker
-            | Code::InvalidEscape
-            | Code::InvalidNumber
-            | Code::InvalidUnicode
-            | Code::TrailingChars => Category::Syntax,
-        }
-    }
-    /// Zero-based position at which the error was detected
-    ///
-    /// Errors may currently be missing a position in some cases
+            ErrorCode::Message(_) => Category::Data,
+            ErrorCode::Io(_) => Category::Io,
+            ErrorCode::EofValue
+            | ErrorCode::UnclosedList(_)
+            | ErrorCode::UnclosedObject(_)
+            | ErrorCode::EofString
+            | ErrorCode::EofMarker => Category::Eof,
+            ErrorCode::ExpectedColon
+            | ErrorCode::ExpectedListSepOrEnd
+            | ErrorCode::ExpectedObjectSepOrEnd
+            | ErrorCode::ExpectedObjectEnd
+            | ErrorCode::InvalidMarker
+            | ErrorCode::InvalidEscape
+            | ErrorCode::ControlCharInString
+            | ErrorCode::InvalidNumber
+            | ErrorCode::NumberOutOfRange
+            | ErrorCode::InvalidIdStart
+            | ErrorCode::InvalidBool
+            | ErrorCode::InvalidUnicode
+            | ErrorCode::RecursionLimitExceeded
+            | ErrorCode::InputLimitExceeded
+            | ErrorCode::StringLimitExceeded
+            | ErrorCode::CollectionLimitExceeded
+            | ErrorCode::TrailingChars
+            | ErrorCode::TrailingComma
+            | ErrorCode::ReservedChar => Category::Syntax,
+            ErrorCode::KeyMustBeAString
+            | ErrorCode::InvalidBase64
+            | ErrorCode::InvalidChar
+            | ErrorCode::NonFiniteFloat
+            | ErrorCode::OutputLimitExceeded
+            | ErrorCode::CharNeedsEncoding(_)
+            | ErrorCode::ExpectedObject
+            | ErrorCode::ExpectedList => Category::Data,
+        }
+    }
+    /// Whether this error was caused by a failure to read or write bytes on
+    /// an IO stream
+    pub fn is_io(&self) -> bool {
+        self.classify() == Category::Io
+    }
+    /// Whether this error was caused by input that is not valid Rison
+    pub fn is_syntax(&self) -> bool {
+        self.classify() == Category::Syntax
+    }
+    /// Whether this error was caused by input that is valid Rison, but is not
+    /// valid for the type being deserialized, or by a value which could not
+    /// be serialized
+    pub fn is_data(&self) -> bool {
+        self.classify() == Category::Data
+    }
+    /// Whether this error was caused by the input ending before a complete
+    /// value
+    ///
+    /// When reading from a stream which delivers input incrementally, such
+    /// errors may be resolved by retrying once more input is available.
+    pub fn is_eof(&self) -> bool {
+        self.classify() == Category::Eof
+    }
+    /// The specific kind of this error
+    pub fn code(&self) -> &ErrorCode {
+        &self.code
+    }
+    /// Zero-based position, in bytes, at which the error was detected
+    ///
+    /// Errors may currently be missing a position in some cases. See
+    /// [`char_position`](Error::char_position) for the position in
+    /// characters.
     pub fn position(&self) -> Option<usize> {
         self.position
     }
+    /// One-based line number at which the error was detected
+    ///
+    /// This is available for errors produced by the `from_*` functions while
+    /// deserializing from an in-memory slice or string, and from streams
+    /// where the error was detected on the line being read. Errors from
+    /// [`from_bytes_mut`](crate::de::from_bytes_mut) have no location.
+    pub fn line(&self) -> Option<usize> {
+        self.location.map(|l| l.line)
+    }
+    /// One-based column, in bytes, at which the error was detected
+    ///
+    /// This is available where [`line`](Error::line) is.
+    pub fn column(&self) -> Option<usize> {
+        self.location.map(|l| l.column)
+    }
+    /// Zero-based position, in characters, at which the error was detected
+    /// in `source`
+    ///
+    /// [`position`](Error::position) counts bytes, so it differs from this
+    /// after any multi-byte character in the input. `source` should be the
+    /// input which produced the error; a position within a multi-byte
+    /// character is taken as the start of that character, and one past the
+    /// end of `source` as its end.
+    ///
+    /// ```
+    /// let source = "(a:'é',b";
+    /// let err = rison::from_str::<serde_json::Value>(source).unwrap_err();
+    ///
+    /// assert_eq!(err.position(), Some(9));
+    /// assert_eq!(err.char_position(source), Some(8));
+    /// ```
+    pub fn char_position(&self, source: &str) -> Option<usize> {
+        let position = floor_char_boundary(source, self.position?);
+        Some(source[..position].chars().count())
+    }
+    /// One-based column, in characters, at which the error was detected in
+    /// `source`
+    ///
+    /// This is [`column`](Error::column) counted in characters rather than
+    /// bytes, for highlighting the input in an editor, and is available
+    /// wherever [`position`](Error::position) is.
+    pub fn char_column(&self, source: &str) -> Option<usize> {
+        let position = floor_char_boundary(source, self.position?);
+        let before = &source[..position];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Some(before[line_start..].chars().count() + 1)
+    }
+    /// A suggestion for fixing the input, where the error was detected at
+    /// something written as it would be in JSON
+    ///
+    /// Hints are given for objects in braces, lists in square brackets,
+    /// strings in double quotes, and `true`, `false` and `null`, where these
+    /// caused the input to be rejected. They are available for errors
+    /// produced while deserializing from an in-memory slice or string.
+    ///
+    /// ```
+    /// let err = rison::from_str::<serde_json::Value>(r#"{"a":1}"#).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     err.hint(),
+    ///     Some("Rison writes objects in parentheses, as `(a:1)`, rather than braces"),
+    /// );
+    /// ```
+    pub fn hint(&self) -> Option<&'static str> {
+        self.hint
+    }
+    /// Path through nested objects and lists to the value in which the error
+    /// was detected, or `None` if it was detected at the top level
+    ///
+    /// ```
+    /// #[derive(Debug, serde::Deserialize)]
+    /// struct A { b: B }
+    /// #[derive(Debug, serde::Deserialize)]
+    /// struct B { c: Vec<i32> }
+    ///
+    /// let err = rison::from_str::<A>("(b:(c:!(1,x)))").unwrap_err();
+    ///
+    /// assert_eq!(err.path().unwrap().to_string(), "b.c[1]");
+    /// ```
+    #[cfg(feature = "path")]
+    pub fn path(&self) -> Option<&Path> {
+        if self.path.innermost_first.is_empty() {
+            None
+        } else {
+            Some(&self.path)
+        }
+    }
+    /// Display this error along with an excerpt of the input it was produced
+    /// from, marking the position of the error with a caret
+    ///
+    /// `source` must be the input that was being deserialized. Long lines are
+    /// cropped to the region surrounding the error.
+    ///
+    /// ```
+    /// let source = "(a:1,b)";
+    /// let err = rison::from_str::<serde_json::Value>(source).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     err.display_with_source(source).to_string(),
+    ///     "expected `:` at line 1 column 7\n  |\n1 | (a:1,b)\n  |       ^",
+    /// );
+    /// ```
+    pub fn display_with_source<'a>(&'a self, source: &'a str) -> WithSource<'a> {
+        WithSource {
+            error: self,
+            source,
+        }
+    }
+}
+
+/// The suggestion for the unquoted token at or just before `position` in
+/// `source`, if it is written as it would be in JSON
+fn hint_for(source: &[u8], position: usize) -> Option<&'static str> {
+    let is_id = |b: &u8| !crate::de::NOT_ID_CHARS.contains(b);
+    let position = position.min(source.len());
+    let start = source[..position]
+        .iter()
+        .rposition(|b| !is_id(b))
+        .map_or(0, |i| i + 1);
+    let end = source[position..]
+        .iter()
+        .position(|b| !is_id(b))
+        .map_or(source.len(), |i| position + i);
+    match &source[start..end] {
+        b"true" | b"false" => Some("Rison writes `true` and `false` as `!t` and `!f`"),
+        b"null" => Some("Rison writes `null` as `!n`"),
+        [b'{', ..] | [.., b'}'] => {
+            Some("Rison writes objects in parentheses, as `(a:1)`, rather than braces")
+        }
+        [b'[', ..] | [.., b']'] => {
+            Some("Rison writes lists as `!(1,2)` rather than in square brackets")
+        }
+        [b'"', ..] | [.., b'"'] => Some(r#"Rison quotes strings with `'` rather than `"`"#),
+        _ => None,
+    }
+}
+
+/// Displays an [`Error`] with an excerpt of its input
+///
+/// Created by [`Error::display_with_source`]
+pub struct WithSource<'a> {
+    error: &'a Error,
+    source: &'a str,
 }
 
-impl std::fmt::Display for Code {
+impl std::fmt::Display for WithSource<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        /// Bytes of context shown either side of the error on long lines
+        const CONTEXT: usize = 40;
+
+        let Some(position) = self.error.position else {
+            return self.error.fmt(f);
+        };
+        // Errors within a multi-byte character, such as an invalid escape
+        // before one, are shown at its start
+        let position = floor_char_boundary(self.source, position);
+        let location = Location::of(self.source.as_bytes(), position);
+
+        #[cfg(feature = "path")]
+        if let Some(path) = self.error.path() {
+            write!(f, "{}: ", path)?;
+        }
+        self.error.code.fmt(f)?;
+        write!(f, " at line {} column {}", location.line, location.column)?;
+
+        let line_start = position + 1 - location.column;
+        let line_end = self.source[position..]
+            .find('\n')
+            .map_or(self.source.len(), |i| position + i);
+
+        let mut start = position.saturating_sub(CONTEXT).max(line_start);
+        while !self.source.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = (position + CONTEXT).min(line_end);
+        while !self.source.is_char_boundary(end) {
+            end += 1;
+        }
+        let (prefix, suffix) = (
+            if start > line_start { "..." } else { "" },
+            if end < line_end { "..." } else { "" },
+        );
+        let caret = prefix.len() + self.source[start..position].chars().count();
+
+        let gutter = location.line.to_string();
+        let pad = " ".repeat(gutter.len());
+        write!(f, "\n{pad} |")?;
+        write!(
+            f,
+            "\n{gutter} | {prefix}{}{suffix}",
+            &self.source[start..end]
+        )?;
+        write!(f, "\n{pad} | {:caret$}^", "")?;
+        if let Some(hint) = self.error.hint {
+            write!(f, "\n{pad} = help: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Code::Message(msg) => f.write_str(msg),
-            Code::Io(err) => err.fmt(f),
-            Code::EofValue => f.write_str("EoF while parsing a value"),
-            Code::EofList => f.write_str("EoF while parsing a list"),
-            Code::EofObject => f.write_str("EoF while parsing an object"),
-            Code::EofString => f.write_str("EoF while parsing a quoted string"),
-            Code::EofMarker => f.write_str("EoF while parsing a `!` marker"),
-            Code::ExpectedColon => f.write_str("expected `:`"),
-            Code::ExpectedListSepOrEnd | Code::ExpectedObjectSepOrEnd => {
+            ErrorCode::Message(msg) => f.write_str(msg),
+            ErrorCode::Io(err) => err.fmt(f),
+            ErrorCode::EofValue => f.write_str("EoF while parsing a value"),
+            ErrorCode::UnclosedList(start) => {
+                write!(f, "unclosed list started at position {}", start)
+            }
+            ErrorCode::UnclosedObject(start) => {
+                write!(f, "unclosed object started at position {}", start)
+            }
+            ErrorCode::EofString => f.write_str("EoF while parsing a quoted string"),
+            ErrorCode::EofMarker => f.write_str("EoF while parsing a `!` marker"),
+            ErrorCode::ExpectedColon => f.write_str("expected `:`"),
+            ErrorCode::ExpectedListSepOrEnd | ErrorCode::ExpectedObjectSepOrEnd => {
                 f.write_str("expected `,` or `)`")
             }
-            Code::InvalidMarker => f.write_str("invalid marker"),
-            Code::InvalidEscape => f.write_str("invalid escape"),
-            Code::InvalidNumber => f.write_str("invalid number"),
-            Code::InvalidUnicode => f.write_str("invalid unicode code point"),
-            Code::TrailingChars => f.write_str("trailing characters"),
+            ErrorCode::ExpectedObjectEnd => f.write_str("expected `)`"),
+            ErrorCode::InvalidMarker => f.write_str("invalid marker"),
+            ErrorCode::InvalidEscape => f.write_str("invalid escape"),
+            ErrorCode::ControlCharInString => f.write_str("control character in string"),
+            ErrorCode::InvalidNumber => f.write_str("invalid number"),
+            ErrorCode::NumberOutOfRange => f.write_str("number out of range"),
+            ErrorCode::InvalidIdStart => {
+                f.write_str("unquoted string may not start with a digit or `-`")
+            }
+            ErrorCode::InvalidBool => f.write_str("invalid bool"),
+            ErrorCode::InvalidUnicode => f.write_str("invalid unicode code point"),
+            ErrorCode::RecursionLimitExceeded => f.write_str("recursion limit exceeded"),
+            ErrorCode::InputLimitExceeded => f.write_str("input length limit exceeded"),
+            ErrorCode::StringLimitExceeded => f.write_str("string length limit exceeded"),
+            ErrorCode::CollectionLimitExceeded => f.write_str("collection length limit exceeded"),
+            ErrorCode::TrailingChars => f.write_str("trailing characters"),
+            ErrorCode::TrailingComma => f.write_str("trailing comma"),
+            ErrorCode::ReservedChar => f.write_str("reserved character"),
+            ErrorCode::KeyMustBeAString => f.write_str("key must be a string"),
+            ErrorCode::InvalidBase64 => f.write_str("invalid base64 in byte string"),
+            ErrorCode::InvalidChar => f.write_str("expected a string of a single character"),
+            ErrorCode::NonFiniteFloat => f.write_str("float must be finite"),
+            ErrorCode::OutputLimitExceeded => f.write_str("output length limit exceeded"),
+            ErrorCode::CharNeedsEncoding(c) => {
+                write!(f, "character {:?} must be percent-encoded", c)
+            }
+            ErrorCode::ExpectedObject => f.write_str("O-Rison must be an object"),
+            ErrorCode::ExpectedList => f.write_str("A-Rison must be a list"),
         }
     }
 }
@@ -98,14 +609,30 @@ impl std::fmt::Debug for Error {
         if let Some(position) = self.position {
             write!(f, ", position: {}", position)?;
         }
+        if let Some(location) = self.location {
+            write!(f, ", line: {}, column: {}", location.line, location.column)?;
+        }
+        if let Some(hint) = self.hint {
+            write!(f, ", hint: {:?}", hint)?;
+        }
+        #[cfg(feature = "path")]
+        if let Some(path) = self.path() {
+            write!(f, ", path: {}", path)?;
+        }
         f.write_char(')')
     }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(feature = "path")]
+        if let Some(path) = self.path() {
+            write!(f, "{}: ", path)?;
+        }
         self.code.fmt(f)?;
-        if let Some(position) = self.position {
+        if let Some(location) = self.location {
+            write!(f, " at line {} column {}", location.line, location.column)?;
+        } else if let Some(position) = self.position {
             write!(f, " at position {}", position)?;
         }
         Ok(())
@@ -119,12 +646,58 @@ impl serde::de::Error for Error {
     where
         T: std::fmt::Display,
     {
-        Self {
-            code: Code::Message(msg.to_string()),
-            position: None,
+        Self::new(ErrorCode::Message(msg.to_string()), None)
+    }
+
+    fn invalid_type(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        serde::de::Error::custom(format_args!(
+            "invalid type: {}, expected {}",
+            RisonUnexpected(unexp),
+            exp,
+        ))
+    }
+
+    fn invalid_value(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        serde::de::Error::custom(format_args!(
+            "invalid value: {}, expected {}",
+            RisonUnexpected(unexp),
+            exp,
+        ))
+    }
+}
+
+/// Describes an unexpected value as it would be written in Rison
+struct RisonUnexpected<'a>(serde::de::Unexpected<'a>);
+
+impl std::fmt::Display for RisonUnexpected<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            serde::de::Unexpected::Bool(true) => f.write_str("boolean `!t`"),
+            serde::de::Unexpected::Bool(false) => f.write_str("boolean `!f`"),
+            serde::de::Unexpected::Unit => f.write_str("null `!n`"),
+            serde::de::Unexpected::Str(s) => {
+                f.write_str("string '")?;
+                for c in s.chars() {
+                    if c == '!' || c == '\'' {
+                        f.write_char('!')?;
+                    }
+                    f.write_char(c)?;
+                }
+                f.write_char('\'')
+            }
+            unexp => unexp.fmt(f),
         }
     }
 }
 
+impl serde::ser::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: std::fmt::Display,
+    {
+        Self::new(ErrorCode::Message(msg.to_string()), None)
+    }
+}
+
 /// An alias for [`Result`](std::result::Result) with the [`rison::Error`](Error) error type
 pub type Result<T> = std::result::Result<T, Error>;
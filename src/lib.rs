@@ -6,15 +6,105 @@
 //! - <https://github.com/Nanonid/rison>
 //! - <https://github.com/w33ble/rison-node>
 //!
-//! The deserializer implementation is broadly inspired by the existing
-//! `serde_json` library which provides a `serde` serializer and
+//! The serializer and deserializer implementations are broadly inspired by
+//! the existing `serde_json` library which provides a `serde` serializer and
 //! deserializer for the standard JSON format.
+//!
+//! Byte strings, which Rison has no native representation for, are
+//! represented as strings containing their unpadded URL-safe base64
+//! encoding.
+//!
+//! Structs with `#[serde(flatten)]` fields are supported. As `serde` buffers
+//! the entries of such structs before deserializing them, without knowing
+//! their types, keys within flattened maps are always strings, and byte
+//! strings within them are not decoded from base64.
+
+#![cfg_attr(not(feature = "unchecked_utf8"), forbid(unsafe_code))]
 
+#[cfg(feature = "axum")]
+pub mod axum;
+mod bytes;
+pub mod datetime;
 pub mod de;
+pub mod decimal;
 pub mod error;
+pub mod map;
+mod number;
+mod profile;
+pub mod query;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod ser;
+pub mod serde_helpers;
+#[cfg(any(test, feature = "testdata"))]
+pub mod testdata;
+pub mod transcode;
+pub mod value;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[doc(inline)]
 pub use error::{Error, Result};
 
 #[doc(inline)]
-pub use de::{from_reader, from_slice, from_str, Deserializer};
+pub use de::{
+    from_bufread, from_fallible_iter, from_iter, from_reader, from_slice, from_str, Deserializer,
+    DeserializerBuilder,
+};
+
+#[cfg(feature = "tokio")]
+#[doc(inline)]
+pub use de::from_async_reader;
+
+#[doc(inline)]
+pub use profile::Profile;
+
+#[doc(inline)]
+pub use query::from_query;
+
+#[doc(inline)]
+pub use ser::{
+    is_valid_id, needs_quoting, to_string, to_string_pretty, to_vec, to_writer, Serializer,
+    SerializerBuilder,
+};
+
+#[doc(inline)]
+pub use map::Map;
+
+#[doc(inline)]
+pub use number::Number;
+
+#[doc(inline)]
+pub use value::Value;
+
+/// Implement `Display` and `FromStr` for a type by way of its Rison
+/// encoding, with the `derive` feature
+///
+/// Query state structs may then be written into URL templates with
+/// `format!` and read back with `str::parse`. The Rison is written as is,
+/// without percent-encoding.
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// #[derive(serde::Serialize, serde::Deserialize, rison::RisonDisplay)]
+/// struct Search {
+///     q: String,
+///     page: u32,
+/// }
+///
+/// let search = Search { q: "rust".into(), page: 2 };
+/// assert_eq!(format!("/search?state={search}"), "/search?state=(q:rust,page:2)");
+///
+/// let search: Search = "(q:serde,page:3)".parse().unwrap();
+/// assert_eq!((search.q.as_str(), search.page), ("serde", 3));
+/// # }
+/// ```
+#[cfg(feature = "derive")]
+pub use rison_derive::RisonDisplay;
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+}
@@ -0,0 +1,602 @@
+//! The map of keys to values backing [`Value::Object`]
+//!
+//! [`Map`] wraps a standard map chosen by features: keys are sorted by
+//! default, kept in insertion order with `preserve_order`, or hashed with
+//! `unordered_map`. The wrapper offers the same API whichever is chosen, so
+//! code using it compiles under every combination of features, and the
+//! backing store may change without breaking it.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FusedIterator;
+use std::ops;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::value::Value;
+
+#[cfg(feature = "preserve_order")]
+use indexmap::map as imp;
+#[cfg(not(any(feature = "preserve_order", feature = "unordered_map")))]
+use std::collections::btree_map as imp;
+#[cfg(all(feature = "unordered_map", not(feature = "preserve_order")))]
+use std::collections::hash_map as imp;
+
+#[cfg(not(any(feature = "preserve_order", feature = "unordered_map")))]
+type MapImpl<K, V> = std::collections::BTreeMap<K, V>;
+#[cfg(feature = "preserve_order")]
+type MapImpl<K, V> = indexmap::IndexMap<K, V>;
+#[cfg(all(feature = "unordered_map", not(feature = "preserve_order")))]
+type MapImpl<K, V> = std::collections::HashMap<K, V>;
+
+/// A map of string keys to Rison values
+///
+/// Keys are sorted by default, so objects are written in a deterministic
+/// order. With the `preserve_order` feature, keys are instead kept in the
+/// order in which they were inserted or parsed, and with the `unordered_map`
+/// feature they are hashed, for speed, and written in an arbitrary order.
+/// `preserve_order` takes precedence if both are enabled.
+pub struct Map<K, V> {
+    map: MapImpl<K, V>,
+}
+
+impl Map<String, Value> {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Map {
+            map: MapImpl::new(),
+        }
+    }
+
+    /// Create an empty map with room for at least `capacity` entries, where
+    /// the backing map supports it
+    pub fn with_capacity(capacity: usize) -> Self {
+        #[cfg(not(any(feature = "preserve_order", feature = "unordered_map")))]
+        {
+            let _ = capacity;
+            Map::new()
+        }
+        #[cfg(any(feature = "preserve_order", feature = "unordered_map"))]
+        {
+            Map {
+                map: MapImpl::with_capacity(capacity),
+            }
+        }
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// The value for `key`, if any
+    pub fn get<Q>(&self, key: &Q) -> Option<&Value>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get(key)
+    }
+
+    /// Whether the map has a value for `key`
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// A mutable reference to the value for `key`, if any
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut Value>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get_mut(key)
+    }
+
+    /// The stored key and value for `key`, if any
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&String, &Value)>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get_key_value(key)
+    }
+
+    /// Insert a value for `key`, returning the value it replaced, if any
+    ///
+    /// With `preserve_order`, a replaced value keeps its key's position.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        self.map.insert(key, value)
+    }
+
+    /// Remove and return the value for `key`, if any
+    ///
+    /// With `preserve_order`, the entries following it are shifted to keep
+    /// their order.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<Value>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove(key);
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove(key);
+    }
+
+    /// Remove and return the stored key and value for `key`, if any
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(String, Value)>
+    where
+        String: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove_entry(key);
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove_entry(key);
+    }
+
+    /// Move all entries of `other` into this map, leaving `other` empty
+    pub fn append(&mut self, other: &mut Self) {
+        self.map.extend(std::mem::take(&mut other.map));
+    }
+
+    /// The entry for `key`, for in-place manipulation
+    ///
+    /// ```
+    /// use rison::{Number, Value};
+    ///
+    /// let mut counts = rison::Map::new();
+    /// for word in ["a", "b", "a"] {
+    ///     let count = counts.entry(word).or_insert(Value::Number(Number::from(0u64)));
+    ///     *count = Value::Number(Number::from(count.as_u64().unwrap() + 1));
+    /// }
+    ///
+    /// assert_eq!(rison::to_string(&counts).unwrap(), "(a:2,b:1)");
+    /// ```
+    pub fn entry<S>(&mut self, key: S) -> Entry<'_>
+    where
+        S: Into<String>,
+    {
+        match self.map.entry(key.into()) {
+            imp::Entry::Vacant(vacant) => Entry::Vacant(VacantEntry { vacant }),
+            imp::Entry::Occupied(occupied) => Entry::Occupied(OccupiedEntry { occupied }),
+        }
+    }
+
+    /// The number of entries
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterate over the entries
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            iter: self.map.iter(),
+        }
+    }
+
+    /// Iterate over the entries, with mutable references to the values
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            iter: self.map.iter_mut(),
+        }
+    }
+
+    /// Iterate over the keys
+    pub fn keys(&self) -> Keys<'_> {
+        Keys {
+            iter: self.map.keys(),
+        }
+    }
+
+    /// Iterate over the values
+    pub fn values(&self) -> Values<'_> {
+        Values {
+            iter: self.map.values(),
+        }
+    }
+
+    /// Iterate over mutable references to the values
+    pub fn values_mut(&mut self) -> ValuesMut<'_> {
+        ValuesMut {
+            iter: self.map.values_mut(),
+        }
+    }
+
+    /// Iterate over the values, consuming the map
+    pub fn into_values(self) -> IntoValues {
+        IntoValues {
+            iter: self.map.into_values(),
+        }
+    }
+
+    /// Keep only the entries for which `f` returns `true`
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&String, &mut Value) -> bool,
+    {
+        self.map.retain(f);
+    }
+}
+
+impl Default for Map<String, Value> {
+    fn default() -> Self {
+        Map::new()
+    }
+}
+
+impl Clone for Map<String, Value> {
+    fn clone(&self) -> Self {
+        Map {
+            map: self.map.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.map.clone_from(&source.map);
+    }
+}
+
+impl PartialEq for Map<String, Value> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl fmt::Debug for Map<String, Value> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+/// Access the value for a key, panicking if there is none
+impl<Q> ops::Index<&Q> for Map<String, Value>
+where
+    String: Borrow<Q>,
+    Q: ?Sized + Ord + Eq + Hash,
+{
+    type Output = Value;
+
+    fn index(&self, key: &Q) -> &Value {
+        self.map.index(key)
+    }
+}
+
+/// Mutably access the value for a key, panicking if there is none
+impl<Q> ops::IndexMut<&Q> for Map<String, Value>
+where
+    String: Borrow<Q>,
+    Q: ?Sized + Ord + Eq + Hash,
+{
+    fn index_mut(&mut self, key: &Q) -> &mut Value {
+        self.map.get_mut(key).expect("no entry found for key")
+    }
+}
+
+impl Serialize for Map<String, Value> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Map<String, Value> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MapVisitor;
+
+        impl<'de> Visitor<'de> for MapVisitor {
+            type Value = Map<String, Value>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object")
+            }
+
+            fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut map = Map::new();
+                while let Some((key, value)) = access.next_entry()? {
+                    map.insert(key, value);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MapVisitor)
+    }
+}
+
+impl FromIterator<(String, Value)> for Map<String, Value> {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (String, Value)>,
+    {
+        Map {
+            map: FromIterator::from_iter(iter),
+        }
+    }
+}
+
+impl Extend<(String, Value)> for Map<String, Value> {
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = (String, Value)>,
+    {
+        self.map.extend(iter);
+    }
+}
+
+/// A view into a single entry of a [`Map`], which may be vacant or
+/// occupied
+///
+/// Created by [`Map::entry`].
+pub enum Entry<'a> {
+    /// An entry with no value
+    Vacant(VacantEntry<'a>),
+    /// An entry with a value
+    Occupied(OccupiedEntry<'a>),
+}
+
+/// A vacant entry of a [`Map`]
+pub struct VacantEntry<'a> {
+    vacant: imp::VacantEntry<'a, String, Value>,
+}
+
+/// An occupied entry of a [`Map`]
+pub struct OccupiedEntry<'a> {
+    occupied: imp::OccupiedEntry<'a, String, Value>,
+}
+
+impl<'a> Entry<'a> {
+    /// The key of this entry
+    pub fn key(&self) -> &String {
+        match self {
+            Entry::Vacant(e) => e.key(),
+            Entry::Occupied(e) => e.key(),
+        }
+    }
+
+    /// The value of this entry, inserting `default` if it is vacant
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    /// The value of this entry, inserting the result of `default` if it is
+    /// vacant
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut Value
+    where
+        F: FnOnce() -> Value,
+    {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    /// Modify the value of this entry with `f`, if it is occupied
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut Value),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    /// The key which would be inserted
+    pub fn key(&self) -> &String {
+        self.vacant.key()
+    }
+
+    /// Insert `value` for the key, returning a reference to it
+    pub fn insert(self, value: Value) -> &'a mut Value {
+        self.vacant.insert(value)
+    }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    /// The key of the entry
+    pub fn key(&self) -> &String {
+        self.occupied.key()
+    }
+
+    /// The value of the entry
+    pub fn get(&self) -> &Value {
+        self.occupied.get()
+    }
+
+    /// A mutable reference to the value of the entry
+    pub fn get_mut(&mut self) -> &mut Value {
+        self.occupied.get_mut()
+    }
+
+    /// A mutable reference to the value of the entry, living as long as the
+    /// map
+    pub fn into_mut(self) -> &'a mut Value {
+        self.occupied.into_mut()
+    }
+
+    /// Replace the value of the entry, returning the old value
+    pub fn insert(&mut self, value: Value) -> Value {
+        self.occupied.insert(value)
+    }
+
+    /// Remove the entry, returning its value
+    pub fn remove(self) -> Value {
+        #[cfg(feature = "preserve_order")]
+        return self.occupied.shift_remove();
+        #[cfg(not(feature = "preserve_order"))]
+        return self.occupied.remove();
+    }
+}
+
+macro_rules! delegate_iterator {
+    ($name:ident $(<$lt:lifetime>)?, $item:ty) => {
+        impl$(<$lt>)? Iterator for $name$(<$lt>)? {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.iter.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.size_hint()
+            }
+        }
+
+        impl$(<$lt>)? ExactSizeIterator for $name$(<$lt>)? {
+            fn len(&self) -> usize {
+                self.iter.len()
+            }
+        }
+
+        impl$(<$lt>)? FusedIterator for $name$(<$lt>)? {}
+    };
+}
+
+/// An iterator over the entries of a [`Map`]
+pub struct Iter<'a> {
+    iter: imp::Iter<'a, String, Value>,
+}
+delegate_iterator!(Iter<'a>, (&'a String, &'a Value));
+
+/// A mutable iterator over the entries of a [`Map`]
+pub struct IterMut<'a> {
+    iter: imp::IterMut<'a, String, Value>,
+}
+delegate_iterator!(IterMut<'a>, (&'a String, &'a mut Value));
+
+/// An owning iterator over the entries of a [`Map`]
+pub struct IntoIter {
+    iter: imp::IntoIter<String, Value>,
+}
+delegate_iterator!(IntoIter, (String, Value));
+
+/// An iterator over the keys of a [`Map`]
+pub struct Keys<'a> {
+    iter: imp::Keys<'a, String, Value>,
+}
+delegate_iterator!(Keys<'a>, &'a String);
+
+/// An iterator over the values of a [`Map`]
+pub struct Values<'a> {
+    iter: imp::Values<'a, String, Value>,
+}
+delegate_iterator!(Values<'a>, &'a Value);
+
+/// A mutable iterator over the values of a [`Map`]
+pub struct ValuesMut<'a> {
+    iter: imp::ValuesMut<'a, String, Value>,
+}
+delegate_iterator!(ValuesMut<'a>, &'a mut Value);
+
+/// An owning iterator over the values of a [`Map`]
+pub struct IntoValues {
+    iter: imp::IntoValues<String, Value>,
+}
+delegate_iterator!(IntoValues, Value);
+
+impl<'a> IntoIterator for &'a Map<String, Value> {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map<String, Value> {
+    type Item = (&'a String, &'a mut Value);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for Map<String, Value> {
+    type Item = (String, Value);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iter: self.map.into_iter(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, Map};
+    use crate::number::Number;
+    use crate::value::Value;
+
+    #[test]
+    fn manipulate_entries() {
+        let mut map: Map<String, Value> = crate::from_str("(a:1,b:!t)").unwrap();
+
+        map.entry("a")
+            .and_modify(|v| *v = Value::Number(Number::from(2u64)));
+        map.entry("c").or_insert_with(|| Value::String("x".into()));
+        match map.entry("b") {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), Value::Bool(true)),
+            Entry::Vacant(_) => panic!("b is present"),
+        }
+        assert!(matches!(map.entry("b"), Entry::Vacant(_)));
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], Value::Number(Number::from(2u64)));
+        assert_eq!(map.get("c").and_then(Value::as_str), Some("x"));
+        assert_eq!(map.remove("c"), Some(Value::String("x".into())));
+        assert_eq!(crate::to_string(&map).unwrap(), "(a:2)");
+    }
+    #[test]
+    fn iterate_entries() {
+        let mut map: Map<String, Value> = crate::from_str("(a:1,b:2,c:3)").unwrap();
+
+        for v in map.values_mut() {
+            *v = Value::Number(Number::from(v.as_u64().unwrap() * 10));
+        }
+        map.retain(|k, _| k != "b");
+
+        let mut entries: Vec<_> = map.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            entries,
+            [
+                ("a".into(), Value::Number(Number::from(10u64))),
+                ("c".into(), Value::Number(Number::from(30u64)))
+            ]
+        );
+    }
+}
@@ -0,0 +1,599 @@
+//! The number type of [`Value`](crate::Value)
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Visitor};
+use serde::ser::Serialize;
+
+#[cfg(feature = "arbitrary_precision")]
+use crate::error::{Error, Result};
+
+/// The name through which an exact number is passed between this crate's
+/// deserializer or serializer and a [`Number`] or [`Value`](crate::Value),
+/// when the `arbitrary_precision` feature is enabled
+///
+/// Requesting a newtype struct of this name asks the deserializer to provide
+/// a number as a single-entry map from this name to the number's text.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) const TOKEN: &str = "$rison::private::Number";
+
+/// A finite Rison number
+///
+/// By default numbers are stored as a `u64`, `i64` or `f64`, depending on
+/// how they were written or constructed. Integers are always written exactly,
+/// and floats in their shortest round-trip form.
+///
+/// With the `arbitrary_precision` feature, numbers are instead stored as the
+/// exact decimal text they were parsed from, so that values beyond the
+/// precision of these types survive a round-trip.
+///
+/// Rison has no NaN or infinities, so a `Number` cannot hold them:
+/// [`from_f64`](Number::from_f64) returns `None` for them, and deserializing
+/// a `Number` from another format which provides one fails. A
+/// [`Value`](crate::Value) deserialized from such a format holds `Null` in
+/// their place instead, as the serializer writes them by default. Rison
+/// numbers too large for an `f64`, such as `1e400`, fail to deserialize
+/// with [`ErrorCode::NumberOutOfRange`](crate::error::ErrorCode::NumberOutOfRange)
+/// rather than becoming infinite, unless kept as their exact text with the
+/// `arbitrary_precision` feature.
+///
+/// ```
+/// use rison::Number;
+///
+/// let n: Number = rison::from_str("18446744073709551615").unwrap();
+/// assert!(n.is_u64());
+/// assert_eq!(n.as_u64(), Some(u64::MAX));
+/// assert_eq!(n.as_i64(), None);
+/// ```
+#[derive(Clone)]
+pub struct Number {
+    n: N,
+}
+
+#[cfg(not(feature = "arbitrary_precision"))]
+#[derive(Clone, Copy)]
+enum N {
+    PosInt(u64),
+    /// Always less than zero
+    NegInt(i64),
+    /// Always finite
+    Float(f64),
+}
+#[cfg(feature = "arbitrary_precision")]
+type N = String;
+
+impl Number {
+    /// Whether this number is an integer representable as an `i64`
+    pub fn is_i64(&self) -> bool {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return match self.n {
+            N::PosInt(v) => v <= i64::MAX as u64,
+            N::NegInt(_) => true,
+            N::Float(_) => false,
+        };
+        #[cfg(feature = "arbitrary_precision")]
+        return self.as_i64().is_some();
+    }
+
+    /// Whether this number is an integer representable as a `u64`
+    pub fn is_u64(&self) -> bool {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return matches!(self.n, N::PosInt(_));
+        #[cfg(feature = "arbitrary_precision")]
+        return self.as_u64().is_some();
+    }
+
+    /// Whether this number is neither an `i64` nor a `u64`, and so is
+    /// represented as an `f64`
+    pub fn is_f64(&self) -> bool {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return matches!(self.n, N::Float(_));
+        #[cfg(feature = "arbitrary_precision")]
+        return !self.is_i64() && !self.is_u64();
+    }
+
+    /// This number as an `i64`, if it is an integer in range
+    pub fn as_i64(&self) -> Option<i64> {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return match self.n {
+            N::PosInt(v) => i64::try_from(v).ok(),
+            N::NegInt(v) => Some(v),
+            N::Float(_) => None,
+        };
+        #[cfg(feature = "arbitrary_precision")]
+        return self.n.parse().ok();
+    }
+
+    /// This number as a `u64`, if it is a non-negative integer in range
+    pub fn as_u64(&self) -> Option<u64> {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return match self.n {
+            N::PosInt(v) => Some(v),
+            N::NegInt(_) | N::Float(_) => None,
+        };
+        #[cfg(feature = "arbitrary_precision")]
+        return self.n.parse().ok();
+    }
+
+    /// The nearest `f64` to this number
+    ///
+    /// This is `None` only for numbers which, with the `arbitrary_precision`
+    /// feature, are beyond the range of an `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return Some(match self.n {
+            N::PosInt(v) => v as f64,
+            N::NegInt(v) => v as f64,
+            N::Float(v) => v,
+        });
+        #[cfg(feature = "arbitrary_precision")]
+        return self.n.parse().ok().filter(|f: &f64| f.is_finite());
+    }
+
+    /// Convert a finite `f64` into a `Number`, or `None` if it is infinite or
+    /// NaN, which Rison cannot represent
+    pub fn from_f64(f: f64) -> Option<Number> {
+        if !f.is_finite() {
+            return None;
+        }
+
+        #[cfg(not(feature = "arbitrary_precision"))]
+        let n = N::Float(f);
+        #[cfg(feature = "arbitrary_precision")]
+        let n = crate::ser::format_float(f);
+        Some(Number { n })
+    }
+
+    /// Convert a finite `f32` into a `Number`, or `None` if it is infinite or
+    /// NaN
+    ///
+    /// The number is the shortest decimal which reads back as the same `f32`,
+    /// as the serializer writes an `f32`, so that `0.1f32` becomes `0.1`
+    /// rather than the exact value of the nearest `f64`.
+    pub fn from_f32(f: f32) -> Option<Number> {
+        if !f.is_finite() {
+            return None;
+        }
+
+        let shortest = crate::ser::format_float(f);
+        #[cfg(not(feature = "arbitrary_precision"))]
+        let n = N::Float(shortest.parse().expect("formatted floats parse"));
+        #[cfg(feature = "arbitrary_precision")]
+        let n = shortest;
+        Some(Number { n })
+    }
+
+    /// The exact decimal text of this number
+    #[cfg(feature = "arbitrary_precision")]
+    pub fn as_str(&self) -> &str {
+        &self.n
+    }
+
+    /// Wrap text which has already been validated as a Rison number
+    #[cfg(feature = "arbitrary_precision")]
+    pub(crate) fn from_string_unchecked(n: String) -> Number {
+        Number { n }
+    }
+}
+
+/// Numbers are equal if they have the same value, so `1` is equal to `1.0`.
+/// An integer is compared with a float at the float's precision. With the
+/// `arbitrary_precision` feature, numbers are equal only if their text is
+/// identical.
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return match (self.n, other.n) {
+            (N::PosInt(a), N::PosInt(b)) => a == b,
+            (N::NegInt(a), N::NegInt(b)) => a == b,
+            (N::PosInt(_), N::NegInt(_)) | (N::NegInt(_), N::PosInt(_)) => false,
+            _ => self.as_f64() == other.as_f64(),
+        };
+        #[cfg(feature = "arbitrary_precision")]
+        return self.n == other.n;
+    }
+}
+
+impl From<u64> for Number {
+    fn from(u: u64) -> Self {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        let n = N::PosInt(u);
+        #[cfg(feature = "arbitrary_precision")]
+        let n = u.to_string();
+        Number { n }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(i: i64) -> Self {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        let n = match u64::try_from(i) {
+            Ok(u) => N::PosInt(u),
+            Err(_) => N::NegInt(i),
+        };
+        #[cfg(feature = "arbitrary_precision")]
+        let n = i.to_string();
+        Number { n }
+    }
+}
+
+macro_rules! from_integer {
+    ($($ty:ty => $via:ty)*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(i: $ty) -> Self {
+                    Number::from(<$via>::from(i))
+                }
+            }
+        )*
+    };
+}
+
+from_integer! {
+    i8 => i64 i16 => i64 i32 => i64
+    u8 => u64 u16 => u64 u32 => u64
+}
+
+impl From<usize> for Number {
+    fn from(u: usize) -> Self {
+        Number::from(u as u64)
+    }
+}
+
+impl From<isize> for Number {
+    fn from(i: isize) -> Self {
+        Number::from(i as i64)
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return match self.n {
+            N::PosInt(v) => fmt::Display::fmt(&v, f),
+            N::NegInt(v) => fmt::Display::fmt(&v, f),
+            N::Float(v) => f.write_str(&crate::ser::format_float(v)),
+        };
+        #[cfg(feature = "arbitrary_precision")]
+        return f.write_str(&self.n);
+    }
+}
+
+impl fmt::Debug for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Number({})", self)
+    }
+}
+
+impl Serialize for Number {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return match self.n {
+            N::PosInt(v) => serializer.serialize_u64(v),
+            N::NegInt(v) => serializer.serialize_i64(v),
+            N::Float(v) => serializer.serialize_f64(v),
+        };
+        #[cfg(feature = "arbitrary_precision")]
+        return serializer.serialize_newtype_struct(TOKEN, &self.n);
+    }
+}
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return deserializer.deserialize_any(NumberVisitor);
+        #[cfg(feature = "arbitrary_precision")]
+        return deserializer.deserialize_newtype_struct(TOKEN, NumberVisitor);
+    }
+}
+
+pub(crate) struct NumberVisitor;
+
+impl<'de> Visitor<'de> for NumberVisitor {
+    type Value = Number;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a finite number")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Number, E> {
+        Ok(Number::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Number, E> {
+        Ok(Number::from(v))
+    }
+
+    fn visit_f32<E>(self, v: f32) -> std::result::Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Number::from_f32(v)
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Float(v.into()), &self))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Number, E>
+    where
+        E: de::Error,
+    {
+        Number::from_f64(v).ok_or_else(|| de::Error::invalid_value(de::Unexpected::Float(v), &self))
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Number, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<Number, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        if map.next_key::<NumberKey>()?.is_none() {
+            return Err(de::Error::invalid_type(de::Unexpected::Map, &self));
+        }
+        map.next_value::<String>()
+            .map(Number::from_string_unchecked)
+    }
+}
+
+/// The key of the single-entry map through which the deserializer provides
+/// exact numbers, distinguishing them from ordinary maps
+#[cfg(feature = "arbitrary_precision")]
+struct NumberKey;
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'de> Deserialize<'de> for NumberKey {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct FieldVisitor;
+
+        impl Visitor<'_> for FieldVisitor {
+            type Value = ();
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a number")
+            }
+
+            fn visit_str<E>(self, s: &str) -> std::result::Result<(), E>
+            where
+                E: de::Error,
+            {
+                if s == TOKEN {
+                    Ok(())
+                } else {
+                    Err(de::Error::custom("expected a number"))
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)?;
+        Ok(NumberKey)
+    }
+}
+
+/// Provides the text of a parsed number to a [`Number`] visitor
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) struct NumberDeserializer {
+    number: Option<String>,
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl NumberDeserializer {
+    pub(crate) fn new(number: String) -> Self {
+        Self {
+            number: Some(number),
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'de> de::MapAccess<'de> for NumberDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if self.number.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize(de::value::BorrowedStrDeserializer::new(TOKEN))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        match self.number.take() {
+            Some(number) => seed.deserialize(de::value::StringDeserializer::new(number)),
+            None => Err(de::Error::custom("number already taken")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Number;
+
+    #[test]
+    fn number_from_f64() {
+        assert_eq!(Number::from_f64(1.5).unwrap().as_f64(), Some(1.5));
+        assert!(Number::from_f64(f64::NAN).is_none());
+        assert!(Number::from_f64(f64::INFINITY).is_none());
+        assert_eq!(Number::from_f32(0.1).unwrap().as_f64(), Some(0.1));
+        assert_eq!(Number::from_f32(-3e-42).unwrap().to_string(), "-3e-42");
+        assert!(Number::from_f32(f32::NAN).is_none());
+        assert!(Number::from_f32(f32::NEG_INFINITY).is_none());
+    }
+    #[test]
+    fn non_finite_floats_in_values() {
+        use serde::de::IntoDeserializer;
+        use serde::Deserialize;
+
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let de: serde::de::value::F64Deserializer<crate::Error> = f.into_deserializer();
+            assert_eq!(crate::Value::deserialize(de).unwrap(), crate::Value::Null);
+            assert!(Number::deserialize(de).is_err());
+        }
+        let de: serde::de::value::F32Deserializer<crate::Error> = 0.1f32.into_deserializer();
+        let v = crate::Value::deserialize(de).unwrap();
+        assert_eq!(crate::to_string(&v).unwrap(), "0.1");
+    }
+    #[test]
+    fn fail_deserialize_out_of_range_numbers() {
+        use crate::error::ErrorCode;
+
+        let err = crate::from_str::<f64>("1e400").unwrap_err();
+        assert!(matches!(err.code(), ErrorCode::NumberOutOfRange));
+        assert_eq!(err.position(), Some(0));
+        let err = crate::from_str::<Vec<f64>>("!(1.5,-1e400)").unwrap_err();
+        assert!(matches!(err.code(), ErrorCode::NumberOutOfRange));
+        assert_eq!(err.position(), Some(6));
+        let digits = format!("1{}", "0".repeat(400));
+        assert!(crate::from_str::<u128>(&digits).is_err());
+
+        // Without `arbitrary_precision`, a `Value` would otherwise hold `Null`
+        let v = "!(1.5,1e400)".parse::<crate::Value>();
+        #[cfg(not(feature = "arbitrary_precision"))]
+        assert!(matches!(v.unwrap_err().code(), ErrorCode::NumberOutOfRange));
+        #[cfg(feature = "arbitrary_precision")]
+        assert_eq!(v.unwrap().to_string(), "!(1.5,1e400)");
+    }
+    #[test]
+    fn round_trip_floats_exactly() {
+        for f in [
+            0.1,
+            1e-7,
+            0.3,
+            2.0 / 3.0,
+            1e21,
+            1e-21,
+            123456789.123,
+            9007199254740993.0,
+            f64::MAX,
+            f64::MIN,
+            f64::EPSILON,
+            f64::MIN_POSITIVE,
+            // The largest subnormal, and the smallest
+            2.225073858507201e-308,
+            5e-324,
+            -5e-324,
+        ] {
+            assert_float_round_trips(f);
+        }
+        for f in [0.1f32, 1e-7, 16777217.0, f32::MAX, f32::MIN_POSITIVE, 1e-45] {
+            let rison = crate::to_string(&f).unwrap();
+            assert_eq!(crate::from_str::<f32>(&rison).unwrap(), f, "{rison}");
+            let v: crate::Value = crate::from_str(&rison).unwrap();
+            assert_eq!(crate::to_string(&v).unwrap(), rison);
+        }
+    }
+    /// Check that `f` is read back exactly, whether as an `f64` or within a
+    /// [`Value`](crate::Value), and that a `Value` writes it back unchanged
+    fn assert_float_round_trips(f: f64) {
+        let rison = crate::to_string(&f).unwrap();
+        let parsed: f64 = crate::from_str(&rison).unwrap();
+        assert_eq!(parsed.to_bits(), f.to_bits(), "{rison}");
+
+        let v: crate::Value = crate::from_str(&rison).unwrap();
+        let crate::Value::Number(n) = &v else {
+            panic!("{rison} is not a number");
+        };
+        // Negative zero is written as zero, and so reads back as zero
+        assert_eq!(n.as_f64(), Some(f), "{rison}");
+        assert_eq!(crate::to_string(&v).unwrap(), rison);
+    }
+    proptest::proptest! {
+        #[test]
+        fn round_trip_any_float(bits in proptest::prelude::any::<u64>()) {
+            let f = f64::from_bits(bits);
+            if f.is_finite() {
+                assert_float_round_trips(f);
+            }
+        }
+    }
+    #[test]
+    fn number_accessors() {
+        let v: Vec<Number> =
+            crate::from_str("!(-9223372036854775808,18446744073709551615,1.5)").unwrap();
+
+        assert!(v[0].is_i64() && !v[0].is_u64() && !v[0].is_f64());
+        assert_eq!(v[0].as_i64(), Some(i64::MIN));
+        assert!(!v[1].is_i64() && v[1].is_u64() && !v[1].is_f64());
+        assert_eq!(v[1].as_u64(), Some(u64::MAX));
+        assert!(!v[2].is_i64() && !v[2].is_u64() && v[2].is_f64());
+        assert_eq!(v[2].as_i64(), None);
+        assert_eq!(v[2].as_f64(), Some(1.5));
+    }
+    #[cfg(not(feature = "arbitrary_precision"))]
+    #[test]
+    fn compare_numbers_by_value() {
+        assert_eq!(Number::from(1), Number::from_f64(1.0).unwrap());
+        assert_eq!(Number::from(0), Number::from_f64(-0.0).unwrap());
+        assert_ne!(Number::from(u64::MAX), Number::from(-1));
+        assert_ne!(Number::from(1), Number::from_f64(1.5).unwrap());
+    }
+    #[test]
+    fn serialize_integers_exactly() {
+        let v = [Number::from(u64::MAX), Number::from(i64::MIN)];
+
+        assert_eq!(
+            crate::to_string(&v).unwrap(),
+            "!(18446744073709551615,-9223372036854775808)"
+        );
+    }
+    #[test]
+    fn display_number() {
+        assert_eq!(Number::from(-12).to_string(), "-12");
+        assert_eq!(Number::from_f64(1e21).unwrap().to_string(), "1e21");
+    }
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn deserialize_exact_number() {
+        let input = "!(12345678901234567890123,0.10000000000000000001,1.50)";
+        let v: Vec<Number> = crate::from_str(input).unwrap();
+
+        assert_eq!(v[0].as_str(), "12345678901234567890123");
+        assert_eq!(v[1].as_str(), "0.10000000000000000001");
+        assert_eq!(v[2].as_str(), "1.50");
+        assert_eq!(crate::to_string(&v).unwrap(), input);
+    }
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn deserialize_exact_number_in_value() {
+        let input = "(a:!(1.50,-0),id:340282366920938463463374607431768211455)";
+        let v: crate::Value = crate::from_str(input).unwrap();
+
+        assert_eq!(crate::to_string(&v).unwrap(), input);
+    }
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn deserialize_number_into_string() {
+        #[derive(serde::Deserialize)]
+        #[serde(transparent)]
+        struct Id(String);
+
+        #[derive(serde::Deserialize)]
+        struct Row {
+            id: Id,
+            name: String,
+        }
+
+        let v: Row = crate::from_str("(id:12345678901234567890123,name:'1e3')").unwrap();
+        assert_eq!(v.id.0, "12345678901234567890123");
+        assert_eq!(v.name, "1e3");
+    }
+}
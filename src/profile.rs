@@ -0,0 +1,190 @@
+//! The dialects of Rison written and read by other implementations
+
+use crate::de::NOT_ID_CHARS;
+
+/// Characters which may not begin an unquoted string, in addition to
+/// [`NOT_ID_CHARS`], as they would be parsed as the start of a number
+const NOT_ID_START: &[u8] = b"-0123456789";
+
+/// The dialect of Rison to write and read, for exchanging it with a
+/// particular peer implementation
+///
+/// Implementations differ in which numbers they accept, and in which
+/// strings they write and read without quotes. A profile set with
+/// [`SerializerBuilder::profile`](crate::SerializerBuilder::profile) decides
+/// which strings are written unquoted, and one set with
+/// [`DeserializerBuilder::profile`](crate::DeserializerBuilder::profile)
+/// how numbers and unquoted strings are read. Options such as
+/// [`DeserializerBuilder::allow_reserved_chars`](crate::DeserializerBuilder::allow_reserved_chars)
+/// apply in addition to the profile.
+///
+/// ```
+/// use rison::Profile;
+///
+/// assert!(!Profile::Strict.is_valid_id("2024-01-01"));
+/// assert!(Profile::Lenient.is_valid_id("2024-01-01"));
+/// assert!(!Profile::Lenient.is_valid_id("2024"));
+///
+/// let builder = rison::Deserializer::builder().profile(Profile::Lenient);
+/// let date: String = serde::Deserialize::deserialize(&mut builder.from_str("2024-01-01")).unwrap();
+/// assert_eq!(date, "2024-01-01");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Profile {
+    /// The Rison grammar, exactly
+    ///
+    /// Numbers match `-?(0|[1-9][0-9]*)(\.[0-9]+)?(e-?[0-9]+)?`, and
+    /// unquoted strings may contain none of ` '!:(),*@$` and may not begin
+    /// with `-` or a digit.
+    #[default]
+    Strict,
+    /// As rison.js and rison-node, and hence Kibana, read and write
+    ///
+    /// Numbers may also have leading zeros, a `.` without digits after it,
+    /// and a capital `E`, as in `007`, `1.` or `1E3`. Strings are written
+    /// as under [`Strict`](Profile::Strict), which these implementations
+    /// read back.
+    RisonJs,
+    /// As [`RisonJs`](Profile::RisonJs), and additionally reading values
+    /// which begin with `-` or a digit but are not numbers, such as
+    /// `2024-01-01` or `-a`, as unquoted strings, and permitting the
+    /// reserved `*` and `@` within unquoted strings
+    ///
+    /// Strings are written unquoted wherever such a reader reads them back,
+    /// which other profiles may not.
+    Lenient,
+}
+
+impl Profile {
+    /// Whether `value` may be written as an unquoted string, as an object
+    /// key or otherwise, and read back as the same string under this profile
+    ///
+    /// Under [`Strict`](Profile::Strict) and [`RisonJs`](Profile::RisonJs),
+    /// this is [`is_valid_id`](crate::is_valid_id).
+    pub fn is_valid_id(self, value: &str) -> bool {
+        let bytes = value.as_bytes();
+        let Some(first) = bytes.first() else {
+            return false;
+        };
+        let is_id_char =
+            |b: &u8| !NOT_ID_CHARS.contains(b) || self.reserved_chars_in_ids() && b"*@".contains(b);
+        if !bytes.iter().all(is_id_char) {
+            return false;
+        }
+        if !NOT_ID_START.contains(first) {
+            return true;
+        }
+        self.numeric_ids() && !crate::de::is_number(bytes, self.js_numbers())
+    }
+
+    /// Whether numbers are read as rison.js reads them
+    pub(crate) fn js_numbers(self) -> bool {
+        matches!(self, Profile::RisonJs | Profile::Lenient)
+    }
+
+    /// Whether unquoted strings may contain the reserved `*` and `@`
+    pub(crate) fn reserved_chars_in_ids(self) -> bool {
+        matches!(self, Profile::Lenient)
+    }
+
+    /// Whether values beginning like a number but which are not numbers are
+    /// unquoted strings, rather than invalid
+    pub(crate) fn numeric_ids(self) -> bool {
+        matches!(self, Profile::Lenient)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::Profile;
+    use crate::Value;
+
+    #[test]
+    fn valid_ids_by_profile() {
+        for (value, strict, lenient) in [
+            ("abc", true, true),
+            ("2024-01-01", false, true),
+            ("-a", false, true),
+            ("-", false, true),
+            ("1x", false, true),
+            ("a@b*c", false, true),
+            ("12", false, false),
+            ("007", false, false),
+            ("1.", false, false),
+            ("1E3", false, false),
+            ("a b", false, false),
+            ("", false, false),
+        ] {
+            assert_eq!(Profile::Strict.is_valid_id(value), strict, "{value}");
+            assert_eq!(Profile::RisonJs.is_valid_id(value), strict, "{value}");
+            assert_eq!(Profile::Lenient.is_valid_id(value), lenient, "{value}");
+            assert_eq!(crate::is_valid_id(value), strict, "{value}");
+        }
+    }
+    #[test]
+    fn deserialize_numbers_by_profile() {
+        for (input, strict, js) in [
+            ("12", Some(12.0), Some(12.0)),
+            ("007", None, Some(7.0)),
+            ("1.", None, Some(1.0)),
+            ("-2.e1", None, Some(-20.0)),
+            ("1E3", None, Some(1000.0)),
+            ("1e+3", None, None),
+            ("--1", None, None),
+        ] {
+            for (profile, expected) in [(Profile::Strict, strict), (Profile::RisonJs, js)] {
+                let builder = crate::Deserializer::builder().profile(profile);
+                let n = f64::deserialize(&mut builder.from_str(input)).ok();
+                assert_eq!(n, expected, "{input} as {profile:?}");
+            }
+        }
+    }
+    #[test]
+    fn rewrite_js_numbers_in_rison_grammar() {
+        let builder = crate::Deserializer::builder().profile(Profile::RisonJs);
+        let numbers: Vec<_> = crate::de::Parser::new(builder.from_str("!(007,1.,-00.5,1.E3,0)"))
+            .filter_map(|event| match event.unwrap().into_inner() {
+                crate::de::Event::Number(n) => Some(n.into_owned()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(numbers, ["7", "1", "-0.5", "1e3", "0"]);
+    }
+    #[test]
+    fn deserialize_lenient_ids() {
+        let builder = crate::Deserializer::builder().profile(Profile::Lenient);
+        let value = Value::deserialize(&mut builder.from_str("!(2024-01-01,-a,a@b,007,-1.5)"));
+
+        assert_eq!(
+            value.unwrap(),
+            crate::from_str::<Value>("!('2024-01-01','-a','a@b',7,-1.5)").unwrap()
+        );
+        let builder = crate::Deserializer::builder().profile(Profile::RisonJs);
+        assert!(Value::deserialize(&mut builder.from_str("2024-01-01")).is_err());
+    }
+    #[test]
+    fn serialize_strings_by_profile() {
+        let value = ("2024-01-01", "a@b", "007", "x");
+        let lenient = crate::Serializer::builder().profile(Profile::Lenient);
+
+        assert_eq!(
+            crate::to_string(&value).unwrap(),
+            "!('2024-01-01','a@b','007',x)"
+        );
+        assert_eq!(
+            lenient.to_string(&value).unwrap(),
+            "!(2024-01-01,a@b,'007',x)"
+        );
+        let builder = crate::Deserializer::builder().profile(Profile::Lenient);
+        let rison = lenient.to_string(&value).unwrap();
+        let back = <(String, String, String, String)>::deserialize(&mut builder.from_str(&rison));
+        assert_eq!(
+            back.unwrap(),
+            ("2024-01-01".into(), "a@b".into(), "007".into(), "x".into())
+        );
+    }
+}
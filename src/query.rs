@@ -0,0 +1,143 @@
+//! Parsing Rison from URL query strings
+//!
+//! Rison is typically embedded in a query string as the value of a named
+//! parameter, as in `/search?state=(q:rust,page:2)&tab=open`. Query strings
+//! are encoded as `application/x-www-form-urlencoded`, so values are decoded,
+//! with `+` decoding to a space and `%xx` to the byte it encodes, as they are
+//! parsed.
+
+use std::collections::btree_map::{BTreeMap, Entry};
+
+use serde::de::DeserializeOwned;
+
+use crate::de::read::IterRead;
+use crate::de::UrlDecode;
+use crate::error::Result;
+use crate::value::Value;
+
+/// Deserialize an instance of `T` from the value of the query parameter
+/// called `name`, or return `None` if there is no such parameter
+///
+/// `query` is the part of a URL after the `?`, and is split into parameters
+/// at `&`. Parameter names are decoded before being compared to `name`. If
+/// the parameter appears more than once, the first occurrence is used.
+///
+/// ```
+/// #[derive(Debug, PartialEq, serde::Deserialize)]
+/// struct Search {
+///     q: String,
+///     page: u32,
+/// }
+///
+/// let query = "tab=open&state=(q:'rust+serde',page:%32)";
+///
+/// assert_eq!(
+///     rison::from_query::<Search>(query, "state").unwrap(),
+///     Some(Search { q: "rust serde".into(), page: 2 }),
+/// );
+/// assert_eq!(rison::from_query::<Search>(query, "other").unwrap(), None);
+/// ```
+pub fn from_query<T>(query: &str, name: &str) -> Result<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    let Some(value) = find_param(query, name) else {
+        return Ok(None);
+    };
+    parse_param(value).map(Some)
+}
+
+/// Parse the value of every parameter of a query string as Rison, by
+/// decoded parameter name
+///
+/// Pages often carry several independent Rison parameters, such as
+/// Kibana's `_g` and `_a`. Each is parsed separately, so one which is not
+/// valid Rison fails alone, with its own error. As with [`from_query`], a
+/// parameter appearing more than once is parsed from its first occurrence,
+/// and a parameter without a `=` has an empty value, which is not valid
+/// Rison. Names which do not decode as utf-8 have invalid sequences
+/// replaced with `U+FFFD`.
+///
+/// ```
+/// let query = "_g=(time:(from:now-15m))&_a=(query:'status:500')&tab=open&x=(";
+/// let params = rison::query::parse_all(query);
+///
+/// assert_eq!(params["_a"].as_ref().unwrap().to_string(), "(query:'status:500')");
+/// assert_eq!(params["tab"].as_ref().unwrap().as_str(), Some("open"));
+/// assert!(params["x"].is_err());
+/// ```
+pub fn parse_all(query: &str) -> BTreeMap<String, Result<Value>> {
+    let mut params = BTreeMap::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (encoded_name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let name: Vec<u8> = UrlDecode::new(encoded_name).map_while(|b| b.ok()).collect();
+        if let Entry::Vacant(entry) = params.entry(String::from_utf8_lossy(&name).into_owned()) {
+            entry.insert(parse_param(value));
+        }
+    }
+    params
+}
+
+/// Deserialize an instance of `T` from the still-encoded value of a
+/// parameter
+pub(crate) fn parse_param<T>(value: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    crate::de::from_trait(IterRead::new(UrlDecode::new(value)))
+}
+
+/// Find the still-encoded value of the first parameter called `name`
+pub(crate) fn find_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| {
+        let (encoded_name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let decoded_name = UrlDecode::new(encoded_name).map_while(|b| b.ok());
+        decoded_name.eq(name.bytes()).then_some(value)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    #[test]
+    fn deserialize_from_query() {
+        let query = "a=1&b%5B%5D=!(x,%27y+z%27)&c=(d:'100%+sure')&b[]=ignored";
+
+        let v: Option<serde_json::Value> = super::from_query(query, "b[]").unwrap();
+        assert_eq!(v, Some(json!(["x", "y z"])));
+        let v: Option<serde_json::Value> = super::from_query(query, "c").unwrap();
+        assert_eq!(v, Some(json!({"d": "100% sure"})));
+        let v: Option<u32> = super::from_query(query, "a").unwrap();
+        assert_eq!(v, Some(1));
+        let v: Option<u32> = super::from_query(query, "d").unwrap();
+        assert_eq!(v, None);
+    }
+    #[test]
+    fn fail_deserialize_from_query() {
+        let query = "a=(b:1)x&c=%FF&d";
+
+        assert!(super::from_query::<serde_json::Value>(query, "a").is_err());
+        assert!(super::from_query::<serde_json::Value>(query, "c").is_err());
+        assert!(super::from_query::<serde_json::Value>(query, "d").is_err());
+    }
+    #[test]
+    fn parse_all_params() {
+        let query = "a=1&b%5B%5D=!(x,%27y+z%27)&&c=(d:1)x&b[]=ignored&e&%FF=2";
+        let params = super::parse_all(query);
+
+        assert_eq!(
+            params.keys().collect::<Vec<_>>(),
+            ["a", "b[]", "c", "e", "\u{fffd}"]
+        );
+        assert_eq!(
+            params["a"].as_ref().unwrap(),
+            &crate::Value::Number(1.into())
+        );
+        assert_eq!(params["b[]"].as_ref().unwrap().to_string(), "!(x,'y z')");
+        assert!(params["c"].as_ref().unwrap_err().is_syntax());
+        assert!(params["e"].is_err());
+        assert!(params["\u{fffd}"].is_ok());
+        assert!(super::parse_all("").is_empty());
+    }
+}
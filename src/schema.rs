@@ -0,0 +1,846 @@
+//! Validation of Rison values against a JSON Schema
+//!
+//! A [`Schema`] is compiled from a JSON Schema document, itself given as a
+//! [`Value`], and may then either [`validate`](Schema::validate) a value
+//! which has already been parsed, or validate Rison as it is deserialized.
+//! In the latter case, as a [`DeserializeSeed`], errors carry the position
+//! of the offending value within the input, and its path with the `path`
+//! feature.
+//!
+//! ```
+//! use rison::schema::Schema;
+//!
+//! let document = "(type:object,\
+//!     properties:(page:(type:integer,minimum:1),q:(type:string,maxLength:64)),\
+//!     required:!(q),additionalProperties:!f)";
+//! let schema = Schema::new(&document.parse().unwrap()).unwrap();
+//!
+//! let value = rison::de::from_str_seed("(page:2,q:rust)", &schema).unwrap();
+//! assert_eq!(value.get("page").and_then(|page| page.as_u64()), Some(2));
+//!
+//! let err = rison::de::from_str_seed("(page:0,q:rust)", &schema).unwrap_err();
+//! assert_eq!(err.position(), Some(6));
+//! assert!(err.to_string().contains("expected a number at least 1"));
+//! ```
+//!
+//! # Supported keywords
+//!
+//! The assertions `type`, `enum`, `const`, `minimum`, `maximum`,
+//! `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`, `minLength`,
+//! `maxLength`, `items`, `prefixItems`, `additionalItems`, `minItems`,
+//! `maxItems`, `uniqueItems`, `properties`, `additionalProperties`,
+//! `required`, `minProperties` and `maxProperties` are supported, as are the
+//! combinators `allOf`, `anyOf`, `oneOf` and `not`, and boolean schemas.
+//!
+//! References, conditionals and keywords requiring regular expressions, such
+//! as `$ref`, `if` and `pattern`, are not supported, and are rejected by
+//! [`Schema::new`] rather than ignored, so that a schema is never silently
+//! more permissive than it reads. Annotations such as `title`, `default` and
+//! `format`, and unknown keywords, are ignored.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, Deserialize, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+
+#[cfg(feature = "path")]
+use crate::error::Segment;
+use crate::error::{Error, Result};
+#[cfg(feature = "arbitrary_precision")]
+use crate::value::{KeyClass, KeyClassifier};
+use crate::value::{Map, Value, ValueVisitor};
+
+/// Keywords which could change the outcome of validation, but which are not
+/// implemented
+const UNSUPPORTED: &[&str] = &[
+    "$ref",
+    "$dynamicRef",
+    "$recursiveRef",
+    "pattern",
+    "patternProperties",
+    "propertyNames",
+    "contains",
+    "minContains",
+    "maxContains",
+    "if",
+    "then",
+    "else",
+    "dependencies",
+    "dependentRequired",
+    "dependentSchemas",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// A compiled JSON Schema
+///
+/// Used as a [`DeserializeSeed`], a schema deserializes a [`Value`],
+/// validating each nested value as soon as it has been parsed, as with
+/// [`rison::de::from_str_seed`](crate::de::from_str_seed).
+#[derive(Clone, Debug)]
+pub struct Schema {
+    inner: Inner,
+}
+
+#[derive(Clone, Debug)]
+enum Inner {
+    /// `!t` accepts every value, and `!f` none
+    Bool(bool),
+    Checks(Box<Checks>),
+}
+
+/// The schema which accepts every value
+static ANY: Schema = Schema {
+    inner: Inner::Bool(true),
+};
+
+#[derive(Clone, Debug, Default)]
+struct Checks {
+    types: Option<Types>,
+    enumeration: Option<Vec<Value>>,
+    constant: Option<Value>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    multiple_of: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    prefix_items: Vec<Schema>,
+    items: Option<Schema>,
+    additional_items: Option<Schema>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    unique_items: bool,
+    properties: BTreeMap<String, Schema>,
+    additional_properties: Option<Schema>,
+    required: Vec<String>,
+    min_properties: Option<usize>,
+    max_properties: Option<usize>,
+    all_of: Vec<Schema>,
+    any_of: Vec<Schema>,
+    one_of: Vec<Schema>,
+    not: Option<Schema>,
+}
+
+/// The JSON Schema type names, and how each is described in errors
+const TYPES: [(&str, &str); 7] = [
+    ("null", "null"),
+    ("boolean", "a boolean"),
+    ("object", "an object"),
+    ("array", "a list"),
+    ("number", "a number"),
+    ("string", "a string"),
+    ("integer", "an integer"),
+];
+
+/// A set of the [`TYPES`], one bit for each
+#[derive(Clone, Copy, Debug)]
+struct Types(u8);
+
+impl Types {
+    fn parse(value: &Value) -> Result<Self> {
+        match value {
+            Value::String(name) => Types::named(name),
+            Value::Array(names) => names.iter().try_fold(Types(0), |types, name| {
+                let name = name
+                    .as_str()
+                    .ok_or_else(|| malformed("type", "a list of strings"))?;
+                Ok(Types(types.0 | Types::named(name)?.0))
+            }),
+            _ => Err(malformed("type", "a string or a list")),
+        }
+    }
+
+    fn named(name: &str) -> Result<Self> {
+        match TYPES.iter().position(|&(known, _)| known == name) {
+            Some(index) => Ok(Types(1 << index)),
+            None => Err(invalid(format_args!("unknown type `{}`", name))),
+        }
+    }
+
+    fn contains(self, name: &str) -> bool {
+        let index = TYPES.iter().position(|&(known, _)| known == name);
+        index.is_some_and(|index| self.0 & (1 << index) != 0)
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match value {
+            Value::Null => self.contains("null"),
+            Value::Bool(_) => self.contains("boolean"),
+            Value::Object(_) => self.contains("object"),
+            Value::Array(_) => self.contains("array"),
+            Value::String(_) => self.contains("string"),
+            Value::Number(_) => {
+                self.contains("number") || (self.contains("integer") && is_integer(value))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Types {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = TYPES
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| self.0 & (1 << index) != 0)
+            .map(|(_, &(_, description))| description);
+        if let Some(first) = names.next() {
+            f.write_str(first)?;
+        }
+        for description in names {
+            write!(f, " or {}", description)?;
+        }
+        Ok(())
+    }
+}
+
+impl Schema {
+    /// Compile the JSON Schema `document`
+    ///
+    /// Fails if the document is not a valid schema, or uses a keyword which
+    /// is not supported.
+    pub fn new(document: &Value) -> Result<Self> {
+        let object = match document {
+            Value::Bool(accept) => {
+                return Ok(Self {
+                    inner: Inner::Bool(*accept),
+                })
+            }
+            Value::Object(object) => object,
+            _ => return Err(invalid("a schema must be an object or a boolean")),
+        };
+
+        let mut checks = Checks::default();
+        for (keyword, value) in object {
+            let keyword = keyword.as_str();
+            match keyword {
+                "type" => checks.types = Some(Types::parse(value)?),
+                "enum" => {
+                    let values = value
+                        .as_array()
+                        .ok_or_else(|| malformed(keyword, "a list"))?;
+                    checks.enumeration = Some(values.clone());
+                }
+                "const" => checks.constant = Some(value.clone()),
+                "minimum" => checks.minimum = Some(number(keyword, value)?),
+                "maximum" => checks.maximum = Some(number(keyword, value)?),
+                "exclusiveMinimum" => checks.exclusive_minimum = Some(number(keyword, value)?),
+                "exclusiveMaximum" => checks.exclusive_maximum = Some(number(keyword, value)?),
+                "multipleOf" => match number(keyword, value)? {
+                    n if n > 0.0 => checks.multiple_of = Some(n),
+                    _ => return Err(malformed(keyword, "a positive number")),
+                },
+                "minLength" => checks.min_length = Some(count(keyword, value)?),
+                "maxLength" => checks.max_length = Some(count(keyword, value)?),
+                // Before draft 2020-12, `items` was also written as a list
+                "items" if value.is_array() => checks.prefix_items = schemas(keyword, value)?,
+                "items" => checks.items = Some(Schema::new(value)?),
+                "prefixItems" => checks.prefix_items = schemas(keyword, value)?,
+                "additionalItems" => checks.additional_items = Some(Schema::new(value)?),
+                "minItems" => checks.min_items = Some(count(keyword, value)?),
+                "maxItems" => checks.max_items = Some(count(keyword, value)?),
+                "uniqueItems" => {
+                    let unique = value
+                        .as_bool()
+                        .ok_or_else(|| malformed(keyword, "a boolean"))?;
+                    checks.unique_items = unique;
+                }
+                "properties" => {
+                    let properties = value
+                        .as_object()
+                        .ok_or_else(|| malformed(keyword, "an object"))?;
+                    for (key, property) in properties {
+                        checks
+                            .properties
+                            .insert(key.clone(), Schema::new(property)?);
+                    }
+                }
+                "additionalProperties" => {
+                    checks.additional_properties = Some(Schema::new(value)?);
+                }
+                "required" => {
+                    let keys = value
+                        .as_array()
+                        .ok_or_else(|| malformed(keyword, "a list"))?;
+                    for key in keys {
+                        let key = key
+                            .as_str()
+                            .ok_or_else(|| malformed(keyword, "a list of strings"))?;
+                        checks.required.push(key.to_owned());
+                    }
+                }
+                "minProperties" => checks.min_properties = Some(count(keyword, value)?),
+                "maxProperties" => checks.max_properties = Some(count(keyword, value)?),
+                "allOf" => checks.all_of = schemas(keyword, value)?,
+                "anyOf" => checks.any_of = schemas(keyword, value)?,
+                "oneOf" => checks.one_of = schemas(keyword, value)?,
+                "not" => checks.not = Some(Schema::new(value)?),
+                _ if UNSUPPORTED.contains(&keyword) => {
+                    return Err(invalid(format_args!(
+                        "unsupported schema keyword `{}`",
+                        keyword
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            inner: Inner::Checks(Box::new(checks)),
+        })
+    }
+
+    /// Validate `value` against this schema
+    ///
+    /// Nested values are validated before the list or object containing
+    /// them, and the first error found is returned. As the value has no
+    /// source text, the error has no position, but has a path with the
+    /// `path` feature.
+    ///
+    /// ```
+    /// use rison::schema::Schema;
+    ///
+    /// let schema = Schema::new(&"(items:(enum:!(asc,desc)))".parse().unwrap()).unwrap();
+    ///
+    /// assert!(schema.validate(&"!(asc,desc)".parse().unwrap()).is_ok());
+    /// assert!(schema.validate(&"!(asc,up)".parse().unwrap()).is_err());
+    /// ```
+    pub fn validate(&self, value: &Value) -> Result<()> {
+        match value {
+            Value::Array(elements) => {
+                for (index, element) in elements.iter().enumerate() {
+                    let result = self.element(index).validate(element);
+                    #[cfg(feature = "path")]
+                    let result = result.map_err(|err| err.within(Segment::Index(index)));
+                    result?;
+                }
+            }
+            Value::Object(object) => {
+                for (key, value) in object {
+                    self.check_key(key)?;
+                    let result = self.property(key).validate(value);
+                    #[cfg(feature = "path")]
+                    let result = result.map_err(|err| err.within(Segment::Key(key.clone())));
+                    result?;
+                }
+            }
+            _ => {}
+        }
+        self.check(value)
+    }
+
+    /// Whether `value` is valid against this schema
+    pub fn is_valid(&self, value: &Value) -> bool {
+        self.validate(value).is_ok()
+    }
+
+    fn checks(&self) -> Option<&Checks> {
+        match &self.inner {
+            Inner::Bool(_) => None,
+            Inner::Checks(checks) => Some(checks),
+        }
+    }
+
+    /// The schema for the list element at `index`
+    fn element(&self, index: usize) -> &Schema {
+        let Some(checks) = self.checks() else {
+            return self;
+        };
+        checks
+            .prefix_items
+            .get(index)
+            .or(checks.items.as_ref())
+            .or(checks.additional_items.as_ref())
+            .unwrap_or(&ANY)
+    }
+
+    /// The schema for the value of the object entry with `key`
+    fn property(&self, key: &str) -> &Schema {
+        let Some(checks) = self.checks() else {
+            return self;
+        };
+        checks
+            .properties
+            .get(key)
+            .or(checks.additional_properties.as_ref())
+            .unwrap_or(&ANY)
+    }
+
+    /// Reject keys which `additionalProperties: !f` forbids, so that they
+    /// are reported as unexpected rather than their values as invalid
+    fn check_key(&self, key: &str) -> Result<()> {
+        let Some(checks) = self.checks() else {
+            return Ok(());
+        };
+        match &checks.additional_properties {
+            Some(Schema {
+                inner: Inner::Bool(false),
+            }) if !checks.properties.contains_key(key) => {
+                Err(invalid(format_args!("unexpected key `{}`", key)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validate `value` itself, assuming that any nested values have already
+    /// been validated
+    fn check(&self, value: &Value) -> Result<()> {
+        match &self.inner {
+            Inner::Bool(true) => Ok(()),
+            Inner::Bool(false) => Err(invalid("no value is allowed here")),
+            Inner::Checks(checks) => checks.check(value),
+        }
+    }
+}
+
+impl Checks {
+    fn check(&self, value: &Value) -> Result<()> {
+        if let Some(types) = self.types {
+            if !types.matches(value) {
+                return Err(invalid(format_args!("expected {}", types)));
+            }
+        }
+        if let Some(values) = &self.enumeration {
+            if !values.contains(value) {
+                let values = values.iter().map(|v| format!("`{}`", v));
+                let values = values.collect::<Vec<_>>().join(", ");
+                return Err(invalid(format_args!("expected one of {}", values)));
+            }
+        }
+        if let Some(constant) = &self.constant {
+            if value != constant {
+                return Err(invalid(format_args!("expected `{}`", constant)));
+            }
+        }
+
+        match value {
+            Value::Number(number) => {
+                if let Some(n) = number.as_f64() {
+                    self.check_number(n)?;
+                }
+            }
+            Value::String(s) => {
+                let len = s.chars().count();
+                check_count(
+                    len,
+                    self.min_length,
+                    self.max_length,
+                    "a string",
+                    "characters",
+                )?;
+            }
+            Value::Array(elements) => {
+                check_count(
+                    elements.len(),
+                    self.min_items,
+                    self.max_items,
+                    "a list",
+                    "elements",
+                )?;
+                if self.unique_items {
+                    for (index, element) in elements.iter().enumerate() {
+                        if elements[..index].contains(element) {
+                            return Err(invalid(format_args!(
+                                "expected unique list elements, but `{}` is repeated",
+                                element
+                            )));
+                        }
+                    }
+                }
+            }
+            Value::Object(object) => {
+                let (min, max) = (self.min_properties, self.max_properties);
+                check_count(object.len(), min, max, "an object", "entries")?;
+                if let Some(key) = self.required.iter().find(|&key| !object.contains_key(key)) {
+                    return Err(invalid(format_args!("missing key `{}`", key)));
+                }
+            }
+            Value::Null | Value::Bool(_) => {}
+        }
+
+        for schema in &self.all_of {
+            schema.validate(value)?;
+        }
+        if !self.any_of.is_empty() && !self.any_of.iter().any(|s| s.is_valid(value)) {
+            return Err(invalid(
+                "expected a value matching at least one `anyOf` schema",
+            ));
+        }
+        if !self.one_of.is_empty() {
+            let matched = self.one_of.iter().filter(|s| s.is_valid(value)).count();
+            if matched != 1 {
+                return Err(invalid(format_args!(
+                    "expected a value matching exactly one `oneOf` schema, but it matched {}",
+                    matched
+                )));
+            }
+        }
+        if self.not.as_ref().is_some_and(|s| s.is_valid(value)) {
+            return Err(invalid("expected a value not matching the `not` schema"));
+        }
+        Ok(())
+    }
+
+    fn check_number(&self, n: f64) -> Result<()> {
+        let bounds = [
+            (self.minimum, "at least", f64::ge as fn(&f64, &f64) -> bool),
+            (self.maximum, "at most", f64::le),
+            (self.exclusive_minimum, "greater than", f64::gt),
+            (self.exclusive_maximum, "less than", f64::lt),
+        ];
+        for (bound, relation, satisfies) in bounds {
+            if let Some(bound) = bound.filter(|bound| !satisfies(&n, bound)) {
+                return Err(invalid(format_args!(
+                    "expected a number {} {}, found {}",
+                    relation, bound, n
+                )));
+            }
+        }
+        if let Some(divisor) = self.multiple_of {
+            // Allow for the rounding of decimal divisors such as `0.1`
+            let quotient = n / divisor;
+            if (quotient - quotient.round()).abs() > f64::EPSILON * quotient.abs().max(1.0) {
+                return Err(invalid(format_args!(
+                    "expected a multiple of {}, found {}",
+                    divisor, n
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_count(
+    len: usize,
+    min: Option<usize>,
+    max: Option<usize>,
+    kind: &str,
+    units: &str,
+) -> Result<()> {
+    if let Some(min) = min.filter(|&min| len < min) {
+        return Err(invalid(format_args!(
+            "expected {} of at least {} {}, found {}",
+            kind, min, units, len
+        )));
+    }
+    if let Some(max) = max.filter(|&max| len > max) {
+        return Err(invalid(format_args!(
+            "expected {} of at most {} {}, found {}",
+            kind, max, units, len
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `value` is a number with no fractional part, as JSON Schema
+/// considers `1.0` an integer
+fn is_integer(value: &Value) -> bool {
+    value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+}
+
+fn invalid(msg: impl fmt::Display) -> Error {
+    de::Error::custom(msg)
+}
+
+fn malformed(keyword: &str, expected: &str) -> Error {
+    invalid(format_args!("`{}` must be {}", keyword, expected))
+}
+
+fn number(keyword: &str, value: &Value) -> Result<f64> {
+    value.as_f64().ok_or_else(|| malformed(keyword, "a number"))
+}
+
+fn count(keyword: &str, value: &Value) -> Result<usize> {
+    value
+        .as_u64()
+        .and_then(|n| usize::try_from(n).ok())
+        .ok_or_else(|| malformed(keyword, "a non-negative integer"))
+}
+
+fn schemas(keyword: &str, value: &Value) -> Result<Vec<Schema>> {
+    let documents = value
+        .as_array()
+        .ok_or_else(|| malformed(keyword, "a list"))?;
+    documents.iter().map(Schema::new).collect()
+}
+
+impl<'de> DeserializeSeed<'de> for &Schema {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return deserializer.deserialize_any(Validator(self));
+        #[cfg(feature = "arbitrary_precision")]
+        return deserializer.deserialize_newtype_struct(crate::number::TOKEN, Validator(self));
+    }
+}
+
+/// Builds a [`Value`] as [`ValueVisitor`] does, validating each value once
+/// it is complete
+struct Validator<'s>(&'s Schema);
+
+impl Validator<'_> {
+    fn finish<E>(&self, value: Value) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.0.check(&value).map_err(E::custom)?;
+        Ok(value)
+    }
+}
+
+impl<'de> Visitor<'de> for Validator<'_> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid Rison value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(ValueVisitor.visit_bool(v)?)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(ValueVisitor.visit_i64(v)?)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(ValueVisitor.visit_u64(v)?)
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(ValueVisitor.visit_f64(v)?)
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(Value::String(v))
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.finish(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> std::result::Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.0.deserialize(deserializer)
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element_seed(self.0.element(vec.len()))? {
+            vec.push(element);
+        }
+        self.finish(Value::Array(vec))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut object = Map::new();
+
+        #[cfg(feature = "arbitrary_precision")]
+        match map.next_key_seed(KeyClassifier)? {
+            Some(KeyClass::Number) => {
+                let number = map.next_value()?;
+                let number = crate::Number::from_string_unchecked(number);
+                return self.finish(Value::Number(number));
+            }
+            Some(KeyClass::Map(key)) => {
+                self.0.check_key(&key).map_err(de::Error::custom)?;
+                let value = map.next_value_seed(self.0.property(&key))?;
+                object.insert(key, value);
+            }
+            None => return self.finish(Value::Object(object)),
+        }
+
+        while let Some(key) = map.next_key_seed(Key(self.0))? {
+            let value = map.next_value_seed(self.0.property(&key))?;
+            object.insert(key, value);
+        }
+        self.finish(Value::Object(object))
+    }
+}
+
+/// Deserializes an object key, rejecting it if the schema forbids it
+struct Key<'s>(&'s Schema);
+
+impl<'de> DeserializeSeed<'de> for Key<'_> {
+    type Value = String;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<String, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let key = String::deserialize(deserializer)?;
+        self.0.check_key(&key).map_err(de::Error::custom)?;
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Schema;
+    use crate::value::Value;
+
+    fn schema(rison: &str) -> Schema {
+        Schema::new(&rison.parse().unwrap()).unwrap()
+    }
+
+    fn value(rison: &str) -> Value {
+        rison.parse().unwrap()
+    }
+
+    #[test]
+    fn validate_keywords() {
+        for (schema_rison, valid, invalid) in [
+            ("(type:string)", "abc", "1"),
+            ("(type:!(integer,null))", "!n", "1.5"),
+            ("(type:integer)", "2.0", "!t"),
+            ("(enum:!(asc,desc))", "desc", "up"),
+            ("(const:(a:1))", "(a:1)", "(a:2)"),
+            ("(minimum:1,maximum:3)", "3", "0"),
+            ("(exclusiveMinimum:1)", "1.5", "1"),
+            ("(exclusiveMaximum:1)", "0", "1"),
+            ("(multipleOf:0.1)", "0.3", "0.35"),
+            ("(minLength:2,maxLength:3)", "'é!!'", "abcd"),
+            ("(minItems:1,maxItems:2)", "!(a)", "!()"),
+            ("(uniqueItems:!t)", "!(1,2)", "!(1,2,1)"),
+            ("(items:(type:integer))", "!(1,2)", "!(1,x)"),
+            ("(prefixItems:!((type:string)),items:!f)", "!(a)", "!(a,b)"),
+            (
+                "(items:!((type:string)),additionalItems:(type:integer))",
+                "!(a,1)",
+                "!(a,b)",
+            ),
+            ("(required:!(a),minProperties:2)", "(a:1,b:2)", "(b:2,c:3)"),
+            ("(maxProperties:1)", "(a:1)", "(a:1,b:2)"),
+            ("(properties:(a:(type:integer)))", "(a:1,b:x)", "(a:x)"),
+            (
+                "(properties:(a:!t),additionalProperties:!f)",
+                "(a:x)",
+                "(a:x,b:y)",
+            ),
+            ("(additionalProperties:(type:string))", "(a:x)", "(a:1)"),
+            ("(allOf:!((minimum:1),(maximum:2)))", "2", "3"),
+            ("(anyOf:!((type:string),(minimum:1)))", "x", "0"),
+            ("(oneOf:!((minimum:1),(maximum:2)))", "3", "2"),
+            ("(not:(type:string))", "1", "x"),
+        ] {
+            let schema = schema(schema_rison);
+            schema.validate(&value(valid)).unwrap();
+            assert!(
+                !schema.is_valid(&value(invalid)),
+                "{} {}",
+                schema_rison,
+                invalid
+            );
+        }
+        for accepted in ["x", "!n", "(a:!(1))"] {
+            assert!(schema("!t").is_valid(&value(accepted)));
+            assert!(schema("(title:'Ignored',format:email)").is_valid(&value(accepted)));
+            assert!(!schema("!f").is_valid(&value(accepted)));
+        }
+    }
+
+    #[test]
+    fn reject_invalid_schemas() {
+        for document in [
+            "1",
+            "(type:text)",
+            "(minimum:x)",
+            "(minLength:-1)",
+            "(multipleOf:0)",
+            "(required:!(1))",
+            "(properties:(a:x))",
+            "(pattern:'^a')",
+            "(items:('$ref':'#'))",
+        ] {
+            assert!(Schema::new(&value(document)).is_err(), "{}", document);
+        }
+    }
+
+    #[test]
+    fn validate_while_deserializing() {
+        let schema = schema(
+            "(properties:(filters:(items:(properties:(op:(enum:!(is,not))),required:!(op)))),\
+             additionalProperties:!f)",
+        );
+
+        let input = "(filters:!((op:is),(op:has)))";
+        let err = crate::de::from_str_seed(input, &schema).unwrap_err();
+        assert_eq!(err.position(), Some(23));
+        assert!(err.to_string().contains("expected one of `is`, `not`"));
+
+        let err = crate::de::from_str_seed("(filters:!((x:1)))", &schema).unwrap_err();
+        assert_eq!(err.position(), Some(11));
+        assert!(err.to_string().contains("missing key `op`"));
+
+        let err = crate::de::from_str_seed("(filters:!(),page:1)", &schema).unwrap_err();
+        assert_eq!(err.position(), Some(13));
+        assert!(err.to_string().contains("unexpected key `page`"));
+
+        let value = crate::de::from_str_seed("(filters:!((op:not)))", &schema).unwrap();
+        assert_eq!(value, self::value("(filters:!((op:not)))"));
+    }
+
+    #[cfg(feature = "path")]
+    #[test]
+    fn errors_have_paths() {
+        let schema = schema("(properties:(a:(items:(type:integer))))");
+
+        let err = schema.validate(&value("(a:!(1,x))")).unwrap_err();
+        assert_eq!(err.path().unwrap().to_string(), "a[1]");
+        let err = crate::de::from_str_seed("(a:!(1,x))", &schema).unwrap_err();
+        assert_eq!(err.path().unwrap().to_string(), "a[1]");
+    }
+}
@@ -0,0 +1,1877 @@
+//! Serialize Rust data structures into Rison
+
+use std::io;
+
+use serde::ser::Serialize;
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::Profile;
+
+mod builder;
+mod chunks;
+mod format;
+mod pretty;
+mod uri;
+
+use builder::Options;
+pub use builder::{FloatStyle, NonFiniteFloats, NonStringKeys, SerializerBuilder};
+use format::LimitedWriter;
+pub use format::{CompactFormatter, Formatter};
+pub use pretty::PrettyStyle;
+pub use uri::UriComponent;
+
+/// A serializer of Rust values into Rison
+///
+/// The tokens of the output are written by a [`Formatter`], which writes
+/// compact Rison unless one is given with
+/// [`with_formatter`](Serializer::with_formatter).
+pub struct Serializer<W, F = CompactFormatter> {
+    writer: W,
+    formatter: F,
+    options: Options,
+    /// The number of bytes written so far
+    written: usize,
+}
+
+impl Serializer<()> {
+    /// Create a builder for serializers with non-default configuration
+    ///
+    /// ```
+    /// use rison::ser::{FloatStyle, Serializer};
+    ///
+    /// let rison = Serializer::builder()
+    ///     .float_style(FloatStyle::Ryu)
+    ///     .to_string(&[1.0, 0.000001])
+    ///     .unwrap();
+    /// assert_eq!(rison, "!(1.0,1e-6)");
+    /// ```
+    pub fn builder() -> SerializerBuilder {
+        SerializerBuilder::new()
+    }
+}
+
+impl<W: io::Write> Serializer<W> {
+    /// Create a Rison serializer writing to an `io::Write`
+    pub fn new(writer: W) -> Self {
+        Self::with_options(writer, CompactFormatter, Options::default())
+    }
+}
+
+impl<W: io::Write, F: Formatter> Serializer<W, F> {
+    /// Create a Rison serializer writing to an `io::Write`, with the tokens
+    /// of its output written by `formatter`
+    pub fn with_formatter(writer: W, formatter: F) -> Self {
+        Self::with_options(writer, formatter, Options::default())
+    }
+
+    fn with_options(writer: W, formatter: F, options: Options) -> Self {
+        Self {
+            writer,
+            formatter,
+            options,
+            written: 0,
+        }
+    }
+
+    /// Unwrap the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write through the formatter, counting its output against the limit
+    fn format(
+        &mut self,
+        write: impl FnOnce(&mut F, &mut LimitedWriter<'_, W>) -> io::Result<()>,
+    ) -> Result<()> {
+        let mut writer = LimitedWriter {
+            writer: &mut self.writer,
+            written: &mut self.written,
+            limit: self.options.max_output_len(),
+            exceeded: false,
+        };
+        let result = write(&mut self.formatter, &mut writer);
+        if writer.exceeded {
+            return Err(Error::new(ErrorCode::OutputLimitExceeded, None));
+        }
+        result.map_err(Error::io)
+    }
+
+    fn write_str(&mut self, value: &str) -> Result<()> {
+        let profile = self.options.profile;
+        self.format(|f, w| write_string(f, w, profile, value))
+    }
+
+    fn write_float<T>(&mut self, value: T, finite: bool) -> Result<()>
+    where
+        T: ryu::Float,
+    {
+        if !finite {
+            return match self.options.non_finite_floats {
+                NonFiniteFloats::Null => self.format(|f, w| f.write_null(w)),
+                NonFiniteFloats::Error => Err(Error::new(ErrorCode::NonFiniteFloat, None)),
+            };
+        }
+
+        let mut buffer = ryu::Buffer::new();
+        let shortest = buffer.format_finite(value);
+        match self.options.float_style {
+            FloatStyle::JavaScript => {
+                let number = javascript_layout(shortest);
+                self.format(|f, w| f.write_number_str(w, &number))
+            }
+            FloatStyle::Ryu => self.format(|f, w| f.write_number_str(w, shortest)),
+        }
+    }
+
+    /// Write an integer, formatted on the stack so that it is counted
+    /// before it is written
+    fn write_integer(&mut self, value: impl std::fmt::Display) -> Result<()> {
+        // Long enough for any `i128` or `u128`
+        let mut buffer = [0u8; 40];
+        let mut cursor = io::Cursor::new(&mut buffer[..]);
+        io::Write::write_fmt(&mut cursor, format_args!("{}", value))
+            .expect("integers fit in the buffer");
+        let len = cursor.position() as usize;
+        let number = std::str::from_utf8(&buffer[..len]).expect("integers are ASCII");
+        self.format(|f, w| f.write_number_str(w, number))
+    }
+
+    /// Open an object holding the enum variant `variant`, up to its value
+    fn begin_variant(&mut self, variant: &str) -> Result<()> {
+        let profile = self.options.profile;
+        self.format(|f, w| {
+            f.begin_object(w)?;
+            f.begin_object_key(w, true)?;
+            write_string(f, w, profile, variant)?;
+            f.end_object_key(w)?;
+            f.begin_object_value(w)
+        })
+    }
+
+    /// Close an object holding an enum variant, following its value
+    fn end_variant(&mut self) -> Result<()> {
+        self.format(|f, w| {
+            f.end_object_value(w)?;
+            f.end_object(w)
+        })
+    }
+}
+
+/// Whether `value` may be written as an unquoted string, or "id"
+///
+/// Ids must be non-empty and may not contain any of the characters
+/// `` '!:(),*@$`` or a space. Nor, as the Rison grammar reserves them for
+/// numbers, may they start with a digit or `-`, though either may appear
+/// later: `a-1` is an id, while `-a` and `1a` are not. The serializer
+/// writes ids unquoted and quotes all other strings, as should code
+/// building Rison by hand.
+///
+/// ```
+/// assert!(rison::is_valid_id("user_id"));
+/// assert!(rison::is_valid_id("a-1"));
+/// assert!(!rison::is_valid_id("-a"));
+/// assert!(!rison::is_valid_id("2024-01-01"));
+/// assert!(!rison::is_valid_id("a b"));
+/// assert!(!rison::is_valid_id(""));
+/// ```
+pub fn is_valid_id(value: &str) -> bool {
+    Profile::Strict.is_valid_id(value)
+}
+
+/// Whether `value` must be quoted to be written as a Rison string, as an
+/// object key or otherwise
+///
+/// The inverse of [`is_valid_id`].
+///
+/// ```
+/// let key = "page size";
+/// let rison = if rison::needs_quoting(key) {
+///     format!("('{key}':10)")
+/// } else {
+///     format!("({key}:10)")
+/// };
+/// assert_eq!(rison, "('page size':10)");
+/// ```
+pub fn needs_quoting(value: &str) -> bool {
+    !is_valid_id(value)
+}
+
+/// Write a string or object key with a formatter, unquoted where the
+/// serializer's profile reads it back unquoted although the Rison grammar
+/// would not
+fn write_string<F, W>(f: &mut F, w: &mut W, profile: Profile, value: &str) -> io::Result<()>
+where
+    F: Formatter,
+    W: ?Sized + io::Write,
+{
+    if profile != Profile::Strict && !is_valid_id(value) && profile.is_valid_id(value) {
+        f.write_unquoted_string(w, value)
+    } else {
+        f.write_string(w, value)
+    }
+}
+
+/// Format a finite float as the shortest representation which round-trips,
+/// laid out as JavaScript's `Number.prototype.toString` would (and hence as
+/// the reference implementations do), minus the `+` in positive exponents
+pub(crate) fn format_float(value: impl ryu::Float) -> String {
+    javascript_layout(ryu::Buffer::new().format_finite(value))
+}
+
+/// Lay out the shortest round-trip digits produced by `ryu` as JavaScript
+/// would
+fn javascript_layout(shortest: &str) -> String {
+    let (negative, shortest) = match shortest.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, shortest),
+    };
+    let (mantissa, exponent) = shortest.split_once('e').unwrap_or((shortest, "0"));
+    let exponent: i32 = exponent.parse().expect("`ryu` exponent is an integer");
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = [int, frac].concat();
+    let significant = digits.trim_start_matches('0');
+    let leading_zeros = (digits.len() - significant.len()) as i32;
+    let digits = significant.trim_end_matches('0');
+
+    if digits.is_empty() {
+        // Negative zero is formatted as zero, as in JavaScript
+        return "0".to_owned();
+    }
+
+    // The value is `0.{digits} * 10^point`
+    let len = digits.len() as i32;
+    let point = int.len() as i32 + exponent - leading_zeros;
+
+    let mut out = String::with_capacity(digits.len() + 8);
+    if negative {
+        out.push('-');
+    }
+    if len <= point && point <= 21 {
+        out.push_str(digits);
+        out.extend(std::iter::repeat_n('0', (point - len) as usize));
+    } else if 0 < point && point <= 21 {
+        let (int, frac) = digits.split_at(point as usize);
+        out.push_str(int);
+        out.push('.');
+        out.push_str(frac);
+    } else if -6 < point && point <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', -point as usize));
+        out.push_str(digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.push_str(first);
+        if !rest.is_empty() {
+            out.push('.');
+            out.push_str(rest);
+        }
+        out.push('e');
+        out.push_str(&(point - 1).to_string());
+    }
+    out
+}
+
+impl<'a, W: io::Write, F: Formatter> serde::Serializer for &'a mut Serializer<W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a, W, F>;
+    type SerializeTuple = Compound<'a, W, F>;
+    type SerializeTupleStruct = Compound<'a, W, F>;
+    type SerializeTupleVariant = Compound<'a, W, F>;
+    type SerializeMap = Compound<'a, W, F>;
+    type SerializeStruct = Compound<'a, W, F>;
+    type SerializeStructVariant = Compound<'a, W, F>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.format(|f, w| f.write_bool(w, v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.write_integer(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.write_float(v, v.is_finite())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.write_float(v, v.is_finite())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.write_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.write_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.write_str(&crate::bytes::encode(v))
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.format(|f, w| f.write_null(w))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.write_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == crate::value::raw::TOKEN {
+            return value.serialize(RawValueStrEmitter {
+                ser: self,
+                number: false,
+            });
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        if name == crate::number::TOKEN {
+            return value.serialize(RawValueStrEmitter {
+                ser: self,
+                number: true,
+            });
+        }
+
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.begin_variant(variant)?;
+        value.serialize(&mut *self)?;
+        self.end_variant()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.format(|f, w| f.begin_array(w))?;
+        Ok(Compound::new(self, false, false))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.begin_variant(variant)?;
+        self.format(|f, w| f.begin_array(w))?;
+        Ok(Compound::new(self, false, true))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.format(|f, w| f.begin_object(w))?;
+        Ok(Compound::new(self, true, false))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.begin_variant(variant)?;
+        self.format(|f, w| f.begin_object(w))?;
+        Ok(Compound::new(self, true, true))
+    }
+}
+
+/// Serializes the elements of lists and the entries of objects
+#[doc(hidden)]
+pub struct Compound<'a, W, F = CompactFormatter> {
+    ser: &'a mut Serializer<W, F>,
+    first: bool,
+    /// Whether the compound is an object rather than a list
+    object: bool,
+    /// Whether the compound is wrapped in an enum variant object, which must
+    /// be closed in addition to the compound itself
+    variant: bool,
+}
+
+impl<'a, W: io::Write, F: Formatter> Compound<'a, W, F> {
+    fn new(ser: &'a mut Serializer<W, F>, object: bool, variant: bool) -> Self {
+        Self {
+            ser,
+            first: true,
+            object,
+            variant,
+        }
+    }
+
+    fn element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let first = std::mem::replace(&mut self.first, false);
+        self.ser.format(|f, w| f.begin_array_value(w, first))?;
+        value.serialize(&mut *self.ser)?;
+        self.ser.format(|f, w| f.end_array_value(w))
+    }
+
+    fn field<T>(&mut self, key: &str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.ser.options.skip_none_fields && value.serialize(IsNone).is_ok() {
+            return Ok(());
+        }
+        let first = std::mem::replace(&mut self.first, false);
+        let profile = self.ser.options.profile;
+        self.ser.format(|f, w| {
+            f.begin_object_key(w, first)?;
+            write_string(f, w, profile, key)?;
+            f.end_object_key(w)?;
+            f.begin_object_value(w)
+        })?;
+        value.serialize(&mut *self.ser)?;
+        self.ser.format(|f, w| f.end_object_value(w))
+    }
+
+    fn close(self) -> Result<()> {
+        if self.object {
+            self.ser.format(|f, w| f.end_object(w))?;
+        } else {
+            self.ser.format(|f, w| f.end_array(w))?;
+        }
+        if self.variant {
+            self.ser.end_variant()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::ser::SerializeSeq for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::ser::SerializeTuple for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::ser::SerializeTupleStruct for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::ser::SerializeTupleVariant for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::ser::SerializeMap for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let first = std::mem::replace(&mut self.first, false);
+        self.ser.format(|f, w| f.begin_object_key(w, first))?;
+        key.serialize(MapKeySerializer {
+            ser: &mut *self.ser,
+        })?;
+        self.ser.format(|f, w| f.end_object_key(w))
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.ser.format(|f, w| f.begin_object_value(w))?;
+        value.serialize(&mut *self.ser)?;
+        self.ser.format(|f, w| f.end_object_value(w))
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::ser::SerializeStruct for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::ser::SerializeStructVariant for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.close()
+    }
+}
+
+/// Serializes object keys, which must be strings
+struct MapKeySerializer<'a, W, F> {
+    ser: &'a mut Serializer<W, F>,
+}
+
+fn key_must_be_a_string() -> Error {
+    Error::new(ErrorCode::KeyMustBeAString, None)
+}
+
+impl<W: io::Write, F: Formatter> MapKeySerializer<'_, W, F> {
+    /// Write a key which is not a string with `write`, if configured to
+    /// with [`NonStringKeys::Unquoted`]
+    fn non_string(self, write: impl FnOnce(&mut Serializer<W, F>) -> Result<()>) -> Result<()> {
+        match self.ser.options.non_string_keys {
+            NonStringKeys::Error => Err(key_must_be_a_string()),
+            NonStringKeys::Unquoted => write(self.ser),
+        }
+    }
+
+    fn float_key<T: ryu::Float>(self, value: T, finite: bool) -> Result<()> {
+        self.non_string(|ser| {
+            if finite {
+                ser.write_float(value, finite)
+            } else {
+                Err(Error::new(ErrorCode::NonFiniteFloat, None))
+            }
+        })
+    }
+}
+
+impl<W: io::Write, F: Formatter> serde::Serializer for MapKeySerializer<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = serde::ser::Impossible<(), Error>;
+    type SerializeTuple = serde::ser::Impossible<(), Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<(), Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<(), Error>;
+    type SerializeMap = serde::ser::Impossible<(), Error>;
+    type SerializeStruct = serde::ser::Impossible<(), Error>;
+    type SerializeStructVariant = serde::ser::Impossible<(), Error>;
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.ser.write_str(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.ser.serialize_char(v)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.ser.write_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.non_string(|ser| ser.write_str(if v { "true" } else { "false" }))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.non_string(|ser| ser.write_integer(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.float_key(v, v.is_finite())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.float_key(v, v.is_finite())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
+
+/// Writes the text of a [`RawValue`](crate::value::RawValue), or of an
+/// exact [`Number`](crate::Number), verbatim
+struct RawValueStrEmitter<'a, W, F> {
+    ser: &'a mut Serializer<W, F>,
+    number: bool,
+}
+
+fn expected_raw_value() -> Error {
+    serde::ser::Error::custom("expected RawValue")
+}
+
+impl<W: io::Write, F: Formatter> serde::Serializer for RawValueStrEmitter<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = serde::ser::Impossible<(), Error>;
+    type SerializeTuple = serde::ser::Impossible<(), Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<(), Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<(), Error>;
+    type SerializeMap = serde::ser::Impossible<(), Error>;
+    type SerializeStruct = serde::ser::Impossible<(), Error>;
+    type SerializeStructVariant = serde::ser::Impossible<(), Error>;
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        if self.number {
+            self.ser.format(|f, w| f.write_number_str(w, v))
+        } else {
+            self.ser.format(|f, w| f.write_raw_fragment(w, v))
+        }
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_char(self, _v: char) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(expected_raw_value())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(expected_raw_value())
+    }
+}
+
+/// Succeeds only when serializing `None`, to find struct fields to skip
+///
+/// Fails as soon as any other value begins, without serializing its
+/// contents.
+struct IsNone;
+
+fn not_none() -> Error {
+    Error::new(ErrorCode::Message(String::new()), None)
+}
+
+impl serde::Serializer for IsNone {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = serde::ser::Impossible<(), Error>;
+    type SerializeTuple = serde::ser::Impossible<(), Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<(), Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<(), Error>;
+    type SerializeMap = serde::ser::Impossible<(), Error>;
+    type SerializeStruct = serde::ser::Impossible<(), Error>;
+    type SerializeStructVariant = serde::ser::Impossible<(), Error>;
+
+    fn serialize_str(self, _v: &str) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_char(self, _v: char) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(not_none())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        Err(not_none())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(not_none())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(not_none())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(not_none())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(not_none())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(not_none())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(not_none())
+    }
+}
+
+/// Serialize an instance of `T` to a byte vector of Rison
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut ser = Serializer::new(Vec::with_capacity(128));
+    value.serialize(&mut ser)?;
+    Ok(ser.into_inner())
+}
+
+/// Serialize an instance of `T` as Rison into an `io::Write`, such as a
+/// file or socket
+///
+/// The many small writes of punctuation and short strings are batched in a
+/// buffer, so `writer` need not be buffered itself, while long strings are
+/// passed straight through to it. The buffer is flushed before returning,
+/// but `writer` itself is not.
+///
+/// ```
+/// let mut out = Vec::new();
+/// for record in [("a", 1), ("b", 2)] {
+///     rison::to_writer(&mut out, &record).unwrap();
+///     out.push(b'\n');
+/// }
+///
+/// assert_eq!(out, b"!(a,1)\n!(b,2)\n");
+/// ```
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    write_buffered(writer, |writer| {
+        value.serialize(&mut Serializer::new(writer))
+    })
+}
+
+/// Size of the buffer in which `to_writer` batches writes
+const WRITE_BUFFER_LEN: usize = 8 * 1024;
+
+/// Pass `serialize` a buffered writer, flushing it into `writer` afterwards
+pub(crate) fn write_buffered<W: io::Write>(
+    writer: W,
+    serialize: impl FnOnce(&mut io::BufWriter<W>) -> Result<()>,
+) -> Result<()> {
+    let mut buffered = io::BufWriter::with_capacity(WRITE_BUFFER_LEN, writer);
+    serialize(&mut buffered)?;
+    buffered
+        .into_inner()
+        .map(drop)
+        .map_err(|err| Error::io(err.into_error()))
+}
+
+/// The length in bytes of the Rison which [`to_string`] would produce for
+/// an instance of `T`, computed without allocating it
+///
+/// This is exact, not an estimate. It is the length of the Rison itself:
+/// embedded in a URL, characters outside the URL-safe set, such as spaces
+/// and non-ASCII characters in strings, are longer once percent-encoded.
+/// To fail as soon as a limit is crossed instead, see
+/// [`SerializerBuilder::max_output_len`].
+///
+/// ```
+/// let ids: Vec<u32> = (0..1000).collect();
+/// let len = rison::ser::estimate_len(&ids).unwrap();
+///
+/// assert_eq!(len, rison::to_string(&ids).unwrap().len());
+/// assert!(len > 2000, "too long for some browsers' URLs");
+/// ```
+pub fn estimate_len<T>(value: &T) -> Result<usize>
+where
+    T: ?Sized + Serialize,
+{
+    count_written(|writer| value.serialize(&mut Serializer::new(writer)))
+}
+
+/// The number of bytes written by `serialize`, which writes to a sink
+pub(crate) fn count_written(serialize: impl FnOnce(&mut Counter) -> Result<()>) -> Result<usize> {
+    let mut counter = Counter(0);
+    serialize(&mut counter)?;
+    Ok(counter.0)
+}
+
+/// An `io::Write` which discards bytes, counting them
+pub(crate) struct Counter(usize);
+
+impl io::Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialize an instance of `T` to strings of Rison of at most `budget`
+/// bytes each, splitting a list or object between its elements
+///
+/// Each chunk is a list or object of as many consecutive elements as fit,
+/// for APIs which accept long lists in several requests, or merge objects
+/// from several query parameters. A value which fits in the budget is
+/// returned whole. Any other value, an empty list or object, or an element
+/// which does not fit in a chunk by itself, fails with
+/// [`ErrorCode::OutputLimitExceeded`].
+/// As with [`estimate_len`], the budget applies to the Rison itself, before
+/// any percent-encoding.
+///
+/// ```
+/// let ids: Vec<u32> = (1..=12).collect();
+/// let chunks = rison::ser::to_chunks(&ids, 12).unwrap();
+///
+/// assert_eq!(chunks, ["!(1,2,3,4,5)", "!(6,7,8,9)", "!(10,11,12)"]);
+/// ```
+pub fn to_chunks<T>(value: &T, budget: usize) -> Result<Vec<String>>
+where
+    T: ?Sized + Serialize,
+{
+    chunks::split(to_string(value)?, budget)
+}
+
+/// Serialize an instance of `T` to a string of Rison
+pub fn to_string<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    to_vec(value).map(string_from_output)
+}
+
+/// Convert output of the serializer to a `String`
+///
+/// The serializer only emits ASCII punctuation alongside the contents of
+/// `str`s, which are valid utf-8, split only at ASCII characters. The
+/// output is therefore valid utf-8. Without the `unchecked_utf8` feature
+/// this is checked, and panics instead.
+#[cfg(feature = "unchecked_utf8")]
+pub(crate) fn string_from_output(vec: Vec<u8>) -> String {
+    unsafe { String::from_utf8_unchecked(vec) }
+}
+
+#[cfg(not(feature = "unchecked_utf8"))]
+pub(crate) fn string_from_output(vec: Vec<u8>) -> String {
+    String::from_utf8(vec).expect("serializer output is valid utf-8")
+}
+
+/// Serialize an instance of `T` to an indented string, laid out in `style`,
+/// for logs and error messages
+///
+/// Each element of a non-empty list or object is written on a line of its
+/// own. The output is meant for people rather than URLs: see
+/// [`PrettyStyle`] for how far each style may be parsed back. To lay out
+/// Rison text which has already been written, pretty print it as a
+/// [`Value`](crate::Value) or [`RawValue`](crate::value::RawValue).
+///
+/// ```
+/// use rison::ser::PrettyStyle;
+///
+/// let state: rison::Value = "(columns:!(host,message),query:'')".parse().unwrap();
+///
+/// assert_eq!(
+///     rison::to_string_pretty(&state, PrettyStyle::Rison).unwrap(),
+///     "(\n  columns: !(\n    host,\n    message\n  ),\n  query: ''\n)",
+/// );
+/// assert_eq!(
+///     rison::to_string_pretty(&state, PrettyStyle::Json).unwrap(),
+///     "{\n  \"columns\": [\n    \"host\",\n    \"message\"\n  ],\n  \"query\": \"\"\n}",
+/// );
+/// ```
+pub fn to_string_pretty<T>(value: &T, style: PrettyStyle) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    Ok(pretty::reformat(&to_string(value)?, style))
+}
+
+/// Serialize an instance of `T` to a string which can be embedded in
+/// `component` of a URI without percent-encoding
+///
+/// Rison is designed to need little encoding, but quoted strings may
+/// contain any character, and even unquoted output may hold characters
+/// which are special in some parts of a URI, such as `&` and `=` in a
+/// query string. Rather than encode these, this fails with
+/// [`ErrorCode::CharNeedsEncoding`] at the first one, with the error's
+/// position giving its byte offset in the output.
+///
+/// ```
+/// use rison::ser::{to_uri_string_strict, UriComponent};
+///
+/// let state = ("logs", "level:error");
+/// assert_eq!(
+///     to_uri_string_strict(&state, UriComponent::Query).unwrap(),
+///     "!(logs,'level:error')",
+/// );
+///
+/// let err = to_uri_string_strict(&("a&b", 1), UriComponent::Query).unwrap_err();
+/// assert_eq!(err.position(), Some(3));
+/// ```
+pub fn to_uri_string_strict<T>(value: &T, component: UriComponent) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let rison = to_string(value)?;
+    uri::check(&rison, component)?;
+    Ok(rison)
+}
+
+/// Serialize an instance of `T` as O-Rison, an object without its
+/// enclosing parentheses
+///
+/// O-Rison suits query parameters which always hold an object, writing
+/// `(a:1,b:2)` as `a:1,b:2`, and an empty object as an empty string. Structs,
+/// maps and enum variants with content are serialized as objects; anything
+/// else fails with [`ErrorCode::ExpectedObject`].
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let state = BTreeMap::from([("page", 2), ("size", 50)]);
+/// assert_eq!(rison::ser::to_string_orison(&state).unwrap(), "page:2,size:50");
+/// assert!(rison::ser::to_string_orison(&[1, 2]).is_err());
+/// ```
+pub fn to_string_orison<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut rison = to_string(value)?;
+    if !rison.starts_with('(') {
+        return Err(Error::new(ErrorCode::ExpectedObject, None));
+    }
+    rison.pop();
+    rison.remove(0);
+    Ok(rison)
+}
+
+/// Serialize an instance of `T` as A-Rison, a list without its enclosing
+/// `!(` and `)`
+///
+/// A-Rison suits query parameters which always hold a list, writing
+/// `!(a,b)` as `a,b`, and an empty list as an empty string. Sequences,
+/// tuples and tuple structs are serialized as lists; anything else fails
+/// with [`ErrorCode::ExpectedList`].
+///
+/// ```
+/// assert_eq!(rison::ser::to_string_arison(&["a", "b c"]).unwrap(), "a,'b c'");
+/// assert!(rison::ser::to_string_arison(&"a").is_err());
+/// ```
+pub fn to_string_arison<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut rison = to_string(value)?;
+    if !rison.starts_with("!(") {
+        return Err(Error::new(ErrorCode::ExpectedList, None));
+    }
+    rison.pop();
+    rison.drain(..2);
+    Ok(rison)
+}
+
+/// Serialize an instance of `T` as Rison, appending it to a string
+///
+/// This allows one buffer to be reused for many values. If serialization
+/// fails, `string` is left as it was.
+///
+/// ```
+/// let mut url = String::from("/search?state=");
+/// rison::ser::to_string_in(&mut url, &("rust", 2)).unwrap();
+///
+/// assert_eq!(url, "/search?state=!(rust,2)");
+/// ```
+pub fn to_string_in<T>(string: &mut String, value: &T) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    append_to_string(string, |vec| value.serialize(&mut Serializer::new(vec)))
+}
+
+/// Serialize an instance of `T` as Rison into a `fmt::Write`, such as a
+/// `fmt::Formatter`
+pub fn to_fmt_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: std::fmt::Write,
+    T: ?Sized + Serialize,
+{
+    value.serialize(&mut Serializer::new(FmtWriter(writer)))
+}
+
+/// Append the output of `serialize` to `string`, or nothing if it fails
+pub(crate) fn append_to_string(
+    string: &mut String,
+    serialize: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    let mut vec = std::mem::take(string).into_bytes();
+    let len = vec.len();
+    let result = serialize(&mut vec);
+    if result.is_err() {
+        vec.truncate(len);
+    }
+
+    // `vec` held a valid `String`, to which only complete, valid Rison has
+    // been appended
+    *string = string_from_output(vec);
+    result
+}
+
+/// Adapts a `fmt::Write` to the `io::Write` the serializer writes to
+///
+/// The serializer writes whole `str`s, or parts of them split at ASCII
+/// characters, so each write is valid utf-8 by itself.
+pub(crate) struct FmtWriter<W>(pub(crate) W);
+
+impl<W: std::fmt::Write> io::Write for FmtWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s =
+            std::str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.0.write_str(s).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::error::{Category, ErrorCode};
+
+    #[test]
+    fn classify_ids() {
+        for id in ["a", "_", "a-1", "a.b", "é", "true", "a1e5", "~"] {
+            assert!(super::is_valid_id(id), "{}", id);
+            assert_eq!(super::to_string(id).unwrap(), id);
+        }
+        for not_id in [
+            "", "-", "-a", "0", "1a", "a b", "a'b", "a!", "(", "a:b", "a,b", "*", "@", "$",
+        ] {
+            assert!(super::needs_quoting(not_id), "{}", not_id);
+            assert!(super::to_string(not_id).unwrap().starts_with('\''));
+        }
+    }
+    #[test]
+    fn serialize_conformance_vectors() {
+        for (rison, json) in crate::testdata::ENCODE {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+            assert_eq!(&super::to_string(&value).unwrap(), rison, "{}", json);
+        }
+    }
+    #[test]
+    fn serialize_floats_as_javascript() {
+        for (value, expected) in [
+            (0.1, "0.1"),
+            (-0.0, "0"),
+            (100.0, "100"),
+            (123456.789, "123456.789"),
+            (1e21, "1e21"),
+            (1.5e300, "1.5e300"),
+            (1e20, "100000000000000000000"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-2.5e-8, "-2.5e-8"),
+            (f64::NAN, "!n"),
+            (f64::INFINITY, "!n"),
+        ] {
+            assert_eq!(super::to_string(&value).unwrap(), expected);
+        }
+        assert_eq!(super::to_string(&0.1f32).unwrap(), "0.1");
+    }
+    #[test]
+    fn serialize_floats_with_ryu_style() {
+        let builder = super::Serializer::builder().float_style(super::FloatStyle::Ryu);
+        for (value, expected) in [
+            (0.1, "0.1"),
+            (100.0, "100.0"),
+            (1e21, "1e21"),
+            (0.000001, "1e-6"),
+            (-2.5e-8, "-2.5e-8"),
+            (f64::NAN, "!n"),
+        ] {
+            assert_eq!(builder.to_string(&value).unwrap(), expected);
+        }
+        assert_eq!(builder.to_string(&1u32).unwrap(), "1");
+    }
+    #[test]
+    fn fail_non_finite_floats_when_configured() {
+        let builder = super::Serializer::builder().non_finite_floats(super::NonFiniteFloats::Error);
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let err = builder.to_string(&value).unwrap_err();
+            assert!(matches!(err.code(), ErrorCode::NonFiniteFloat));
+            assert!(matches!(err.classify(), Category::Data));
+        }
+        let err = builder.to_string(&(1.0, f32::NAN)).unwrap_err();
+        assert!(matches!(err.code(), ErrorCode::NonFiniteFloat));
+        assert_eq!(builder.to_string(&1.5).unwrap(), "1.5");
+    }
+    #[test]
+    fn serialize_into_existing_buffers() {
+        let mut s = String::from("a=");
+        super::to_string_in(&mut s, &json!({"b": "it's", "c": [1, 2]})).unwrap();
+        assert_eq!(s, "a=(b:'it!'s',c:!(1,2))");
+
+        let builder = super::Serializer::builder().non_finite_floats(super::NonFiniteFloats::Error);
+        assert!(builder.to_string_in(&mut s, &(1, f64::NAN)).is_err());
+        assert_eq!(s, "a=(b:'it!'s',c:!(1,2))");
+
+        let mut s = String::new();
+        super::to_fmt_writer(&mut s, &("x y", "ü!")).unwrap();
+        assert_eq!(s, "!('x y','ü!!')");
+        builder.to_fmt_writer(&mut s, &1.5).unwrap();
+        assert_eq!(s, "!('x y','ü!!')1.5");
+    }
+    #[test]
+    fn serialize_struct() {
+        #[derive(serde::Serialize)]
+        struct Full {
+            a: String,
+            b: Option<u32>,
+            c: Vec<bool>,
+        }
+        let v = super::to_string(&Full {
+            a: "hello world".into(),
+            b: None,
+            c: vec![true],
+        })
+        .unwrap();
+
+        assert_eq!(v, "(a:'hello world',b:!n,c:!(!t))");
+    }
+    #[test]
+    fn serialize_struct_skipping_none_fields() {
+        #[derive(serde::Serialize)]
+        struct Inner {
+            a: Option<()>,
+            b: Option<Option<u8>>,
+        }
+        #[derive(serde::Serialize)]
+        enum E {
+            Struct { a: Option<u8>, b: Option<u8> },
+        }
+        #[derive(serde::Serialize)]
+        struct Outer {
+            a: Option<Inner>,
+            b: Option<u8>,
+            c: (),
+            d: Vec<Option<u8>>,
+            e: E,
+        }
+        let builder = super::Serializer::builder().skip_none_fields(true);
+        let v = builder
+            .to_string(&Outer {
+                a: Some(Inner {
+                    a: None,
+                    b: Some(None),
+                }),
+                b: None,
+                c: (),
+                d: vec![None],
+                e: E::Struct {
+                    a: None,
+                    b: Some(1),
+                },
+            })
+            .unwrap();
+
+        assert_eq!(v, "(a:(b:!n),c:!n,d:!(!n),e:(Struct:(b:1)))");
+        assert_eq!(
+            builder.to_string(&Inner { a: None, b: None }).unwrap(),
+            "()"
+        );
+    }
+    #[test]
+    fn serialize_enum_variants() {
+        #[derive(serde::Serialize)]
+        enum E {
+            Unit,
+            Newtype(u8),
+            Tuple(u8, u8),
+            Struct { a: u8 },
+        }
+
+        assert_eq!(super::to_string(&E::Unit).unwrap(), "Unit");
+        assert_eq!(super::to_string(&E::Newtype(1)).unwrap(), "(Newtype:1)");
+        assert_eq!(super::to_string(&E::Tuple(1, 2)).unwrap(), "(Tuple:!(1,2))");
+        assert_eq!(
+            super::to_string(&E::Struct { a: 1 }).unwrap(),
+            "(Struct:(a:1))"
+        );
+    }
+    #[test]
+    fn fail_serialize_non_string_key() {
+        let v: std::collections::BTreeMap<u32, u32> = [(1, 2)].into_iter().collect();
+
+        assert!(super::to_string(&v).is_err());
+    }
+    #[test]
+    fn serialize_unquoted_non_string_keys() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let builder = super::Serializer::builder().non_string_keys(super::NonStringKeys::Unquoted);
+
+        let ints: BTreeMap<i64, &str> = [(-1, "a"), (20, "b")].into_iter().collect();
+        let rison = builder.to_string(&ints).unwrap();
+        assert_eq!(rison, "(-1:a,20:b)");
+        assert_eq!(
+            crate::from_str::<BTreeMap<i64, String>>(&rison)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let ids: HashMap<u32, Vec<u8>> = [(1, vec![]), (u32::MAX, vec![1])].into_iter().collect();
+        let rison = builder.to_string(&ids).unwrap();
+        assert_eq!(
+            crate::from_str::<HashMap<u32, Vec<u8>>>(&rison).unwrap(),
+            ids
+        );
+
+        let flags: BTreeMap<bool, u8> = [(false, 0), (true, 1)].into_iter().collect();
+        let rison = builder.to_string(&flags).unwrap();
+        assert_eq!(rison, "(false:0,true:1)");
+        assert_eq!(
+            crate::from_str::<BTreeMap<bool, u8>>(&rison).unwrap(),
+            flags
+        );
+
+        let mut ser = builder.build(Vec::new());
+        let mut map = serde::Serializer::serialize_map(&mut ser, None).unwrap();
+        serde::ser::SerializeMap::serialize_entry(&mut map, &1.5, &1).unwrap();
+        assert!(serde::ser::SerializeMap::serialize_entry(&mut map, &f64::NAN, &2).is_err());
+        assert_eq!(ser.into_inner(), b"(1.5:1,");
+    }
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn serialize_to_writer_in_batches() {
+        /// Records the size of each write
+        #[derive(Default)]
+        struct Writes(Vec<usize>, Vec<u8>);
+
+        impl std::io::Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.push(buf.len());
+                self.1.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let records: Vec<_> = (0..1000).map(|i| (i, "it's")).collect();
+        let mut writes = Writes::default();
+        super::to_writer(&mut writes, &records).unwrap();
+
+        assert_eq!(writes.1, super::to_vec(&records).unwrap());
+        assert!(writes.0.len() < 5, "{} writes", writes.0.len());
+
+        let long = "x".repeat(100_000);
+        let mut writes = Writes::default();
+        super::to_writer(&mut writes, &long).unwrap();
+        assert_eq!(writes.0, [100_000]);
+    }
+    #[test]
+    fn estimate_output_len() {
+        let values = [
+            json!(null),
+            json!("-1"),
+            json!({"a": [1, -2.5, "b c", "it's"], "": {"x": true}}),
+        ];
+        for value in values {
+            let len = super::estimate_len(&value).unwrap();
+            assert_eq!(len, super::to_string(&value).unwrap().len());
+        }
+        assert_eq!(super::estimate_len(&i128::MIN).unwrap(), 40);
+
+        let builder = super::Serializer::builder().skip_none_fields(true);
+        #[derive(serde::Serialize)]
+        struct Page {
+            n: Option<u8>,
+        }
+        assert_eq!(builder.estimate_len(&Page { n: None }).unwrap(), 2);
+    }
+    #[test]
+    fn fail_serialize_beyond_output_limit() {
+        let builder = super::Serializer::builder().max_output_len(7);
+        assert_eq!(builder.to_string(&[1, 22]).unwrap(), "!(1,22)");
+        let err = builder.to_string(&[1, 222]).unwrap_err();
+        assert!(matches!(err.code(), ErrorCode::OutputLimitExceeded));
+        assert_eq!(err.classify(), Category::Data);
+
+        let mut output = Vec::new();
+        assert!(builder.to_writer(&mut output, &"long string").is_err());
+        assert!(output.len() <= 7);
+        let mut output = String::from("a=");
+        assert!(builder.to_string_in(&mut output, &[1, 222]).is_err());
+        assert_eq!(output, "a=");
+    }
+    #[test]
+    fn fail_serialize_to_writer() {
+        struct Broken;
+
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let err = super::to_writer(Broken, &(1, 2)).unwrap_err();
+        assert!(err.is_io());
+    }
+    #[test]
+    fn serialize_round_trips() {
+        let value = json!({"a": [1, "two", {"three": null}], "b c": "it's", "-": 1.5});
+        let rison = super::to_string(&value).unwrap();
+        let v: serde_json::Value = crate::from_str(&rison).unwrap();
+
+        assert_eq!(v, value);
+    }
+    #[test]
+    fn serialize_bytes_as_base64() {
+        use serde::Serializer;
+
+        let mut ser = super::Serializer::new(Vec::new());
+        ser.serialize_bytes(b"\xfbrison\xff").unwrap();
+        assert_eq!(ser.into_inner(), b"'-3Jpc29u_w'");
+
+        let mut ser = super::Serializer::new(Vec::new());
+        ser.serialize_bytes(b"rison").unwrap();
+        assert_eq!(ser.into_inner(), b"cmlzb24");
+
+        let mut ser = super::Serializer::new(Vec::new());
+        ser.serialize_bytes(b"").unwrap();
+        assert_eq!(ser.into_inner(), b"''");
+    }
+    #[test]
+    fn serialize_orison_and_arison() {
+        #[derive(serde::Serialize)]
+        enum E {
+            Unit,
+            Struct { a: u8 },
+        }
+
+        let object = json!({"a": [1, "x y"], "b": {"c": null}});
+        assert_eq!(
+            super::to_string_orison(&object).unwrap(),
+            "a:!(1,'x y'),b:(c:!n)"
+        );
+        assert_eq!(super::to_string_orison(&json!({})).unwrap(), "");
+        assert_eq!(
+            super::to_string_orison(&E::Struct { a: 1 }).unwrap(),
+            "Struct:(a:1)"
+        );
+        assert_eq!(
+            super::to_string_arison(&json!([[1], {"a": 2}, "(x)"])).unwrap(),
+            "!(1),(a:2),'(x)'"
+        );
+        assert_eq!(super::to_string_arison(&json!([])).unwrap(), "");
+        assert_eq!(super::to_string_arison(&(1, "a")).unwrap(), "1,a");
+
+        for value in [json!([1]), json!("(a:1)"), json!(1), json!(null)] {
+            let err = super::to_string_orison(&value).unwrap_err();
+            assert!(matches!(err.code(), ErrorCode::ExpectedObject), "{value}");
+        }
+        for value in [json!({}), json!("!(1)"), json!(true)] {
+            let err = super::to_string_arison(&value).unwrap_err();
+            assert!(matches!(err.code(), ErrorCode::ExpectedList), "{value}");
+        }
+        assert!(super::to_string_orison(&E::Unit).is_err());
+    }
+}
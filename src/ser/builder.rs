@@ -0,0 +1,312 @@
+use std::io;
+
+use serde::ser::Serialize;
+
+use super::{CompactFormatter, Formatter, Serializer};
+use crate::error::Result;
+use crate::Profile;
+
+/// How finite floats are laid out
+///
+/// Both styles write the shortest digits which parse back to the same
+/// float, and never write a `+` in exponents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FloatStyle {
+    /// As JavaScript's `Number.prototype.toString` would, and hence as the
+    /// reference implementations do: `100`, `0.000001`, `1e21`, `1.5e-7`
+    ///
+    /// Output matches rison-node byte for byte, so generated URLs compare
+    /// cleanly against those generated in JavaScript.
+    #[default]
+    JavaScript,
+    /// As `ryu` would: `100.0`, `1e-6`, `1e21`, `1.5e-7`
+    ///
+    /// Integral floats keep a fractional part, distinguishing them from
+    /// integers, and exponents are used for a wider range of magnitudes.
+    Ryu,
+}
+
+/// What to do with NaN and infinite floats, which Rison cannot represent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NonFiniteFloats {
+    /// Write them as `!n`, as JavaScript's `JSON.stringify` (and hence the
+    /// reference implementations) do
+    #[default]
+    Null,
+    /// Fail with [`ErrorCode::NonFiniteFloat`](crate::error::ErrorCode::NonFiniteFloat)
+    Error,
+}
+
+/// What to do with map keys which are not strings, as Rison object keys
+/// must be
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NonStringKeys {
+    /// Fail with [`ErrorCode::KeyMustBeAString`](crate::error::ErrorCode::KeyMustBeAString)
+    #[default]
+    Error,
+    /// Write integer, float and bool keys as their text, unquoted, as in
+    /// `(1:a,-2:b)` or `(true:x)`
+    ///
+    /// The deserializer parses such keys back as the type requested, so
+    /// maps such as a `HashMap<u32, T>` round-trip. Floats are laid out as
+    /// configured with [`SerializerBuilder::float_style`], and NaN or
+    /// infinite keys fail with
+    /// [`ErrorCode::NonFiniteFloat`](crate::error::ErrorCode::NonFiniteFloat).
+    /// Keys of any other type still fail.
+    Unquoted,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Options {
+    pub(crate) float_style: FloatStyle,
+    pub(crate) non_finite_floats: NonFiniteFloats,
+    pub(crate) skip_none_fields: bool,
+    pub(crate) non_string_keys: NonStringKeys,
+    pub(crate) profile: Profile,
+    pub(crate) max_output_len: Option<usize>,
+}
+
+impl Options {
+    pub(crate) fn max_output_len(&self) -> usize {
+        self.max_output_len.unwrap_or(usize::MAX)
+    }
+}
+
+/// A builder for configured [`Serializer`]s
+///
+/// Created by [`Serializer::builder`]. The same builder may be used to
+/// construct any number of serializers sharing one configuration.
+#[derive(Clone, Debug, Default)]
+pub struct SerializerBuilder {
+    options: Options,
+}
+
+impl SerializerBuilder {
+    /// Create a builder with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how finite floats are laid out
+    ///
+    /// Defaults to [`FloatStyle::JavaScript`].
+    pub fn float_style(mut self, style: FloatStyle) -> Self {
+        self.options.float_style = style;
+        self
+    }
+
+    /// Set what to do with NaN and infinite floats
+    ///
+    /// Defaults to [`NonFiniteFloats::Null`], which loses information: the
+    /// output deserializes as `None` or a unit, not as a float.
+    pub fn non_finite_floats(mut self, policy: NonFiniteFloats) -> Self {
+        self.options.non_finite_floats = policy;
+        self
+    }
+
+    /// Set whether struct fields holding `None` are omitted, rather than
+    /// written as `!n`
+    ///
+    /// Defaults to `false`. Omitted fields deserialize as `None` all the
+    /// same, so this saves URL bytes without losing information, as
+    /// `#[serde(skip_serializing_if = "Option::is_none")]` would on every
+    /// field. It applies to the fields of structs and struct variants, but
+    /// not to the entries of maps, nor to the fields of flattened structs,
+    /// which are serialized as map entries.
+    ///
+    /// ```
+    /// #[derive(serde::Serialize)]
+    /// struct Search {
+    ///     q: &'static str,
+    ///     page: Option<u32>,
+    /// }
+    ///
+    /// let rison = rison::Serializer::builder()
+    ///     .skip_none_fields(true)
+    ///     .to_string(&Search { q: "rust", page: None })
+    ///     .unwrap();
+    /// assert_eq!(rison, "(q:rust)");
+    /// ```
+    pub fn skip_none_fields(mut self, skip: bool) -> Self {
+        self.options.skip_none_fields = skip;
+        self
+    }
+
+    /// Set what to do with map keys which are not strings
+    ///
+    /// Defaults to [`NonStringKeys::Error`].
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use rison::ser::NonStringKeys;
+    ///
+    /// let counts: BTreeMap<u32, u32> = [(404, 3), (500, 1)].into_iter().collect();
+    /// let rison = rison::Serializer::builder()
+    ///     .non_string_keys(NonStringKeys::Unquoted)
+    ///     .to_string(&counts)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(rison, "(404:3,500:1)");
+    /// assert_eq!(rison::from_str::<BTreeMap<u32, u32>>(&rison).unwrap(), counts);
+    /// ```
+    pub fn non_string_keys(mut self, policy: NonStringKeys) -> Self {
+        self.options.non_string_keys = policy;
+        self
+    }
+
+    /// Set the dialect of Rison to write, for a particular peer
+    /// implementation
+    ///
+    /// Under [`Profile::Lenient`], strings such as `2024-01-01` are written
+    /// unquoted. Defaults to [`Profile::Strict`].
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.options.profile = profile;
+        self
+    }
+
+    /// Limit the length in bytes of the output
+    ///
+    /// Serialization fails with
+    /// [`ErrorCode::OutputLimitExceeded`](crate::error::ErrorCode::OutputLimitExceeded)
+    /// as soon as the limit would be crossed, before the rest of the value
+    /// is serialized or the offending bytes are written, so that a URL
+    /// which browsers or servers would truncate or reject is never built.
+    /// Output already written to a writer is left in place. No limit is
+    /// applied by default.
+    ///
+    /// ```
+    /// let ids: Vec<u32> = (0..1000).collect();
+    /// let result = rison::Serializer::builder().max_output_len(2000).to_string(&ids);
+    ///
+    /// assert!(result.is_err());
+    /// ```
+    pub fn max_output_len(mut self, len: usize) -> Self {
+        self.options.max_output_len = Some(len);
+        self
+    }
+
+    /// Create a configured Rison serializer writing to an `io::Write`
+    pub fn build<W: io::Write>(&self, writer: W) -> Serializer<W> {
+        Serializer::with_options(writer, CompactFormatter, self.options.clone())
+    }
+
+    /// Create a configured Rison serializer writing to an `io::Write`, with
+    /// the tokens of its output written by `formatter`
+    ///
+    /// See [`Formatter`](super::Formatter).
+    pub fn build_with_formatter<W, F>(&self, writer: W, formatter: F) -> Serializer<W, F>
+    where
+        W: io::Write,
+        F: Formatter,
+    {
+        Serializer::with_options(writer, formatter, self.options.clone())
+    }
+
+    /// Serialize an instance of `T` to a byte vector of Rison with this
+    /// configuration
+    pub fn to_vec<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut ser = self.build(Vec::with_capacity(128));
+        value.serialize(&mut ser)?;
+        Ok(ser.into_inner())
+    }
+
+    /// Serialize an instance of `T` as Rison into an `io::Write` with this
+    /// configuration
+    ///
+    /// See [`to_writer`](super::to_writer).
+    pub fn to_writer<W, T>(&self, writer: W, value: &T) -> Result<()>
+    where
+        W: io::Write,
+        T: ?Sized + Serialize,
+    {
+        super::write_buffered(writer, |writer| value.serialize(&mut self.build(writer)))
+    }
+
+    /// The length in bytes of the Rison which
+    /// [`to_string`](Self::to_string) would produce for an instance of `T`
+    /// with this configuration, computed without allocating it
+    ///
+    /// See [`estimate_len`](super::estimate_len).
+    pub fn estimate_len<T>(&self, value: &T) -> Result<usize>
+    where
+        T: ?Sized + Serialize,
+    {
+        super::count_written(|writer| value.serialize(&mut self.build(writer)))
+    }
+
+    /// Serialize an instance of `T` to a string of Rison with this
+    /// configuration
+    pub fn to_string<T>(&self, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.to_vec(value).map(super::string_from_output)
+    }
+
+    /// Serialize an instance of `T` to an indented string, laid out in
+    /// `style`, with this configuration
+    ///
+    /// See [`to_string_pretty`](super::to_string_pretty).
+    pub fn to_string_pretty<T>(&self, value: &T, style: super::PrettyStyle) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(super::pretty::reformat(&self.to_string(value)?, style))
+    }
+
+    /// Serialize an instance of `T` to a string which can be embedded in
+    /// `component` of a URI without percent-encoding, with this configuration
+    ///
+    /// See [`to_uri_string_strict`](super::to_uri_string_strict).
+    pub fn to_uri_string_strict<T>(
+        &self,
+        value: &T,
+        component: super::UriComponent,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        let rison = self.to_string(value)?;
+        super::uri::check(&rison, component)?;
+        Ok(rison)
+    }
+
+    /// Serialize an instance of `T` to strings of Rison of at most `budget`
+    /// bytes each with this configuration
+    ///
+    /// See [`to_chunks`](super::to_chunks).
+    pub fn to_chunks<T>(&self, value: &T, budget: usize) -> Result<Vec<String>>
+    where
+        T: ?Sized + Serialize,
+    {
+        super::chunks::split(self.to_string(value)?, budget)
+    }
+
+    /// Serialize an instance of `T` as Rison with this configuration,
+    /// appending it to a string
+    ///
+    /// See [`to_string_in`](super::to_string_in).
+    pub fn to_string_in<T>(&self, string: &mut String, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        super::append_to_string(string, |vec| value.serialize(&mut self.build(vec)))
+    }
+
+    /// Serialize an instance of `T` as Rison with this configuration into a
+    /// `fmt::Write`
+    pub fn to_fmt_writer<W, T>(&self, writer: W, value: &T) -> Result<()>
+    where
+        W: std::fmt::Write,
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.build(super::FmtWriter(writer)))
+    }
+}
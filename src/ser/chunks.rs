@@ -0,0 +1,128 @@
+//! Splitting serialized lists and objects into chunks under a length budget
+
+use crate::error::{Error, ErrorCode, Result};
+
+/// Split the Rison of a value into chunks of at most `budget` bytes
+///
+/// A list or object is split between its elements, each chunk being a list
+/// or object of as many consecutive elements as fit. Any other value, an
+/// empty list or object, or an element which does not fit in a chunk by
+/// itself, fails with [`ErrorCode::OutputLimitExceeded`] when over budget.
+pub(crate) fn split(rison: String, budget: usize) -> Result<Vec<String>> {
+    if rison.len() <= budget {
+        return Ok(vec![rison]);
+    }
+    let (open, body) = if let Some(body) = rison.strip_prefix("!(") {
+        ("!(", body)
+    } else if let Some(body) = rison.strip_prefix('(') {
+        ("(", body)
+    } else {
+        return Err(Error::new(ErrorCode::OutputLimitExceeded, None));
+    };
+    let body = body
+        .strip_suffix(')')
+        .expect("serialized lists and objects end with `)`");
+    if body.is_empty() {
+        // An empty list or object has no elements to split it between
+        return Err(Error::new(ErrorCode::OutputLimitExceeded, None));
+    }
+
+    let mut chunks = Vec::new();
+    let mut chunk = String::from(open);
+    for element in elements(body) {
+        if open.len() + element.len() + 1 > budget {
+            return Err(Error::new(ErrorCode::OutputLimitExceeded, None));
+        }
+        if chunk.len() > open.len() {
+            if chunk.len() + element.len() + 2 <= budget {
+                chunk.push(',');
+            } else {
+                chunk.push(')');
+                chunks.push(std::mem::replace(&mut chunk, String::from(open)));
+            }
+        }
+        chunk.push_str(element);
+    }
+    chunk.push(')');
+    chunks.push(chunk);
+    Ok(chunks)
+}
+
+/// The text of each element of the body of a serialized list or object,
+/// between its outermost parentheses
+fn elements(body: &str) -> impl Iterator<Item = &str> {
+    let bytes = body.as_bytes();
+    let mut start = 0;
+    let mut index = 0;
+    let mut depth = 0usize;
+    let mut quoted = false;
+    std::iter::from_fn(move || {
+        if start > bytes.len() || body.is_empty() {
+            return None;
+        }
+        while index < bytes.len() {
+            match (quoted, bytes[index]) {
+                // Skip the character escaped by `!!` or `!'`
+                (true, b'!') => index += 1,
+                (_, b'\'') => quoted = !quoted,
+                (false, b'(') => depth += 1,
+                (false, b')') => depth -= 1,
+                (false, b',') if depth == 0 => {
+                    let element = &body[start..index];
+                    index += 1;
+                    start = index;
+                    return Some(element);
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+        let element = &body[start..];
+        start = bytes.len() + 1;
+        Some(element)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn split_lists_and_objects() {
+        let ids: Vec<u32> = (1..=12).collect();
+        let chunks = crate::ser::to_chunks(&ids, 12).unwrap();
+        assert_eq!(chunks, ["!(1,2,3,4,5)", "!(6,7,8,9)", "!(10,11,12)"]);
+
+        let value = json!({"a": "x,y", "b": [1, [2, 3]], "c": "it's (1)", "d": null});
+        let chunks = crate::ser::to_chunks(&value, 16).unwrap();
+        assert_eq!(
+            chunks,
+            ["(a:'x,y')", "(b:!(1,!(2,3)))", "(c:'it!'s (1)')", "(d:!n)"]
+        );
+        let mut merged = serde_json::Map::new();
+        for chunk in chunks {
+            let serde_json::Value::Object(o) = crate::from_str(&chunk).unwrap() else {
+                panic!("{} is not an object", chunk);
+            };
+            merged.extend(o);
+        }
+        assert_eq!(serde_json::Value::Object(merged), value);
+
+        assert_eq!(crate::ser::to_chunks(&ids, 100).unwrap().len(), 1);
+        assert_eq!(crate::ser::to_chunks(&[(); 0], 3).unwrap(), ["!()"]);
+    }
+    #[test]
+    fn fail_split_beyond_budget() {
+        for (value, budget) in [
+            (json!("long string"), 5),
+            (json!([1, 2222, 3]), 6),
+            (json!([]), 2),
+            (json!({}), 1),
+        ] {
+            let err = crate::ser::to_chunks(&value, budget).unwrap_err();
+            assert!(matches!(err.code(), ErrorCode::OutputLimitExceeded));
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! The tokens a serializer writes, as a trait which can be customized
+
+use std::io;
+
+/// Writes the tokens of serialized Rison on behalf of a
+/// [`Serializer`](super::Serializer)
+///
+/// The serializer decides what to write, and the formatter how to write it.
+/// Every method has a default writing compact Rison, as
+/// [`CompactFormatter`] does, so an implementation overrides only those it
+/// needs to change, for example to quote strings differently, encode output
+/// for a particular context, or count the values written. Output which is
+/// not valid Rison will not parse back.
+///
+/// Each method is given the writer the serializer writes to. Output limits
+/// set with
+/// [`SerializerBuilder::max_output_len`](super::SerializerBuilder::max_output_len)
+/// apply to everything a formatter writes.
+///
+/// ```
+/// use std::io;
+///
+/// use rison::ser::{Formatter, Serializer};
+///
+/// /// Quotes every string, even those which could be written unquoted
+/// struct AlwaysQuote;
+///
+/// impl Formatter for AlwaysQuote {
+///     fn write_string<W>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
+///     where
+///         W: ?Sized + io::Write,
+///     {
+///         self.write_quoted_string(writer, value)
+///     }
+/// }
+///
+/// let mut ser = Serializer::with_formatter(Vec::new(), AlwaysQuote);
+/// serde::Serialize::serialize(&("a", Some("it's")), &mut ser).unwrap();
+///
+/// assert_eq!(ser.into_inner(), b"!('a','it!'s')");
+/// ```
+pub trait Formatter {
+    /// Write `!n`
+    fn write_null<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b"!n")
+    }
+
+    /// Write `!t` or `!f`
+    fn write_bool<W>(&mut self, writer: &mut W, value: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(if value { b"!t" } else { b"!f" })
+    }
+
+    /// Write a number, already formatted as valid Rison
+    fn write_number_str<W>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(value.as_bytes())
+    }
+
+    /// Write a string, or an object key, with
+    /// [`write_unquoted_string`](Formatter::write_unquoted_string) if it is
+    /// a valid id and with
+    /// [`write_quoted_string`](Formatter::write_quoted_string) otherwise
+    ///
+    /// Strings which only a [`Profile`](crate::Profile) other than the
+    /// default permits unquoted are written with `write_unquoted_string`
+    /// directly.
+    fn write_string<W>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if super::is_valid_id(value) {
+            self.write_unquoted_string(writer, value)
+        } else {
+            self.write_quoted_string(writer, value)
+        }
+    }
+
+    /// Write a string which may be written unquoted, verbatim
+    fn write_unquoted_string<W>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(value.as_bytes())
+    }
+
+    /// Write a string in quotes, escaping any `!` and `'` within it
+    fn write_quoted_string<W>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b"'")?;
+        let bytes = value.as_bytes();
+        let mut start = 0;
+        for (i, &b) in bytes.iter().enumerate() {
+            if let b'!' | b'\'' = b {
+                writer.write_all(&bytes[start..i])?;
+                writer.write_all(&[b'!', b])?;
+                start = i + 1;
+            }
+        }
+        writer.write_all(&bytes[start..])?;
+        writer.write_all(b"'")
+    }
+
+    /// Write the text of a [`RawValue`](crate::value::RawValue) verbatim
+    fn write_raw_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(fragment.as_bytes())
+    }
+
+    /// Write the `!(` opening a list
+    fn begin_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b"!(")
+    }
+
+    /// Write the `)` closing a list
+    fn end_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b")")
+    }
+
+    /// Write whatever precedes an element of a list: a `,` unless it is the
+    /// `first`
+    fn begin_array_value<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b",")
+        }
+    }
+
+    /// Write whatever follows an element of a list: nothing
+    fn end_array_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let _ = writer;
+        Ok(())
+    }
+
+    /// Write the `(` opening an object, or an object holding an enum variant
+    fn begin_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b"(")
+    }
+
+    /// Write the `)` closing an object
+    fn end_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b")")
+    }
+
+    /// Write whatever precedes the key of an object entry: a `,` unless it
+    /// is the `first`
+    fn begin_object_key<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b",")
+        }
+    }
+
+    /// Write whatever follows the key of an object entry: nothing
+    fn end_object_key<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let _ = writer;
+        Ok(())
+    }
+
+    /// Write the `:` between the key and value of an object entry
+    fn begin_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b":")
+    }
+
+    /// Write whatever follows the value of an object entry: nothing
+    fn end_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let _ = writer;
+        Ok(())
+    }
+}
+
+/// Writes compact Rison, as the serializer does by default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompactFormatter;
+
+impl Formatter for CompactFormatter {}
+
+/// Passes a formatter's output to the serializer's writer, counting it
+/// against the output limit
+pub(crate) struct LimitedWriter<'a, W> {
+    pub(crate) writer: &'a mut W,
+    pub(crate) written: &'a mut usize,
+    pub(crate) limit: usize,
+    /// Whether a write was refused for crossing the limit, so that the
+    /// resulting IO error is reported as such
+    pub(crate) exceeded: bool,
+}
+
+impl<W: io::Write> io::Write for LimitedWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if *self.written + buf.len() > self.limit {
+            self.exceeded = true;
+            return Err(io::Error::other("output length limit exceeded"));
+        }
+        let len = self.writer.write(buf)?;
+        *self.written += len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use super::Formatter;
+    use crate::ser::Serializer;
+
+    /// Counts the strings written, as telemetry might
+    #[derive(Default)]
+    struct CountStrings(usize);
+
+    impl Formatter for CountStrings {
+        fn write_string<W>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
+        where
+            W: ?Sized + io::Write,
+        {
+            self.0 += 1;
+            super::CompactFormatter.write_string(writer, value)
+        }
+    }
+
+    /// Writes Rison with a space after each `,` and `:`
+    struct Spaced;
+
+    impl Formatter for Spaced {
+        fn begin_array_value<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+        where
+            W: ?Sized + io::Write,
+        {
+            writer.write_all(if first { b"" } else { b", " })
+        }
+
+        fn begin_object_key<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+        where
+            W: ?Sized + io::Write,
+        {
+            writer.write_all(if first { b"" } else { b", " })
+        }
+
+        fn begin_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+        where
+            W: ?Sized + io::Write,
+        {
+            writer.write_all(b": ")
+        }
+    }
+
+    #[derive(serde::Serialize)]
+    enum E {
+        Unit,
+        Newtype(u8),
+        Tuple(u8, u8),
+        Struct { a: bool },
+    }
+
+    #[test]
+    fn serialize_with_formatter() {
+        let value = serde_json::json!({"a": [1, "b c", null], "d": {"e": true}});
+        let mut ser = Serializer::with_formatter(Vec::new(), CountStrings::default());
+        serde::Serialize::serialize(&value, &mut ser).unwrap();
+
+        assert_eq!(ser.formatter.0, 4);
+        assert_eq!(ser.into_inner(), b"(a:!(1,'b c',!n),d:(e:!t))");
+    }
+    #[test]
+    fn serialize_variants_with_formatter() {
+        let value = (
+            E::Unit,
+            E::Newtype(1),
+            E::Tuple(2, 3),
+            E::Struct { a: false },
+        );
+        let mut ser = Serializer::with_formatter(Vec::new(), Spaced);
+        serde::Serialize::serialize(&value, &mut ser).unwrap();
+
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "!(Unit, (Newtype: 1), (Tuple: !(2, 3)), (Struct: (a: !f)))"
+        );
+    }
+    #[test]
+    fn limit_formatter_output() {
+        let builder = crate::Serializer::builder().max_output_len(10);
+        let value = (1, 2, 3, 4);
+        assert_eq!(builder.to_string(&value).unwrap(), "!(1,2,3,4)");
+
+        let mut ser = builder.build_with_formatter(Vec::new(), Spaced);
+        let err = serde::Serialize::serialize(&value, &mut ser).unwrap_err();
+        assert!(matches!(
+            err.code(),
+            crate::error::ErrorCode::OutputLimitExceeded
+        ));
+    }
+}
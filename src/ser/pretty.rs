@@ -0,0 +1,228 @@
+//! Indented layouts of serialized values, for reading rather than for URLs
+
+/// How [`to_string_pretty`](super::to_string_pretty) lays out values
+///
+/// Both styles put each element of a non-empty list or object on a line of
+/// its own, indented by two spaces for each level of nesting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PrettyStyle {
+    /// Rison, with a space after each `:`
+    ///
+    /// The output parses back to the same value with
+    /// [`DeserializerBuilder::allow_whitespace`](crate::de::DeserializerBuilder::allow_whitespace).
+    #[default]
+    Rison,
+    /// JSON, for readers unfamiliar with Rison: `!t`, `!f` and `!n` are
+    /// written as `true`, `false` and `null`, lists and objects in brackets
+    /// and braces, and strings and keys in double quotes
+    Json,
+}
+
+const INDENT: &str = "  ";
+
+/// Lay out `rison`, as written by the serializer, in `style`
+pub(crate) fn reformat(rison: &str, style: PrettyStyle) -> String {
+    let bytes = rison.as_bytes();
+    let mut out = String::with_capacity(rison.len() * 2);
+    // The closing bracket of each list or object being written
+    let mut closers = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        match (bytes[index], bytes.get(index + 1)) {
+            (b'(', _) | (b'!', Some(b'(')) => {
+                let list = bytes[index] == b'!';
+                index += if list { 2 } else { 1 };
+                let (open, close) = match (style, list) {
+                    (PrettyStyle::Rison, true) => ("!(", ")"),
+                    (PrettyStyle::Rison, false) => ("(", ")"),
+                    (PrettyStyle::Json, true) => ("[", "]"),
+                    (PrettyStyle::Json, false) => ("{", "}"),
+                };
+                out.push_str(open);
+                if bytes.get(index) == Some(&b')') {
+                    out.push_str(close);
+                    index += 1;
+                } else {
+                    closers.push(close);
+                    newline(&mut out, closers.len());
+                }
+            }
+            (b')', _) => {
+                let close = closers.pop().expect("serialized brackets are balanced");
+                newline(&mut out, closers.len());
+                out.push_str(close);
+                index += 1;
+            }
+            (b',', _) => {
+                out.push(',');
+                newline(&mut out, closers.len());
+                index += 1;
+            }
+            (b':', _) => {
+                out.push_str(": ");
+                index += 1;
+            }
+            (b' ' | b'\t' | b'\n' | b'\r', _) => index += 1,
+            (b'\'', _) => {
+                let end = quoted_end(bytes, index);
+                match style {
+                    PrettyStyle::Rison => out.push_str(&rison[index..end]),
+                    PrettyStyle::Json => push_json_string(&mut out, &unescape(&rison[index..end])),
+                }
+                index = end;
+            }
+            _ => {
+                let len = bytes[index..]
+                    .iter()
+                    .position(|b| matches!(b, b',' | b':' | b')' | b' ' | b'\t' | b'\n' | b'\r'))
+                    .unwrap_or(bytes.len() - index);
+                let token = &rison[index..index + len];
+                // Object keys are followed by a `:`, and must be JSON strings
+                // even when written unquoted as numbers or bools
+                let key = bytes[index + len..]
+                    .iter()
+                    .find(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+                    == Some(&b':');
+                match (style, token) {
+                    (PrettyStyle::Rison, _) => out.push_str(token),
+                    (PrettyStyle::Json, _) if key => push_json_string(&mut out, token),
+                    (PrettyStyle::Json, "!t") => out.push_str("true"),
+                    (PrettyStyle::Json, "!f") => out.push_str("false"),
+                    (PrettyStyle::Json, "!n") => out.push_str("null"),
+                    // Numbers in the Rison grammar are valid JSON; unquoted
+                    // strings may begin like them under `Profile::Lenient`
+                    (PrettyStyle::Json, _) if crate::de::is_number(token.as_bytes(), false) => {
+                        out.push_str(token)
+                    }
+                    (PrettyStyle::Json, _) => push_json_string(&mut out, token),
+                }
+                index += len;
+            }
+        }
+    }
+    out
+}
+
+fn newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    for _ in 0..depth {
+        out.push_str(INDENT);
+    }
+}
+
+/// The index just past the closing quote of the string starting at `start`
+fn quoted_end(bytes: &[u8], start: usize) -> usize {
+    let mut index = start + 1;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'!' => index += 2,
+            b'\'' => return index + 1,
+            _ => index += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// The contents of a quoted string, with `!!` and `!'` unescaped
+fn unescape(quoted: &str) -> String {
+    let mut chars = quoted[1..quoted.len() - 1].chars();
+    let mut unescaped = String::with_capacity(quoted.len());
+    while let Some(c) = chars.next() {
+        match c {
+            '!' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::PrettyStyle;
+
+    #[test]
+    fn pretty_rison() {
+        let value = json!({"a": [1, "x y", []], "b": {}, "c": {"d": null, "e": "it's"}});
+        let pretty = crate::to_string_pretty(&value, PrettyStyle::Rison).unwrap();
+
+        let expected = "\
+(
+  a: !(
+    1,
+    'x y',
+    !()
+  ),
+  b: (),
+  c: (
+    d: !n,
+    e: 'it!'s'
+  )
+)";
+        assert_eq!(pretty, expected);
+
+        let mut de = crate::Deserializer::builder()
+            .allow_whitespace(true)
+            .from_str(&pretty);
+        let parsed: serde_json::Value = serde::Deserialize::deserialize(&mut de).unwrap();
+        assert_eq!(parsed, value);
+    }
+    #[test]
+    fn pretty_json() {
+        let value = json!({
+            "a": [1.5, -2, true, false, null],
+            "b c": "say \"it!'s\"\n",
+            "1": ["id", "2x", "-", ""],
+        });
+        let pretty = crate::to_string_pretty(&value, PrettyStyle::Json).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(parsed, value);
+        assert_eq!(pretty, serde_json::to_string_pretty(&value).unwrap());
+    }
+    #[test]
+    fn pretty_json_of_lenient_ids() {
+        let value = json!(["2024-01-01", "-x", "1x", 2, -0.5]);
+        let builder = crate::Serializer::builder().profile(crate::Profile::Lenient);
+        let pretty = builder.to_string_pretty(&value, PrettyStyle::Json).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(parsed, value);
+    }
+    #[test]
+    fn pretty_json_of_unquoted_keys() {
+        use std::collections::BTreeMap;
+
+        use crate::ser::NonStringKeys;
+
+        let builder = crate::Serializer::builder().non_string_keys(NonStringKeys::Unquoted);
+        let counts: BTreeMap<u32, u32> = [(1, 2)].into_iter().collect();
+        let pretty = builder
+            .to_string_pretty(&counts, PrettyStyle::Json)
+            .unwrap();
+        assert_eq!(pretty, "{\n  \"1\": 2\n}");
+
+        let flags: BTreeMap<bool, f64> = [(false, -1.5), (true, 0.25)].into_iter().collect();
+        let pretty = builder.to_string_pretty(&flags, PrettyStyle::Json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(parsed, json!({"false": -1.5, "true": 0.25}));
+    }
+}
@@ -0,0 +1,86 @@
+//! Checking that serialized values may be embedded in URIs unencoded
+
+use crate::error::{Error, ErrorCode, Result};
+
+/// The part of a URI in which Rison is to be embedded
+///
+/// Each allows the characters [RFC 3986] permits there without
+/// percent-encoding. Every part excludes spaces, non-ASCII characters, and
+/// `"#%<>[\]^`{|}`, so Rison containing quoted strings with any of these
+/// can never be embedded as it is.
+///
+/// [RFC 3986]: https://www.rfc-editor.org/rfc/rfc3986#section-3.3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UriComponent {
+    /// The value of a parameter of an `application/x-www-form-urlencoded`
+    /// query string, which additionally excludes the `&` and `=` separating
+    /// parameters, and the `+` read as a space
+    Query,
+    /// The fragment, after the `#`
+    Fragment,
+    /// A single segment of the path, which additionally excludes `/` and `?`
+    PathSegment,
+}
+
+impl UriComponent {
+    /// Whether `b` may appear unencoded in this part of a URI
+    fn allows(self, b: u8) -> bool {
+        let pchar = b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&b);
+        match self {
+            UriComponent::Query => (pchar || b"/?".contains(&b)) && !b"&=+".contains(&b),
+            UriComponent::Fragment => pchar || b"/?".contains(&b),
+            UriComponent::PathSegment => pchar,
+        }
+    }
+}
+
+/// Fail at the first character of `rison` which `component` does not allow
+pub(crate) fn check(rison: &str, component: UriComponent) -> Result<()> {
+    match rison.bytes().position(|b| !component.allows(b)) {
+        Some(position) => {
+            let c = rison[position..]
+                .chars()
+                .next()
+                .expect("position is in bounds");
+            Err(Error::new(ErrorCode::CharNeedsEncoding(c), Some(position)))
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UriComponent;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn check_components() {
+        for (value, query, fragment, path) in [
+            ("(a:!(1,-2.5),b:'x/y?')", true, true, false),
+            ("a=b&c", false, true, true),
+            ("1+1", false, true, true),
+            ("a b", false, false, false),
+            ("é", false, false, false),
+            ("100%", false, false, false),
+        ] {
+            let expected = [
+                (UriComponent::Query, query),
+                (UriComponent::Fragment, fragment),
+                (UriComponent::PathSegment, path),
+            ];
+            for (component, allowed) in expected {
+                let result = crate::ser::to_uri_string_strict(value, component);
+                assert_eq!(result.is_ok(), allowed, "{} in {:?}", value, component);
+            }
+        }
+    }
+    #[test]
+    fn report_first_offending_char() {
+        let err =
+            crate::ser::to_uri_string_strict(&("ok", "a é"), UriComponent::Query).unwrap_err();
+
+        assert!(matches!(err.code(), ErrorCode::CharNeedsEncoding(' ')));
+        assert_eq!(err.position(), Some(7));
+    }
+}
@@ -0,0 +1,6 @@
+//! Helpers for use with `#[serde(with = "...")]` in other formats
+//!
+//! - [`rison_string`], for a field holding a value encoded as a Rison
+//!   string, such as within a JSON document
+
+pub mod rison_string;
@@ -0,0 +1,112 @@
+//! Serialization of a value as a string of Rison, for formats such as JSON
+//! which embed Rison documents within string fields
+//!
+//! The field is written as a string holding the value's Rison, and read by
+//! parsing such a string, so the rest of the program works with the typed
+//! value. Errors from the Rison within the string are reported as custom
+//! errors of the enclosing format.
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Visit {
+//!     path: String,
+//!     #[serde(with = "rison::serde_helpers::rison_string")]
+//!     state: BTreeMap<String, u32>,
+//! }
+//!
+//! let json = r#"{"path":"/search","state":"(page:2,size:50)"}"#;
+//! let visit: Visit = serde_json::from_str(json).unwrap();
+//!
+//! assert_eq!(visit.state["page"], 2);
+//! assert_eq!(serde_json::to_string(&visit).unwrap(), json);
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{DeserializeOwned, Deserializer, Error, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Serialize a value as a string containing its Rison
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: Serializer,
+{
+    let rison = crate::to_string(value).map_err(serde::ser::Error::custom)?;
+    serializer.serialize_str(&rison)
+}
+
+/// Deserialize a value by parsing the Rison in a string
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: DeserializeOwned,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(RisonStringVisitor(PhantomData))
+}
+
+struct RisonStringVisitor<T>(PhantomData<T>);
+
+impl<T: DeserializeOwned> Visitor<'_> for RisonStringVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string of Rison")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+    where
+        E: Error,
+    {
+        crate::from_str(v).map_err(E::custom)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E>
+    where
+        E: Error,
+    {
+        crate::from_slice(v).map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Event {
+        kind: String,
+        #[serde(with = "super")]
+        state: serde_json::Value,
+    }
+
+    #[test]
+    fn rison_in_json_strings() {
+        let event = Event {
+            kind: "view".into(),
+            state: json!({"q": "it's", "tags": ["a", "b c"]}),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(
+            json,
+            json!({"kind": "view", "state": "(q:'it!'s',tags:!(a,'b c'))"})
+        );
+        assert_eq!(serde_json::from_value::<Event>(json).unwrap(), event);
+    }
+    #[test]
+    fn fail_invalid_rison_in_json_strings() {
+        for json in [
+            json!({"kind": "view", "state": "(q:"}),
+            json!({"kind": "view", "state": "!(1,2)x"}),
+            json!({"kind": "view", "state": {"q": 1}}),
+        ] {
+            assert!(serde_json::from_value::<Event>(json).is_err());
+        }
+        let err = serde_json::from_str::<Event>(r#"{"kind":"view","state":"(q:1"}"#).unwrap_err();
+        assert!(err.to_string().contains("unclosed object"), "{err}");
+    }
+}
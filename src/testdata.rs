@@ -0,0 +1,108 @@
+//! Test vectors from the reference implementations' test suites
+//!
+//! Taken from rison-node and Nanonid/rison, so that other implementations,
+//! and applications wrapping this one, can check their behaviour against
+//! the same expectations. Each encoding and decoding vector pairs Rison
+//! with the equivalent JSON.
+
+/// Values which encode to exactly this Rison, and decode back from it
+pub const ENCODE: &[(&str, &str)] = &[
+    (
+        "(a:0,b:foo,c:'23skidoo')",
+        r#"{"a":0,"b":"foo","c":"23skidoo"}"#,
+    ),
+    ("!t", "true"),
+    ("!f", "false"),
+    ("!n", "null"),
+    ("0", "0"),
+    ("1.5", "1.5"),
+    ("-3", "-3"),
+    ("1e30", "1e30"),
+    ("1e-30", "1e-30"),
+    ("G.", r#""G.""#),
+    ("a", r#""a""#),
+    ("'0a'", r#""0a""#),
+    ("'abc def'", r#""abc def""#),
+    ("()", "{}"),
+    ("(a:0)", r#"{"a":0}"#),
+    (
+        "(id:!n,type:/common/document)",
+        r#"{"id":null,"type":"/common/document"}"#,
+    ),
+    ("!()", "[]"),
+    ("!(!t,!f,!n,'')", r#"[true,false,null,""]"#),
+    ("'-h'", r#""-h""#),
+    ("a-z", r#""a-z""#),
+    ("'wow!!'", r#""wow!""#),
+    ("domain.com", r#""domain.com""#),
+    ("'user@domain.com'", r#""user@domain.com""#),
+    ("'US $10'", r#""US $10""#),
+    ("'can!'t'", r#""can't""#),
+    ("'Control-F: \u{6}'", r#""Control-F: \u0006""#),
+    ("'Unicode: \u{bef}'", r#""Unicode: ௯""#),
+];
+
+/// Rison which decodes to these values, but is not how they would be
+/// encoded
+pub const DECODE: &[(&str, &str)] = &[
+    ("'a'", r#""a""#),
+    ("'-1'", r#""-1""#),
+    ("1.0", "1"),
+    ("0.5e-2", "0.005"),
+    ("!('a',b)", r#"["a","b"]"#),
+    ("('a':1)", r#"{"a":1}"#),
+    ("(a:(b:!(c)))", r#"{"a":{"b":["c"]}}"#),
+    ("'!!'", r#""!""#),
+];
+
+/// Input which is not valid Rison, and fails to decode
+pub const INVALID: &[&str] = &[
+    "",
+    "-",
+    "--1",
+    "-h",
+    "1e",
+    "1x",
+    "2abc",
+    "!(1,-a)",
+    "(a:0b)",
+    "!",
+    "!x",
+    "!T",
+    "'abc",
+    "'!x'",
+    "(a)",
+    "(a:1",
+    "!(1,2",
+    "!(1,2)x",
+    "(a:1)(b:2)",
+];
+
+#[cfg(test)]
+mod test {
+    // Encoding vectors are checked by the serializer's tests
+
+    #[test]
+    fn decode_vectors() {
+        for (rison, json) in super::ENCODE.iter().chain(super::DECODE) {
+            let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+
+            assert_eq!(
+                crate::from_str::<serde_json::Value>(rison).unwrap(),
+                expected,
+                "{}",
+                rison
+            );
+        }
+    }
+    #[test]
+    fn fail_decode_invalid_vectors() {
+        for rison in super::INVALID {
+            assert!(
+                crate::from_str::<serde_json::Value>(rison).is_err(),
+                "{}",
+                rison
+            );
+        }
+    }
+}
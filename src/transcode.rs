@@ -0,0 +1,188 @@
+//! Converting Rison to JSON as it is read, without building a value
+//!
+//! Events from a [`Parser`] are written out as JSON as soon as they are
+//! read, so memory use is bounded by the longest string and the depth of
+//! nesting rather than the size of the document. This suits ingesting large
+//! Rison documents, such as query logs, into JSON-based tools. Numbers are
+//! written with exactly the digits they were read with, as Rison numbers
+//! are valid JSON numbers.
+
+use std::io;
+
+use crate::de::read::Read;
+use crate::de::{Deserializer, Event, Parser};
+use crate::error::{Error, ErrorCode, Result};
+
+/// Transcode Rison read from an IO stream into compact JSON written to
+/// `writer`
+///
+/// Neither the reader nor the writer is buffered, so wrapping them in an
+/// `io::BufReader` and `io::BufWriter` is recommended. If reading or
+/// writing fails, the JSON already written is incomplete.
+///
+/// ```
+/// let rison = b"(a:!(1,'x y',!n),b:(c:!t))";
+/// let mut json = Vec::new();
+/// rison::transcode::to_json_writer(&rison[..], &mut json).unwrap();
+///
+/// assert_eq!(json, br#"{"a":[1,"x y",null],"b":{"c":true}}"#);
+/// ```
+pub fn to_json_writer<R, W>(reader: R, writer: W) -> Result<()>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    write_json(Parser::new(Deserializer::from_reader(reader)), writer)
+}
+
+/// Transcode the events of a [`Parser`] into compact JSON written to
+/// `writer`
+///
+/// This reads from any input source, with the options of a configured
+/// deserializer.
+///
+/// ```
+/// use rison::de::Parser;
+///
+/// let builder = rison::Deserializer::builder().allow_whitespace(true);
+/// let mut json = Vec::new();
+/// rison::transcode::write_json(Parser::new(builder.from_str("!( 1, 2 )")), &mut json).unwrap();
+///
+/// assert_eq!(json, b"[1,2]");
+/// ```
+pub fn write_json<'de, R, W>(parser: Parser<'de, R>, mut writer: W) -> Result<()>
+where
+    R: Read<'de>,
+    W: io::Write,
+{
+    // Whether each enclosing container is an object, innermost last
+    let mut objects = Vec::new();
+    // Whether a `,` must precede the next value or key
+    let mut needs_comma = false;
+    for event in parser {
+        let event = event?.into_inner();
+        let io = |e| Error::new(ErrorCode::Io(e), None);
+        if needs_comma && !matches!(event, Event::End) {
+            writer.write_all(b",").map_err(io)?;
+        }
+        needs_comma = true;
+        match event {
+            Event::Null => writer.write_all(b"null"),
+            Event::Bool(true) => writer.write_all(b"true"),
+            Event::Bool(false) => writer.write_all(b"false"),
+            Event::Number(n) => writer.write_all(n.as_bytes()),
+            Event::String(s) => write_json_string(&mut writer, &s),
+            Event::StartList => {
+                objects.push(false);
+                needs_comma = false;
+                writer.write_all(b"[")
+            }
+            Event::StartObject => {
+                objects.push(true);
+                needs_comma = false;
+                writer.write_all(b"{")
+            }
+            Event::Key(key) => {
+                needs_comma = false;
+                write_json_string(&mut writer, &key).and_then(|()| writer.write_all(b":"))
+            }
+            Event::End => match objects.pop() {
+                Some(true) => writer.write_all(b"}"),
+                _ => writer.write_all(b"]"),
+            },
+        }
+        .map_err(io)?;
+    }
+    writer
+        .flush()
+        .map_err(|e| Error::new(ErrorCode::Io(e), None))
+}
+
+/// Write a JSON string, escaping `"`, `\` and control characters
+pub(crate) fn write_json_string<W: io::Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    let bytes = s.as_bytes();
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let escape: &[u8] = match b {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08 => b"\\b",
+            0x0c => b"\\f",
+            0x00..=0x1f => &[
+                b'\\',
+                b'u',
+                b'0',
+                b'0',
+                HEX[usize::from(b >> 4)],
+                HEX[usize::from(b & 0xf)],
+            ],
+            _ => continue,
+        };
+        writer.write_all(&bytes[start..i])?;
+        writer.write_all(escape)?;
+        start = i + 1;
+    }
+    writer.write_all(&bytes[start..])?;
+    writer.write_all(b"\"")
+}
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+#[cfg(test)]
+mod test {
+    use crate::de::Parser;
+    use crate::error::ErrorCode;
+
+    fn transcode(rison: &str) -> crate::Result<String> {
+        let mut json = Vec::new();
+        super::to_json_writer(rison.as_bytes(), &mut json)?;
+        Ok(String::from_utf8(json).unwrap())
+    }
+
+    #[test]
+    fn transcode_to_json() {
+        for rison in [
+            "(a:!(1,-2.5e-3,'x y',!n,!t,!f),b:(c:()),d:!(),'e f':'it!'s',g:!(!(1),(h:2)))",
+            "'\"\\\\\u{0}\u{1f}\n\t\r\u{8}\u{c}é\u{7f}'",
+            "12",
+            "abc",
+            "()",
+        ] {
+            let json = transcode(rison).unwrap();
+            let expected: serde_json::Value = crate::from_str(rison).unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+                expected
+            );
+        }
+        assert_eq!(transcode("'a\u{1}\"'").unwrap(), r#""a\u0001\"""#);
+        assert_eq!(transcode("!(1.50,-0)").unwrap(), "[1.50,-0]");
+    }
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn transcode_deeply_nested() {
+        let rison = "!(".repeat(100_000) + &")".repeat(100_000);
+        let json = transcode(&rison).unwrap();
+
+        assert_eq!(json, "[".repeat(100_000) + &"]".repeat(100_000));
+    }
+    #[test]
+    fn fail_transcode_invalid_rison() {
+        let mut json = Vec::new();
+        let err = super::to_json_writer(&b"(a:!(1,2),b:"[..], &mut json).unwrap_err();
+        assert!(err.is_eof());
+        assert_eq!(json, br#"{"a":[1,2],"b":"#);
+
+        let err = transcode("!(1,2)x").unwrap_err();
+        assert!(matches!(err.code(), ErrorCode::TrailingChars));
+
+        let builder = crate::Deserializer::builder().profile(crate::Profile::RisonJs);
+        let mut json = Vec::new();
+        super::write_json(Parser::new(builder.from_str("!(007,1.)")), &mut json).unwrap();
+        assert_eq!(json, b"[7,1]");
+    }
+}
@@ -0,0 +1,634 @@
+//! A loosely typed representation of any valid Rison value
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{Deserialize, MapAccess, SeqAccess, Visitor};
+use serde::ser::Serialize;
+
+use crate::error::Error;
+use crate::number::{Number, NumberVisitor};
+
+#[cfg(any(test, feature = "proptest"))]
+mod arbitrary;
+mod de;
+mod index;
+#[cfg(feature = "json")]
+mod json;
+mod lossy;
+mod merge;
+mod order;
+pub(crate) mod raw;
+#[cfg(feature = "uuid")]
+mod uuid;
+
+pub use de::from_value_ref;
+pub use index::Index;
+pub use lossy::parse_lossy;
+pub use order::SortedBy;
+pub use raw::{serialize_raw, to_raw_value, RawRison, RawValue};
+
+pub use crate::map::Map;
+
+/// Any valid Rison value
+///
+/// A `Value`, or a reference to one, is also a deserializer, and implements
+/// [`IntoDeserializer`](serde::de::IntoDeserializer), so that values which
+/// have already been parsed can be deserialized into typed data as Rison
+/// text would be.
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Search<'a> {
+///     q: &'a str,
+///     page: u32,
+/// }
+///
+/// let state: rison::Value = "(page:2,q:rust)".parse().unwrap();
+/// let search = Search::deserialize(&state).unwrap();
+///
+/// assert_eq!((search.q, search.page), ("rust", 2));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    /// `!n`
+    #[default]
+    Null,
+    /// `!t` or `!f`
+    Bool(bool),
+    /// A number such as `12` or `-1.5e3`
+    Number(Number),
+    /// A quoted string such as `'hello world'`, or an unquoted ident
+    String(String),
+    /// A list such as `!(a,b,c)`
+    Array(Vec<Value>),
+    /// An object such as `(a:1,b:2)`
+    Object(Map<String, Value>),
+}
+
+impl Value {
+    /// Index into an object by key, or a list by position
+    ///
+    /// Returns `None` if the value is not an object or list, or if the key
+    /// or position is not present.
+    ///
+    /// ```
+    /// let state: rison::Value = "(filters:!((field:status)),page:2)".parse().unwrap();
+    ///
+    /// assert_eq!(state.get("page").and_then(|page| page.as_u64()), Some(2));
+    /// assert_eq!(
+    ///     state.get("filters").and_then(|f| f.get(0)).and_then(|f| f.get("field")),
+    ///     Some(&rison::Value::String("status".into())),
+    /// );
+    /// assert_eq!(state.get(0), None);
+    /// ```
+    pub fn get<I: Index>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Index into an object by key, or a list by position, and return a
+    /// mutable reference to the value there
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Value> {
+        index.index_into_mut(self)
+    }
+
+    /// Whether the value is `!n`
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// If the value is `!n`, return `()`
+    pub fn as_null(&self) -> Option<()> {
+        match self {
+            Value::Null => Some(()),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is `!t` or `!f`
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, Value::Bool(_))
+    }
+
+    /// If the value is `!t` or `!f`, return it as a `bool`
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is a number
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Number(_))
+    }
+
+    /// If the value is a number, return it
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is a number representable as an `i64`
+    ///
+    /// See [`Number::is_i64`].
+    pub fn is_i64(&self) -> bool {
+        self.as_number().is_some_and(Number::is_i64)
+    }
+
+    /// Whether the value is a number representable as a `u64`
+    ///
+    /// See [`Number::is_u64`].
+    pub fn is_u64(&self) -> bool {
+        self.as_number().is_some_and(Number::is_u64)
+    }
+
+    /// Whether the value is a number representable only as an `f64`
+    ///
+    /// See [`Number::is_f64`].
+    pub fn is_f64(&self) -> bool {
+        self.as_number().is_some_and(Number::is_f64)
+    }
+
+    /// If the value is a number representable as an `i64`, return it
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    /// If the value is a number representable as a `u64`, return it
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
+    /// If the value is a number, return it as an `f64`, which may be
+    /// approximate
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().and_then(Number::as_f64)
+    }
+
+    /// Whether the value is a string
+    pub fn is_string(&self) -> bool {
+        matches!(self, Value::String(_))
+    }
+
+    /// If the value is a string, return it
+    ///
+    /// Numbers are not strings, even when written unquoted like idents: the
+    /// value of `'12'` is a string, but that of `12` is not.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is a list
+    pub fn is_array(&self) -> bool {
+        matches!(self, Value::Array(_))
+    }
+
+    /// If the value is a list, return its elements
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// If the value is a list, return its elements mutably
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is an object
+    pub fn is_object(&self) -> bool {
+        matches!(self, Value::Object(_))
+    }
+
+    /// If the value is an object, return its entries
+    pub fn as_object(&self) -> Option<&Map<String, Value>> {
+        match self {
+            Value::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// If the value is an object, return its entries mutably
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<String, Value>> {
+        match self {
+            Value::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Look up a value by a JSON Pointer, as defined by RFC 6901
+    ///
+    /// A pointer is a sequence of `/`-prefixed tokens, each of which is an
+    /// object key or a list index. Within tokens, `~1` stands for `/` and
+    /// `~0` for `~`. The empty pointer refers to the whole value.
+    ///
+    /// ```
+    /// let state: rison::Value = "(filters:!((field:status,value:open)))".parse().unwrap();
+    ///
+    /// assert_eq!(
+    ///     state.pointer("/filters/0/value"),
+    ///     Some(&rison::Value::String("open".into())),
+    /// );
+    /// assert_eq!(state.pointer("/filters/1"), None);
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        parse_pointer(pointer)?.try_fold(self, |target, token| match target {
+            Value::Object(o) => o.get(&*token),
+            Value::Array(a) => parse_index(&token).and_then(|i| a.get(i)),
+            _ => None,
+        })
+    }
+
+    /// Look up a value by a JSON Pointer, and return a mutable reference to it
+    ///
+    /// See [`Value::pointer`] for the pointer syntax.
+    ///
+    /// ```
+    /// let mut state: rison::Value = "(filters:!((field:status,value:open)))".parse().unwrap();
+    ///
+    /// *state.pointer_mut("/filters/0/value").unwrap() = rison::Value::String("closed".into());
+    /// let value = state.pointer("/filters/0/value").and_then(rison::Value::as_str);
+    /// assert_eq!(value, Some("closed"));
+    /// ```
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        parse_pointer(pointer)?.try_fold(self, |target, token| match target {
+            Value::Object(o) => o.get_mut(&*token),
+            Value::Array(a) => parse_index(&token).and_then(move |i| a.get_mut(i)),
+            _ => None,
+        })
+    }
+}
+
+/// Split a JSON Pointer into its unescaped tokens, or `None` if it is not
+/// empty and does not begin with `/`
+fn parse_pointer(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    let tokens = match pointer.strip_prefix('/') {
+        Some(tokens) => Some(tokens.split('/')),
+        None if pointer.is_empty() => None,
+        None => return None,
+    };
+    Some(tokens.into_iter().flatten().map(|token| {
+        if token.contains('~') {
+            Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+        } else {
+            Cow::Borrowed(token)
+        }
+    }))
+}
+
+/// Parse a list index within a JSON Pointer, which may not have a leading
+/// zero or sign
+fn parse_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.starts_with('0') && token.len() > 1) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => n.serialize(serializer),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(a) => a.serialize(serializer),
+            Value::Object(o) => o.serialize(serializer),
+        }
+    }
+}
+
+/// Formats the value as Rison text, as with [`crate::to_string`]
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::ser::to_fmt_writer(f, self).map_err(|_| fmt::Error)
+    }
+}
+
+/// Parses Rison text, as with [`crate::from_str`]
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        crate::from_str(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[cfg(not(feature = "arbitrary_precision"))]
+        return deserializer.deserialize_any(ValueVisitor);
+        #[cfg(feature = "arbitrary_precision")]
+        return deserializer.deserialize_newtype_struct(crate::number::TOKEN, ValueVisitor);
+    }
+}
+
+pub(crate) struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid Rison value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        NumberVisitor.visit_i64(v).map(Value::Number)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E>
+    where
+        E: serde::de::Error,
+    {
+        NumberVisitor.visit_u64(v).map(Value::Number)
+    }
+
+    /// Non-finite numbers from other formats, which Rison cannot represent,
+    /// become `Null`
+    fn visit_f32<E>(self, v: f32) -> Result<Value, E> {
+        Ok(Number::from_f32(v).map_or(Value::Null, Value::Number))
+    }
+
+    /// Non-finite numbers from other formats, which Rison cannot represent,
+    /// become `Null`
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            vec.push(element);
+        }
+        Ok(Value::Array(vec))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut object = Map::new();
+
+        #[cfg(feature = "arbitrary_precision")]
+        match map.next_key_seed(KeyClassifier)? {
+            Some(KeyClass::Number) => {
+                let number = map.next_value()?;
+                return Ok(Value::Number(Number::from_string_unchecked(number)));
+            }
+            Some(KeyClass::Map(key)) => {
+                object.insert(key, map.next_value()?);
+            }
+            None => return Ok(Value::Object(object)),
+        }
+
+        while let Some((key, value)) = map.next_entry()? {
+            object.insert(key, value);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+/// Distinguishes the first key of an exact number from that of an object
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) struct KeyClassifier;
+
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) enum KeyClass {
+    Number,
+    Map(String),
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'de> serde::de::DeserializeSeed<'de> for KeyClassifier {
+    type Value = KeyClass;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<KeyClass, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl Visitor<'_> for KeyClassifier {
+    type Value = KeyClass;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string key")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<KeyClass, E> {
+        if s == crate::number::TOKEN {
+            Ok(KeyClass::Number)
+        } else {
+            Ok(KeyClass::Map(s.to_owned()))
+        }
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<KeyClass, E> {
+        if s == crate::number::TOKEN {
+            Ok(KeyClass::Number)
+        } else {
+            Ok(KeyClass::Map(s))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Number, Value};
+
+    #[test]
+    fn deserialize_value() {
+        let v: Value = crate::from_str("(a:!(1,-2.5,'x y'),b:!t,c:!n)").unwrap();
+
+        assert_eq!(
+            v,
+            Value::Object(
+                [
+                    (
+                        "a".into(),
+                        Value::Array(vec![
+                            Value::Number(1.into()),
+                            Value::Number(Number::from_f64(-2.5).unwrap()),
+                            Value::String("x y".into()),
+                        ])
+                    ),
+                    ("b".into(), Value::Bool(true)),
+                    ("c".into(), Value::Null),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+    }
+    #[cfg(any(feature = "preserve_order", not(feature = "unordered_map")))]
+    #[test]
+    fn serialize_value_round_trips() {
+        let input = "(a:!(1,-2.5,'x y'),b:!t,c:!n,d:())";
+        let v: Value = crate::from_str(input).unwrap();
+
+        assert_eq!(crate::to_string(&v).unwrap(), input);
+    }
+    #[cfg(any(feature = "preserve_order", not(feature = "unordered_map")))]
+    #[test]
+    fn parse_and_display_value() {
+        let v: Value = "(a:!(1,'x y'),b:!t)".parse().unwrap();
+
+        assert_eq!(v.to_string(), "(a:!(1,'x y'),b:!t)");
+        assert!("(a:".parse::<Value>().is_err());
+    }
+    #[test]
+    fn access_values_by_type() {
+        let mut v: Value = "(a:!(1,-2,1.5,x,'',!t,!n),b:(c:()))".parse().unwrap();
+
+        let a = v.get("a").and_then(Value::as_array).unwrap();
+        assert_eq!(a.len(), 7);
+        assert_eq!(
+            (a[0].as_u64(), a[0].as_i64(), a[0].as_f64()),
+            (Some(1), Some(1), Some(1.0))
+        );
+        assert_eq!((a[1].as_u64(), a[1].as_i64()), (None, Some(-2)));
+        assert!(a[2].is_f64() && a[2].is_number() && !a[2].is_i64());
+        assert_eq!((a[3].as_str(), a[4].as_str()), (Some("x"), Some("")));
+        assert_eq!((a[5].as_bool(), a[6].as_null()), (Some(true), Some(())));
+        assert!(a[3].as_i64().is_none() && a[0].as_str().is_none() && !a[5].is_null());
+
+        assert!(v.get("b").is_some_and(Value::is_object));
+        assert_eq!(v.get(String::from("a")).and_then(|a| a.get(3)), Some(&a[3]));
+        assert_eq!(v.get("c"), None);
+        assert_eq!(v.get(0), None);
+        assert_eq!(v.get("a").unwrap().get("x"), None);
+
+        v.get_mut("b")
+            .and_then(Value::as_object_mut)
+            .unwrap()
+            .clear();
+        v.get_mut("a")
+            .and_then(Value::as_array_mut)
+            .unwrap()
+            .truncate(1);
+        assert_eq!(v, "(a:!(1),b:())".parse::<Value>().unwrap());
+    }
+    #[test]
+    fn lookup_value_by_pointer() {
+        let mut v: Value = "(a:(b:!(1,2,(c:x))),'d/e':f,'g~h':i,'':j)".parse().unwrap();
+
+        assert_eq!(v.pointer(""), Some(&v.clone()));
+        assert_eq!(v.pointer("/a/b/2/c"), Some(&Value::String("x".into())));
+        assert_eq!(v.pointer("/a/b/0"), Some(&Value::Number(1.into())));
+        assert_eq!(v.pointer("/d~1e"), Some(&Value::String("f".into())));
+        assert_eq!(v.pointer("/g~0h"), Some(&Value::String("i".into())));
+        assert_eq!(v.pointer("/"), Some(&Value::String("j".into())));
+        for missing in [
+            "a", "/a/b/3", "/a/b/01", "/a/b/+1", "/a/b/-1", "/a/x", "/d~1e/f",
+        ] {
+            assert_eq!(v.pointer(missing), None, "{}", missing);
+        }
+
+        *v.pointer_mut("/a/b/2/c").unwrap() = Value::Bool(true);
+        if let Some(Value::Array(b)) = v.pointer_mut("/a/b") {
+            b.remove(0);
+        }
+        assert_eq!(v.pointer("/a").unwrap().to_string(), "(b:!(2,(c:!t)))");
+    }
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn preserve_object_key_order() {
+        let input = "(z:1,a:(y:!t,b:!f),m:'x y')";
+        let v: Value = crate::from_str(input).unwrap();
+
+        assert_eq!(v.to_string(), input);
+    }
+    #[cfg(feature = "json")]
+    #[test]
+    fn convert_json_value() {
+        let json = serde_json::json!({"a": [1, 2.5, "three"], "b": null, "c": false});
+        let v = Value::from(json.clone());
+
+        assert_eq!(v, crate::from_str("(a:!(1,2.5,three),b:!n,c:!f)").unwrap());
+        assert_eq!(serde_json::Value::from(v), json);
+    }
+    #[cfg(feature = "json")]
+    #[test]
+    fn convert_json_numbers_beyond_f64() {
+        use crate::number::Number;
+
+        let n = serde_json::Number::from_f64(-1.5).unwrap();
+        assert_eq!(
+            Number::try_from(n).unwrap(),
+            Number::from_f64(-1.5).unwrap()
+        );
+
+        // Only parsed with `serde_json`'s `arbitrary_precision` feature,
+        // which another crate may enable
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>("[1e400]") {
+            let v = Value::from(json);
+            #[cfg(not(feature = "arbitrary_precision"))]
+            assert_eq!(v, Value::Array(vec![Value::Null]));
+            #[cfg(feature = "arbitrary_precision")]
+            assert_eq!(v.to_string(), "!(1e400)");
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Generation of arbitrary values for property-based testing
+
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+
+use super::Value;
+use crate::number::Number;
+
+impl Arbitrary for Value {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Value>;
+
+    /// Generates any value which survives a round-trip through Rison. In
+    /// particular, numbers are always finite.
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(|n| Value::Number(n.into())),
+            any::<u64>().prop_map(|n| Value::Number(n.into())),
+            (proptest::num::f64::NORMAL | proptest::num::f64::SUBNORMAL | proptest::num::f64::ZERO)
+                .prop_map(|n| Value::Number(Number::from_f64(n).unwrap())),
+            any::<String>().prop_map(Value::String),
+            "[a-z][a-z0-9_.-]{0,8}".prop_map(Value::String),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..8).prop_map(Value::Array),
+                btree_map(any::<String>(), inner, 0..8)
+                    .prop_map(|object| Value::Object(object.into_iter().collect())),
+            ]
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::Value;
+
+    proptest! {
+        #[test]
+        fn value_round_trips(v in any::<Value>()) {
+            let rison = crate::to_string(&v).unwrap();
+            let parsed: Value = crate::from_str(&rison).unwrap();
+
+            prop_assert_eq!(parsed, v);
+        }
+
+        #[test]
+        fn value_round_trips_through_io(v in any::<Value>()) {
+            let rison = crate::to_vec(&v).unwrap();
+            let parsed: Value = crate::from_reader(&rison[..]).unwrap();
+
+            prop_assert_eq!(parsed, v);
+        }
+
+        #[test]
+        fn value_round_trips_in_place(v in any::<Value>()) {
+            let mut rison = crate::to_vec(&v).unwrap();
+            let parsed: Value = crate::de::from_bytes_mut(&mut rison).unwrap();
+
+            prop_assert_eq!(parsed, v);
+        }
+
+        #[test]
+        fn string_round_trips(s in any::<String>()) {
+            let rison = crate::to_string(&s).unwrap();
+            let parsed: String = crate::from_str(&rison).unwrap();
+
+            prop_assert_eq!(parsed, s);
+        }
+
+        #[test]
+        fn arbitrary_input_does_not_panic(s in any::<String>()) {
+            let _ = crate::from_str::<Value>(&s);
+            let _ = crate::de::from_bytes_mut::<Value>(&mut s.clone().into_bytes());
+            let _ = super::super::parse_lossy(&s);
+        }
+    }
+}
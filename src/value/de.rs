@@ -0,0 +1,421 @@
+//! Deserializing Rust data structures from a `Value`
+//!
+//! A [`Value`], or a reference to one, is itself a deserializer, so input
+//! which has already been parsed can be deserialized into a typed struct
+//! through [`IntoDeserializer`] as though it were still Rison text. Strings
+//! are decoded as base64 where bytes are expected, as in Rison text.
+//! Deserializing from a reference borrows its strings.
+
+use serde::de::value::{
+    BorrowedStrDeserializer, MapAccessDeserializer, MapDeserializer, SeqDeserializer,
+};
+use serde::de::{self, Deserialize, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use super::Value;
+use crate::de::Reference;
+use crate::error::{Error, Result};
+use crate::number::Number;
+
+/// Deserialize an instance of `T` from a reference to a [`Value`], without
+/// cloning it
+///
+/// Strings are borrowed from the value where `T` borrows them, so one
+/// parsed value can be viewed as several typed structs cheaply. Bytes are
+/// decoded from base64, and so are always copied.
+///
+/// ```
+/// use rison::value::from_value_ref;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Query<'a> {
+///     q: &'a str,
+/// }
+///
+/// #[derive(serde::Deserialize)]
+/// struct Page {
+///     page: u32,
+/// }
+///
+/// let state: rison::Value = "(page:2,q:'rust serde')".parse().unwrap();
+/// let query: Query = from_value_ref(&state).unwrap();
+/// let page: Page = from_value_ref(&state).unwrap();
+///
+/// assert_eq!((query.q, page.page), ("rust serde", 2));
+/// ```
+pub fn from_value_ref<'a, T>(value: &'a Value) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    T::deserialize(value)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl Number {
+    /// Visit this number as the narrowest of `u64`, `i64` or `f64` which
+    /// represents it
+    fn deserialize_any<'de, V>(&self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(u) = self.as_u64() {
+            visitor.visit_u64(u)
+        } else if let Some(i) = self.as_i64() {
+            visitor.visit_i64(i)
+        } else if let Some(f) = self.as_f64() {
+            visitor.visit_f64(f)
+        } else {
+            #[cfg(feature = "arbitrary_precision")]
+            return visitor.visit_str(self.as_str());
+            #[cfg(not(feature = "arbitrary_precision"))]
+            unreachable!("numbers are representable as an `f64`")
+        }
+    }
+}
+
+/// Deserialize the private newtypes through which raw values and exact
+/// numbers are requested, or hand back the visitor for any other newtype
+fn deserialize_private_newtype<'de, V>(
+    value: &Value,
+    name: &'static str,
+    visitor: V,
+) -> std::result::Result<Result<V::Value>, V>
+where
+    V: Visitor<'de>,
+{
+    if name == super::raw::TOKEN {
+        return Ok(crate::to_string(value).and_then(|rison| {
+            visitor.visit_map(super::raw::RawDeserializer::new(Reference::Copied(&rison)))
+        }));
+    }
+    #[cfg(feature = "arbitrary_precision")]
+    if let (crate::number::TOKEN, Value::Number(n)) = (name, value) {
+        let number = crate::number::NumberDeserializer::new(n.as_str().to_owned());
+        return Ok(visitor.visit_map(number));
+    }
+    Err(visitor)
+}
+
+/// Enums are written as a string for a unit variant, or as an object with a
+/// single entry from the variant to its contents
+fn single_entry(len: usize) -> Result<()> {
+    if len == 1 {
+        return Ok(());
+    }
+    Err(de::Error::invalid_value(
+        de::Unexpected::Map,
+        &"an object with a single key",
+    ))
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(n) => n.deserialize_any(visitor),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Array(a) => {
+                let mut seq = SeqDeserializer::new(a.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(o) => {
+                let mut map = MapDeserializer::new(o.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => match crate::bytes::decode(&s) {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None => Err(de::Error::invalid_value(de::Unexpected::Str(&s), &visitor)),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match deserialize_private_newtype(&self, name, visitor) {
+            Ok(result) => result,
+            Err(visitor) => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+            Value::Object(o) => {
+                single_entry(o.len())?;
+                let map = MapDeserializer::new(o.into_iter());
+                visitor.visit_enum(MapAccessDeserializer::new(map))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Number(n) => n.deserialize_any(visitor),
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Array(a) => {
+                let mut seq = SeqDeserializer::new(a.iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(o) => {
+                let entries = o.iter().map(|(k, v)| (BorrowedStrDeserializer::new(k), v));
+                let mut map = MapDeserializer::new(entries);
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => match crate::bytes::decode(s) {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None => Err(de::Error::invalid_value(de::Unexpected::Str(s), &visitor)),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match deserialize_private_newtype(self, name, visitor) {
+            Ok(result) => result,
+            Err(visitor) => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => visitor.visit_enum(BorrowedStrDeserializer::new(s)),
+            Value::Object(o) => {
+                single_entry(o.len())?;
+                let entries = o.iter().map(|(k, v)| (BorrowedStrDeserializer::new(k), v));
+                visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(entries)))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use serde::de::IntoDeserializer;
+    use serde::Deserialize;
+
+    use crate::value::{RawValue, Value};
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    enum Sort {
+        Asc,
+        Desc,
+        By(String),
+    }
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Search<'a> {
+        q: &'a str,
+        page: Option<u32>,
+        sort: Vec<Sort>,
+        cursor: Bytes,
+        filters: BTreeMap<String, i64>,
+    }
+
+    /// Requests a byte string, as `serde_bytes::ByteBuf` would
+    #[derive(Debug, PartialEq)]
+    struct Bytes(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Bytes {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct BytesVisitor;
+
+            impl serde::de::Visitor<'_> for BytesVisitor {
+                type Value = Bytes;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("bytes")
+                }
+
+                fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                    Ok(Bytes(v))
+                }
+            }
+
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+
+    #[test]
+    fn deserialize_from_value() {
+        let value: Value = "(cursor:AQI,filters:(a:-1),page:!n,q:rust,sort:!(Asc,(By:name)))"
+            .parse()
+            .unwrap();
+
+        let search = Search::deserialize(&value).unwrap();
+        assert_eq!(search.q, "rust");
+        assert_eq!(search.page, None);
+        assert_eq!(search.sort, [Sort::Asc, Sort::By("name".into())]);
+        assert_eq!(search.cursor, Bytes(vec![1, 2]));
+        assert_eq!(search.filters["a"], -1);
+
+        let sort = Vec::<Sort>::deserialize(value.get("sort").unwrap().clone().into_deserializer())
+            .unwrap();
+        assert_eq!(sort, [Sort::Asc, Sort::By("name".into())]);
+    }
+    #[test]
+    fn deserialize_value_from_value() {
+        let value: Value = "(a:!(1,-2,1.5,'x y',!t,!n),b:())".parse().unwrap();
+
+        assert_eq!(Value::deserialize(&value).unwrap(), value);
+        assert_eq!(Value::deserialize(value.clone()).unwrap(), value);
+        // Entries are written in the order the map iterates, which is
+        // arbitrary when it is backed by a hash map
+        let raw = <Box<RawValue>>::deserialize(&value).unwrap();
+        assert_eq!(raw.get().parse::<Value>().unwrap(), value);
+        #[cfg(not(all(feature = "unordered_map", not(feature = "preserve_order"))))]
+        assert_eq!(raw.get(), "(a:!(1,-2,1.5,'x y',!t,!n),b:())");
+    }
+    #[test]
+    fn deserialize_borrowing_from_value_ref() {
+        #[derive(serde::Deserialize)]
+        struct Names<'a> {
+            #[serde(borrow)]
+            names: Vec<&'a str>,
+            #[serde(borrow)]
+            by_id: BTreeMap<&'a str, &'a str>,
+        }
+
+        let value: Value = "(by_id:(a:'x y'),names:!(p,'q r'))".parse().unwrap();
+        let names: Names = super::from_value_ref(&value).unwrap();
+
+        let stored = value.get("names").and_then(Value::as_array).unwrap();
+        assert_eq!(names.names, ["p", "q r"]);
+        assert!(std::ptr::eq(names.names[1], stored[1].as_str().unwrap()));
+        assert_eq!(names.by_id["a"], "x y");
+    }
+    #[test]
+    fn fail_deserialize_from_value() {
+        let value: Value = "(Asc:!n,Desc:!n)".parse().unwrap();
+        assert!(Sort::deserialize(&value).is_err());
+
+        let value: Value = "!(1,2,3)".parse().unwrap();
+        assert!(<(u8, u8)>::deserialize(value).is_err());
+    }
+}
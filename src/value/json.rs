@@ -0,0 +1,94 @@
+//! Conversions between Rison and JSON values
+
+use super::Value;
+use crate::number::Number;
+
+impl From<serde_json::Value> for Value {
+    /// Numbers beyond the range of an `f64` (which JSON may hold with
+    /// `serde_json`'s `arbitrary_precision` feature) are converted to `null`
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => Number::try_from(n).map_or(Value::Null, Value::Number),
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(a) => Value::Array(a.into_iter().map(Value::from).collect()),
+            serde_json::Value::Object(o) => {
+                Value::Object(o.into_iter().map(|(k, v)| (k, Value::from(v))).collect())
+            }
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    /// Integral numbers are converted to JSON integers, and non-finite numbers
+    /// (which JSON cannot represent) to `null`
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Number(n) => serde_json::Number::try_from(n)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            Value::String(s) => serde_json::Value::String(s),
+            Value::Array(a) => {
+                serde_json::Value::Array(a.into_iter().map(serde_json::Value::from).collect())
+            }
+            Value::Object(o) => serde_json::Value::Object(
+                o.into_iter()
+                    .map(|(k, v)| (k, serde_json::Value::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<serde_json::Number> for Number {
+    type Error = serde_json::Number;
+
+    /// Numbers beyond the range of an `f64`, which `serde_json` parses when
+    /// its `arbitrary_precision` feature is enabled, are returned as the
+    /// error, unless this crate's `arbitrary_precision` feature keeps them
+    /// exact.
+    fn try_from(n: serde_json::Number) -> Result<Self, serde_json::Number> {
+        #[cfg(feature = "arbitrary_precision")]
+        return Ok(Number::from_string_unchecked(n.to_string()));
+        #[cfg(not(feature = "arbitrary_precision"))]
+        if let Some(u) = n.as_u64() {
+            Ok(Number::from(u))
+        } else if let Some(i) = n.as_i64() {
+            Ok(Number::from(i))
+        } else {
+            n.as_f64().and_then(Number::from_f64).ok_or(n)
+        }
+    }
+}
+
+impl TryFrom<Number> for serde_json::Number {
+    type Error = Number;
+
+    /// Integers, and integral floats within the range in which `f64` is
+    /// exact, are converted to JSON integers. Numbers beyond the range of
+    /// JSON are returned as the error.
+    fn try_from(n: Number) -> Result<Self, Number> {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Ok(json) = n.as_str().parse() {
+            return Ok(json);
+        }
+
+        if let Some(u) = n.as_u64() {
+            return Ok(serde_json::Number::from(u));
+        }
+        if let Some(i) = n.as_i64() {
+            return Ok(serde_json::Number::from(i));
+        }
+        let Some(f) = n.as_f64() else {
+            return Err(n);
+        };
+        const MAX_SAFE_INT: f64 = (1u64 << f64::MANTISSA_DIGITS) as f64;
+        if f.trunc() == f && f.abs() <= MAX_SAFE_INT {
+            Ok(serde_json::Number::from(f as i64))
+        } else {
+            serde_json::Number::from_f64(f).ok_or(n)
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! Tolerant parsing of damaged Rison, for diagnostics
+
+use super::{Map, Value};
+use crate::de::{DEFAULT_RECURSION_LIMIT, NOT_ID_CHARS};
+use crate::error::{Error, ErrorCode};
+
+/// Parse Rison text as a [`Value`], recovering from errors where possible
+/// and reporting every problem found, rather than stopping at the first
+///
+/// This is intended for tools which inspect or repair hand-edited or
+/// truncated URLs, not for parsing trusted input, for which
+/// [`crate::from_str`] should be used. Recovery is best-effort:
+///
+/// - Lists and objects left open at the end of the input are closed
+/// - Trailing commas in lists and objects are skipped
+/// - Object keys without a `:` are given the value `!n`
+/// - Invalid values, such as unknown `!` markers or malformed numbers,
+///   become `!n`
+/// - Unterminated strings run to the end of the input, and invalid escapes
+///   are kept verbatim
+/// - Anything following the first complete value is skipped
+///
+/// Errors carry positions, lines, and columns, in the order the problems
+/// appear in the input. If no errors are returned, the value is as
+/// [`crate::from_str`] would have parsed it.
+///
+/// ```
+/// use rison::error::ErrorCode;
+///
+/// let (value, errors) = rison::value::parse_lossy("(a:!(1,2,),b:!x,c:'open");
+///
+/// assert_eq!(value, "(a:!(1,2),b:!n,c:open)".parse().unwrap());
+/// assert!(matches!(errors[0].code(), ErrorCode::TrailingComma));
+/// assert!(matches!(errors[1].code(), ErrorCode::InvalidMarker));
+/// assert!(matches!(errors[2].code(), ErrorCode::EofString));
+/// assert!(matches!(errors[3].code(), ErrorCode::UnclosedObject(0)));
+/// ```
+pub fn parse_lossy(input: &str) -> (Value, Vec<Error>) {
+    let mut parser = LossyParser {
+        input: input.as_bytes(),
+        index: 0,
+        remaining_depth: DEFAULT_RECURSION_LIMIT,
+        errors: Vec::new(),
+    };
+    let value = parser.parse_value();
+    if parser.index < parser.input.len() {
+        parser.error(ErrorCode::TrailingChars, parser.index);
+    }
+
+    let errors = parser
+        .errors
+        .into_iter()
+        .map(|err| err.locate(input.as_bytes()))
+        .collect();
+    (value, errors)
+}
+
+struct LossyParser<'a> {
+    input: &'a [u8],
+    index: usize,
+    remaining_depth: usize,
+    errors: Vec<Error>,
+}
+
+impl LossyParser<'_> {
+    fn error(&mut self, code: ErrorCode, position: usize) {
+        self.errors.push(Error::new(code, Some(position)));
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.index).copied()
+    }
+
+    fn parse_value(&mut self) -> Value {
+        match self.peek() {
+            None => {
+                self.error(ErrorCode::EofValue, self.index);
+                Value::Null
+            }
+            Some(b'!') => {
+                self.index += 1;
+                let value = match self.peek() {
+                    Some(b'n') => Value::Null,
+                    Some(b't') => Value::Bool(true),
+                    Some(b'f') => Value::Bool(false),
+                    Some(b'(') => {
+                        self.index += 1;
+                        return self.parse_list();
+                    }
+                    Some(_) => {
+                        self.error(ErrorCode::InvalidMarker, self.index);
+                        Value::Null
+                    }
+                    None => {
+                        self.error(ErrorCode::EofMarker, self.index);
+                        return Value::Null;
+                    }
+                };
+                self.index += 1;
+                value
+            }
+            Some(b'(') => {
+                self.index += 1;
+                self.parse_object()
+            }
+            Some(b'\'') => {
+                self.index += 1;
+                Value::String(self.parse_str())
+            }
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Value::String(self.parse_ident()),
+        }
+    }
+
+    /// Parse the elements of a list following its opening `!(`
+    fn parse_list(&mut self) -> Value {
+        let start = self.index - 2;
+        let mut list = Vec::new();
+        if !self.enter() {
+            return Value::Array(list);
+        }
+        if self.peek() == Some(b')') {
+            self.index += 1;
+        } else {
+            loop {
+                list.push(self.parse_value());
+                if !self.parse_separator(
+                    ErrorCode::UnclosedList(start),
+                    ErrorCode::ExpectedListSepOrEnd,
+                ) {
+                    break;
+                }
+            }
+        }
+        self.remaining_depth += 1;
+        Value::Array(list)
+    }
+
+    /// Parse the entries of an object following its opening `(`
+    fn parse_object(&mut self) -> Value {
+        let start = self.index - 1;
+        let mut object = Map::new();
+        if !self.enter() {
+            return Value::Object(object);
+        }
+        if self.peek() == Some(b')') {
+            self.index += 1;
+        } else {
+            loop {
+                let key = match self.peek() {
+                    Some(b'\'') => {
+                        self.index += 1;
+                        self.parse_str()
+                    }
+                    _ => self.parse_ident(),
+                };
+                let value = if self.peek() == Some(b':') {
+                    self.index += 1;
+                    self.parse_value()
+                } else {
+                    self.error(ErrorCode::ExpectedColon, self.index);
+                    Value::Null
+                };
+                object.insert(key, value);
+                if !self.parse_separator(
+                    ErrorCode::UnclosedObject(start),
+                    ErrorCode::ExpectedObjectSepOrEnd,
+                ) {
+                    break;
+                }
+            }
+        }
+        self.remaining_depth += 1;
+        Value::Object(object)
+    }
+
+    /// Descend into a list or object, or skip the rest of the input if
+    /// nested too deeply
+    fn enter(&mut self) -> bool {
+        if self.remaining_depth == 0 {
+            self.error(ErrorCode::RecursionLimitExceeded, self.index);
+            self.index = self.input.len();
+            return false;
+        }
+        self.remaining_depth -= 1;
+        true
+    }
+
+    /// Consume the separator following an element of a list or object,
+    /// returning whether another element follows
+    ///
+    /// Unexpected characters are reported and skipped one at a time, so
+    /// parsing always makes progress.
+    fn parse_separator(&mut self, eof: ErrorCode, unexpected: ErrorCode) -> bool {
+        match self.peek() {
+            Some(b',') => {
+                let comma = self.index;
+                self.index += 1;
+                if self.peek() == Some(b')') {
+                    self.error(ErrorCode::TrailingComma, comma);
+                    self.index += 1;
+                    return false;
+                }
+                true
+            }
+            Some(b')') => {
+                self.index += 1;
+                false
+            }
+            Some(_) => {
+                self.error(unexpected, self.index);
+                self.index += 1;
+                true
+            }
+            None => {
+                self.error(eof, self.index);
+                false
+            }
+        }
+    }
+
+    /// Parse a quoted string following its opening `'`
+    fn parse_str(&mut self) -> String {
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'\'') => {
+                    self.index += 1;
+                    break;
+                }
+                Some(b'!') => {
+                    self.index += 1;
+                    match self.peek() {
+                        Some(c @ (b'!' | b'\'')) => {
+                            bytes.push(c);
+                            self.index += 1;
+                        }
+                        Some(_) => {
+                            self.error(ErrorCode::InvalidEscape, self.index);
+                            bytes.push(b'!');
+                        }
+                        None => {
+                            self.error(ErrorCode::EofString, self.index);
+                            break;
+                        }
+                    }
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.index += 1;
+                }
+                None => {
+                    self.error(ErrorCode::EofString, self.index);
+                    break;
+                }
+            }
+        }
+
+        // Only whole ASCII characters are removed from the input, which is
+        // valid utf-8, so the contents are valid utf-8
+        String::from_utf8(bytes).expect("string contents are valid utf-8")
+    }
+
+    fn parse_ident(&mut self) -> String {
+        let start = self.index;
+        while let Some(c) = self.peek() {
+            if NOT_ID_CHARS.contains(&c) {
+                break;
+            }
+            self.index += 1;
+        }
+        String::from_utf8_lossy(&self.input[start..self.index]).into_owned()
+    }
+
+    fn parse_number(&mut self) -> Value {
+        let start = self.index;
+        while let Some(b'-' | b'0'..=b'9' | b'.' | b'e') = self.peek() {
+            self.index += 1;
+        }
+        match crate::from_slice(&self.input[start..self.index]) {
+            Ok(number) => number,
+            Err(_) => {
+                self.error(ErrorCode::InvalidNumber, start);
+                Value::Null
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_lossy;
+    use crate::error::ErrorCode;
+    use crate::value::Value;
+
+    /// The recovered value, compared as a value as objects may be written
+    /// in any order, and the errors with their positions
+    fn codes_with(input: &str) -> (Value, Vec<(String, usize)>) {
+        let (value, errors) = parse_lossy(input);
+        let errors = errors
+            .iter()
+            .map(|e| (e.code().to_string(), e.position().unwrap()))
+            .collect();
+        (value, errors)
+    }
+
+    #[test]
+    fn parse_valid_input_without_errors() {
+        for input in [
+            "(a:0,b:foo,c:'23skidoo')",
+            "!(!t,!f,!n,'',-1.5e3)",
+            "(_g:(refreshInterval:(pause:!t,value:0),time:(from:now-15m,to:now)))",
+            "'wow!!!'s'",
+        ] {
+            let (value, errors) = parse_lossy(input);
+            assert!(errors.is_empty(), "{}: {:?}", input, errors);
+            assert_eq!(value, crate::from_str::<Value>(input).unwrap());
+        }
+    }
+    #[test]
+    fn recover_from_errors() {
+        assert_eq!(
+            codes_with("(a:!(1,2"),
+            (
+                "(a:!(1,2))".parse().unwrap(),
+                vec![
+                    ("unclosed list started at position 3".into(), 8),
+                    ("unclosed object started at position 0".into(), 8)
+                ]
+            )
+        );
+        assert_eq!(
+            codes_with("(a:1,b,c:1.2.3,)x"),
+            (
+                "(a:1,b:!n,c:!n)".parse().unwrap(),
+                vec![
+                    ("expected `:`".into(), 6),
+                    ("invalid number".into(), 9),
+                    ("trailing comma".into(), 14),
+                    ("trailing characters".into(), 16)
+                ]
+            )
+        );
+        assert_eq!(
+            codes_with("!(a b,'x!y')"),
+            (
+                "!(a,b,'x!!y')".parse().unwrap(),
+                vec![
+                    ("expected `,` or `)`".into(), 3),
+                    ("invalid escape".into(), 9)
+                ]
+            )
+        );
+        let (value, errors) = parse_lossy(&"!(".repeat(200));
+        assert!(matches!(value, Value::Array(_)));
+        assert!(matches!(
+            errors[0].code(),
+            ErrorCode::RecursionLimitExceeded
+        ));
+    }
+}
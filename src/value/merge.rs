@@ -0,0 +1,142 @@
+//! Merging and diffing of values, following JSON Merge Patch
+
+use super::{Map, Value};
+
+impl Value {
+    /// Apply `patch` to this value, as JSON Merge Patch ([RFC 7386]) would
+    ///
+    /// Objects are merged recursively: each entry of the patch replaces the
+    /// entry of the same key, or is merged into it if both are objects, and
+    /// entries of the patch which are `!n` remove the entry instead. A patch
+    /// which is not an object replaces this value entirely, so lists are
+    /// replaced rather than merged.
+    ///
+    /// ```
+    /// let mut state: rison::Value = "(query:x,time:(from:now-15m,to:now))".parse().unwrap();
+    /// let user: rison::Value = "(query:!n,time:(from:now-1h))".parse().unwrap();
+    /// state.merge(user);
+    ///
+    /// assert_eq!(state, "(time:(from:now-1h,to:now))".parse().unwrap());
+    /// ```
+    ///
+    /// [RFC 7386]: https://www.rfc-editor.org/rfc/rfc7386
+    pub fn merge(&mut self, patch: Value) {
+        let Value::Object(patch) = patch else {
+            *self = patch;
+            return;
+        };
+        if !self.is_object() {
+            *self = Value::Object(Map::new());
+        }
+        let Value::Object(target) = self else {
+            unreachable!("replaced by an object");
+        };
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(&key);
+            } else {
+                target.entry(key).or_insert(Value::Null).merge(value);
+            }
+        }
+    }
+
+    /// The smallest patch which [`merge`](Value::merge)s `from` into `to`
+    ///
+    /// Objects are compared recursively, so the patch of two objects holds
+    /// only the entries which were added or changed, and `!n` for those
+    /// which were removed. It is an empty object if they are equal. Any
+    /// other values are replaced whole, so the patch is `to` itself.
+    ///
+    /// Merge patches cannot set an entry to `!n`, which they read as
+    /// removing it, so entries of `to` which are `!n` are removed by the
+    /// patch rather than preserved.
+    ///
+    /// ```
+    /// let defaults: rison::Value = "(columns:!(host),query:'',sort:desc)".parse().unwrap();
+    /// let state: rison::Value = "(columns:!(host,message),sort:desc)".parse().unwrap();
+    /// let patch = rison::Value::diff(&defaults, &state);
+    ///
+    /// assert_eq!(patch, "(columns:!(host,message),query:!n)".parse().unwrap());
+    ///
+    /// let mut restored = defaults.clone();
+    /// restored.merge(patch);
+    /// assert_eq!(restored, state);
+    /// ```
+    pub fn diff(from: &Value, to: &Value) -> Value {
+        let (Value::Object(from), Value::Object(to)) = (from, to) else {
+            return to.clone();
+        };
+        let mut patch = Map::new();
+        for key in from.keys() {
+            if !to.contains_key(key) {
+                patch.insert(key.clone(), Value::Null);
+            }
+        }
+        for (key, value) in to {
+            match from.get(key) {
+                Some(old) if old == value => {}
+                Some(old @ Value::Object(_)) => {
+                    patch.insert(key.clone(), Value::diff(old, value));
+                }
+                _ => {
+                    patch.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        Value::Object(patch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::value::Value;
+
+    fn value(rison: &str) -> Value {
+        rison.parse().unwrap()
+    }
+
+    #[test]
+    fn merge_rfc_7386_examples() {
+        // The examples of RFC 7386, appendix A, in Rison
+        for (target, patch, expected) in [
+            ("(a:b)", "(a:c)", "(a:c)"),
+            ("(a:b)", "(b:c)", "(a:b,b:c)"),
+            ("(a:b)", "(a:!n)", "()"),
+            ("(a:b,b:c)", "(a:!n)", "(b:c)"),
+            ("(a:!(b))", "(a:c)", "(a:c)"),
+            ("(a:c)", "(a:!(b))", "(a:!(b))"),
+            ("(a:(b:c))", "(a:(b:d,c:!n))", "(a:(b:d))"),
+            ("(a:!((b:c)))", "(a:!(1))", "(a:!(1))"),
+            ("!(a,b)", "!(c,d)", "!(c,d)"),
+            ("(a:b)", "!(c)", "!(c)"),
+            ("(a:foo)", "!n", "!n"),
+            ("(a:foo)", "bar", "bar"),
+            ("(e:!n)", "(a:1)", "(a:1,e:!n)"),
+            ("!(1,2)", "(a:b,c:!n)", "(a:b)"),
+            ("()", "(a:(bb:(ccc:!n)))", "(a:(bb:()))"),
+        ] {
+            let mut merged = value(target);
+            merged.merge(value(patch));
+            assert_eq!(merged, value(expected), "{} + {}", target, patch);
+        }
+    }
+
+    #[test]
+    fn diff_and_merge_back() {
+        for (from, to, expected) in [
+            ("(a:1,b:2)", "(a:1,b:2)", "()"),
+            ("(a:1,b:2)", "(a:1,c:3)", "(b:!n,c:3)"),
+            ("(a:(x:1,y:!(1)))", "(a:(x:1,y:!(1,2)))", "(a:(y:!(1,2)))"),
+            ("(a:(x:1))", "(a:!(x))", "(a:!(x))"),
+            ("(a:!(x))", "(a:(x:1))", "(a:(x:1))"),
+            ("!(1)", "(a:1)", "(a:1)"),
+            ("x", "y", "y"),
+        ] {
+            let patch = Value::diff(&value(from), &value(to));
+            assert_eq!(patch, value(expected), "{} -> {}", from, to);
+            let mut merged = value(from);
+            merged.merge(patch);
+            assert_eq!(merged, value(to), "{} -> {}", from, to);
+        }
+    }
+}
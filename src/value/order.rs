@@ -0,0 +1,154 @@
+//! Serializing values with object entries in a caller-defined order
+
+use std::cmp::Ordering;
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::Value;
+
+/// Serializes a [`Value`] with the entries of each of its objects sorted by
+/// a comparator of their keys
+///
+/// Created by [`Value::sorted_by`].
+pub struct SortedBy<'a, F> {
+    value: &'a Value,
+    compare: F,
+}
+
+impl Value {
+    /// Serialize this value with the entries of every object, at any depth,
+    /// ordered by `compare` applied to their keys
+    ///
+    /// Some consumers of Rison URLs, such as URL pattern matchers, are
+    /// sensitive to the order of object entries, while the order a `Value`
+    /// keeps depends on how it was built and on the `preserve_order`
+    /// feature. The sort is stable, so entries whose keys compare equal keep
+    /// their order relative to each other. The value itself is unchanged.
+    ///
+    /// ```
+    /// let state: rison::Value = "(columns:!(host),query:x,time:(from:a,to:b))".parse().unwrap();
+    ///
+    /// // `query` first, `time` last, and everything else in between by name
+    /// let rank = |key: &str| match key {
+    ///     "query" => 0,
+    ///     "time" => 2,
+    ///     _ => 1,
+    /// };
+    /// let sorted = state.sorted_by(|a, b| rank(a).cmp(&rank(b)).then(a.cmp(b)));
+    ///
+    /// assert_eq!(
+    ///     rison::to_string(&sorted).unwrap(),
+    ///     "(query:x,columns:!(host),time:(from:a,to:b))",
+    /// );
+    /// ```
+    pub fn sorted_by<F>(&self, compare: F) -> SortedBy<'_, F>
+    where
+        F: Fn(&str, &str) -> Ordering,
+    {
+        SortedBy {
+            value: self,
+            compare,
+        }
+    }
+}
+
+impl<F> Serialize for SortedBy<'_, F>
+where
+    F: Fn(&str, &str) -> Ordering,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Sorted {
+            value: self.value,
+            compare: &self.compare,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A node within the value being serialized, sharing the comparator of the
+/// whole
+struct Sorted<'a, F> {
+    value: &'a Value,
+    compare: &'a F,
+}
+
+impl<F> Serialize for Sorted<'_, F>
+where
+    F: Fn(&str, &str) -> Ordering,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let nested = |value| Sorted {
+            value,
+            compare: self.compare,
+        };
+        match self.value {
+            Value::Array(a) => {
+                let mut seq = serializer.serialize_seq(Some(a.len()))?;
+                for element in a {
+                    seq.serialize_element(&nested(element))?;
+                }
+                seq.end()
+            }
+            Value::Object(o) => {
+                let mut entries: Vec<_> = o.iter().collect();
+                entries.sort_by(|(a, _), (b, _)| (self.compare)(a, b));
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, &nested(value))?;
+                }
+                map.end()
+            }
+            scalar => scalar.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Value;
+
+    #[test]
+    fn serialize_sorted_by() {
+        let value: Value = "!((b:1,a:(d:!(),c:(f:1,e:2))),(z:1))".parse().unwrap();
+
+        let reversed = value.sorted_by(|a, b| b.cmp(a));
+        assert_eq!(
+            crate::to_string(&reversed).unwrap(),
+            "!((b:1,a:(d:!(),c:(f:1,e:2))),(z:1))"
+        );
+        let forwards = value.sorted_by(str::cmp);
+        assert_eq!(
+            crate::to_string(&forwards).unwrap(),
+            "!((a:(c:(e:2,f:1),d:!()),b:1),(z:1))"
+        );
+    }
+    #[test]
+    fn serialize_sorted_by_is_stable() {
+        let value: Value = "(a:1,ab:2,b:3,bc:4)".parse().unwrap();
+        let by_len = value.sorted_by(|a, b| b.len().cmp(&a.len()));
+
+        // Ties keep the order in which the map iterates, which is arbitrary
+        // when it is backed by a hash map
+        let Value::Object(map) = &value else {
+            unreachable!()
+        };
+        let (long, short): (Vec<_>, Vec<_>) = map.keys().partition(|key| key.len() == 2);
+        let entries: Vec<_> = long
+            .into_iter()
+            .chain(short)
+            .map(|key| format!("{key}:{}", map[key]))
+            .collect();
+        assert_eq!(
+            crate::to_string(&by_len).unwrap(),
+            format!("({})", entries.join(","))
+        );
+        #[cfg(not(all(feature = "unordered_map", not(feature = "preserve_order"))))]
+        assert_eq!(crate::to_string(&by_len).unwrap(), "(ab:2,bc:4,a:1,b:3)");
+    }
+}
@@ -0,0 +1,414 @@
+//! Unparsed Rison text, captured during deserialization or written verbatim
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, DeserializeSeed, MapAccess, Visitor};
+use serde::ser::Serialize;
+
+use crate::de::Reference;
+use crate::error::{Error, Result};
+#[cfg(not(feature = "unchecked_utf8"))]
+use zerocopy::{FromBytes, FromZeros, Immutable, IntoBytes, KnownLayout};
+
+type BorrowedStrDeserializer<'de> = de::value::BorrowedStrDeserializer<'de, Error>;
+type StrDeserializer<'a> = de::value::StrDeserializer<'a, Error>;
+
+/// The name of the newtype struct through which a [`RawValue`] is requested
+/// from, and written to, this crate's deserializer and serializer
+pub(crate) const TOKEN: &str = "$rison::private::RawValue";
+
+/// A range of Rison text which has not been parsed into a value
+///
+/// When deserialized as a `&RawValue`, the exact input text of the value is
+/// borrowed. This requires deserializing from a slice or string, rather than
+/// an IO stream. A `Box<RawValue>` may be deserialized from any source.
+///
+/// When serialized, the text is written verbatim.
+///
+/// ```
+/// use rison::value::RawValue;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Envelope<'a> {
+///     kind: String,
+///     #[serde(borrow)]
+///     payload: &'a RawValue,
+/// }
+///
+/// let envelope: Envelope = rison::from_str("(kind:query,payload:(a:!(1,2)))").unwrap();
+///
+/// assert_eq!(envelope.kind, "query");
+/// assert_eq!(envelope.payload.get(), "(a:!(1,2))");
+/// ```
+#[cfg_attr(feature = "unchecked_utf8", repr(transparent))]
+#[cfg_attr(
+    not(feature = "unchecked_utf8"),
+    derive(FromBytes, IntoBytes, KnownLayout, Immutable),
+    repr(C)
+)]
+pub struct RawValue {
+    #[cfg(feature = "unchecked_utf8")]
+    rison: str,
+    /// Without the `unchecked_utf8` feature, the text is held as bytes, so
+    /// that it can be wrapped without `unsafe` at the cost of a copy, and
+    /// checked to be utf-8 when read
+    #[cfg(not(feature = "unchecked_utf8"))]
+    rison: [u8],
+}
+
+#[cfg(feature = "unchecked_utf8")]
+impl RawValue {
+    fn from_borrowed(rison: &str) -> &Self {
+        // # Safety
+        // `RawValue` is a `repr(transparent)` wrapper around `str`, so the
+        // pointer cast preserves both layout and metadata.
+        unsafe { &*(rison as *const str as *const RawValue) }
+    }
+
+    fn from_owned(rison: Box<str>) -> Box<Self> {
+        // # Safety
+        // As in `from_borrowed`; the allocation is reinterpreted, not copied.
+        unsafe { Box::from_raw(Box::into_raw(rison) as *mut RawValue) }
+    }
+
+    fn into_owned(raw_value: Box<Self>) -> Box<str> {
+        // # Safety
+        // As in `from_borrowed`; the allocation is reinterpreted, not copied.
+        unsafe { Box::from_raw(Box::into_raw(raw_value) as *mut str) }
+    }
+
+    /// The Rison text of this value
+    pub fn get(&self) -> &str {
+        &self.rison
+    }
+}
+
+// Boxes are taken and returned as they are with `unchecked_utf8`, where
+// their allocations are reused
+#[cfg(not(feature = "unchecked_utf8"))]
+#[allow(clippy::boxed_local)]
+impl RawValue {
+    fn from_borrowed(rison: &str) -> &Self {
+        Self::ref_from_bytes(rison.as_bytes()).expect("`RawValue` has the layout of `[u8]`")
+    }
+
+    fn from_owned(rison: Box<str>) -> Box<Self> {
+        let mut raw_value = Self::new_box_zeroed_with_elems(rison.len()).unwrap_or_else(|_| {
+            std::alloc::handle_alloc_error(std::alloc::Layout::for_value(&*rison))
+        });
+        raw_value.rison.copy_from_slice(rison.as_bytes());
+        raw_value
+    }
+
+    fn into_owned(raw_value: Box<Self>) -> Box<str> {
+        raw_value.get().into()
+    }
+
+    /// The Rison text of this value
+    pub fn get(&self) -> &str {
+        std::str::from_utf8(&self.rison).expect("`RawValue` holds valid utf-8")
+    }
+}
+
+impl RawValue {
+    /// Validate that `rison` is a single complete Rison value, and wrap it
+    pub fn from_string(rison: String) -> Result<Box<Self>> {
+        crate::from_str::<de::IgnoredAny>(&rison)?;
+        Ok(Self::from_owned(rison.into_boxed_str()))
+    }
+}
+
+/// Serialize `value` and wrap the resulting Rison text as a [`RawValue`]
+pub fn to_raw_value<T>(value: &T) -> Result<Box<RawValue>>
+where
+    T: ?Sized + Serialize,
+{
+    let rison = crate::to_string(value)?;
+    Ok(RawValue::from_owned(rison.into_boxed_str()))
+}
+
+/// Rison text to be spliced verbatim into serialized output, such as a
+/// fragment encoded earlier and cached
+///
+/// Unlike a [`RawValue`], the text is borrowed rather than validated up
+/// front; it is checked to be a single complete Rison value when serialized,
+/// failing serialization otherwise. [`serialize_raw`] serializes a field of
+/// a type holding Rison text in the same way.
+///
+/// ```
+/// use rison::value::RawRison;
+///
+/// let cached = "(columns:!(a,b),sort:a)";
+/// let state = ("v2", RawRison(cached));
+///
+/// assert_eq!(rison::to_string(&state).unwrap(), "!(v2,(columns:!(a,b),sort:a))");
+/// assert!(rison::to_string(&RawRison("(a:")).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RawRison<'a>(pub &'a str);
+
+impl Serialize for RawRison<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        crate::from_str::<de::IgnoredAny>(self.0).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_newtype_struct(TOKEN, self.0)
+    }
+}
+
+/// Serialize Rison text verbatim, as [`RawRison`] does, for use with
+/// `#[serde(serialize_with = "rison::value::serialize_raw")]`
+///
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Dashboard {
+///     title: String,
+///     #[serde(serialize_with = "rison::value::serialize_raw")]
+///     filters: String,
+/// }
+///
+/// let dashboard = Dashboard {
+///     title: "Sales".into(),
+///     filters: "!((region:eu))".into(),
+/// };
+///
+/// assert_eq!(
+///     rison::to_string(&dashboard).unwrap(),
+///     "(title:Sales,filters:!((region:eu)))"
+/// );
+/// ```
+pub fn serialize_raw<T, S>(rison: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    T: ?Sized + AsRef<str>,
+    S: serde::Serializer,
+{
+    RawRison(rison.as_ref()).serialize(serializer)
+}
+
+impl Clone for Box<RawValue> {
+    fn clone(&self) -> Self {
+        (**self).to_owned()
+    }
+}
+
+impl ToOwned for RawValue {
+    type Owned = Box<RawValue>;
+
+    fn to_owned(&self) -> Self::Owned {
+        RawValue::from_owned(self.get().into())
+    }
+}
+
+impl From<Box<RawValue>> for Box<str> {
+    fn from(raw_value: Box<RawValue>) -> Self {
+        RawValue::into_owned(raw_value)
+    }
+}
+
+impl fmt::Debug for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawValue").field(&self.get()).finish()
+    }
+}
+
+impl fmt::Display for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get())
+    }
+}
+
+impl Serialize for RawValue {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(TOKEN, self.get())
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for &'a RawValue {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ReferenceVisitor;
+
+        impl<'de> Visitor<'de> for ReferenceVisitor {
+            type Value = &'de RawValue;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a borrowed Rison value")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                if map.next_key::<RawKey>()?.is_none() {
+                    return Err(de::Error::invalid_type(de::Unexpected::Map, &self));
+                }
+                map.next_value::<&str>().map(RawValue::from_borrowed)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(TOKEN, ReferenceVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<RawValue> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct BoxedVisitor;
+
+        impl<'de> Visitor<'de> for BoxedVisitor {
+            type Value = Box<RawValue>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("any valid Rison value")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                if map.next_key::<RawKey>()?.is_none() {
+                    return Err(de::Error::invalid_type(de::Unexpected::Map, &self));
+                }
+                map.next_value::<String>()
+                    .map(|rison| RawValue::from_owned(rison.into_boxed_str()))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(TOKEN, BoxedVisitor)
+    }
+}
+
+/// The key of the single-entry map through which the deserializer provides
+/// raw values, distinguishing them from ordinary maps
+struct RawKey;
+
+impl<'de> Deserialize<'de> for RawKey {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct FieldVisitor;
+
+        impl Visitor<'_> for FieldVisitor {
+            type Value = ();
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("raw value")
+            }
+
+            fn visit_str<E>(self, s: &str) -> std::result::Result<(), E>
+            where
+                E: de::Error,
+            {
+                if s == TOKEN {
+                    Ok(())
+                } else {
+                    Err(de::Error::custom("unexpected raw value"))
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)?;
+        Ok(RawKey)
+    }
+}
+
+/// Provides a captured raw value to a [`RawValue`] visitor
+pub(crate) struct RawDeserializer<'de, 's> {
+    raw_value: Option<Reference<'de, 's, str>>,
+}
+
+impl<'de, 's> RawDeserializer<'de, 's> {
+    pub(crate) fn new(raw_value: Reference<'de, 's, str>) -> Self {
+        Self {
+            raw_value: Some(raw_value),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for RawDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.raw_value.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize(BorrowedStrDeserializer::new(TOKEN))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.raw_value.take() {
+            Some(Reference::Borrowed(b)) => seed.deserialize(BorrowedStrDeserializer::new(b)),
+            Some(Reference::Copied(c)) => seed.deserialize(StrDeserializer::new(c)),
+            None => Err(de::Error::custom("raw value already taken")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RawRison, RawValue};
+
+    #[derive(serde::Deserialize, serde::Serialize)]
+    struct Envelope<R> {
+        kind: String,
+        payload: R,
+    }
+
+    #[test]
+    fn deserialize_borrowed_raw_value() {
+        let input = "(kind:query,payload:(b:'it!'s',a:!(1,2)))";
+        let v: Envelope<&RawValue> = crate::from_str(input).unwrap();
+
+        assert_eq!(v.payload.get(), "(b:'it!'s',a:!(1,2))");
+    }
+    #[test]
+    fn deserialize_boxed_raw_value_from_io() {
+        let input = b"(kind:query,payload:!(x,'y z'))";
+        let v: Envelope<Box<RawValue>> = crate::from_reader(&input[..]).unwrap();
+
+        assert_eq!(v.payload.get(), "!(x,'y z')");
+    }
+    #[test]
+    fn serialize_raw_value_verbatim() {
+        let input = "(kind:query,payload:(b:1,a:2))";
+        let v: Envelope<Box<RawValue>> = crate::from_str(input).unwrap();
+
+        assert_eq!(crate::to_string(&v).unwrap(), input);
+    }
+    #[test]
+    fn serialize_raw_rison_verbatim() {
+        let v = Envelope {
+            kind: "query".into(),
+            payload: RawRison("(b:'it!'s',a:!(1,2))"),
+        };
+        assert_eq!(
+            crate::to_string(&v).unwrap(),
+            "(kind:query,payload:(b:'it!'s',a:!(1,2)))"
+        );
+
+        for invalid in ["", "(a:", "1,2", "'it's'"] {
+            assert!(crate::to_string(&RawRison(invalid)).is_err(), "{invalid}");
+        }
+    }
+    #[test]
+    fn fail_raw_value_from_invalid_string() {
+        assert!(RawValue::from_string("(a:".into()).is_err());
+        assert!(RawValue::from_string("(a:1)".into()).is_ok());
+    }
+}
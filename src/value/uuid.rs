@@ -0,0 +1,54 @@
+//! Conversions from UUIDs to Rison values
+//!
+//! UUIDs serialize in their hyphenated form, which needs no quotes unless it
+//! starts with a digit (as it would otherwise begin a number):
+//! `e4d0a1c6-8a8e-4e6b-9b8f-3c2a1d0e5f7a`, but
+//! `'67e55044-10b1-426f-9247-bb680e5fe0c8'`. They deserialize from either,
+//! borrowing from the input where possible rather than allocating.
+
+use uuid::Uuid;
+
+use super::Value;
+
+impl From<Uuid> for Value {
+    fn from(uuid: Uuid) -> Self {
+        Value::String(uuid.hyphenated().to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use super::Value;
+
+    const LETTER: Uuid = Uuid::from_u128(0xe4d0a1c6_8a8e_4e6b_9b8f_3c2a1d0e5f7a);
+    const DIGIT: Uuid = Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+
+    #[test]
+    fn serialize_uuids() {
+        assert_eq!(
+            crate::to_string(&[LETTER, DIGIT]).unwrap(),
+            "!(e4d0a1c6-8a8e-4e6b-9b8f-3c2a1d0e5f7a,'67e55044-10b1-426f-9247-bb680e5fe0c8')"
+        );
+        assert_eq!(
+            crate::to_string(&Value::from(LETTER)).unwrap(),
+            crate::to_string(&LETTER).unwrap()
+        );
+    }
+    #[test]
+    fn deserialize_uuids() {
+        let rison =
+            "!(e4d0a1c6-8a8e-4e6b-9b8f-3c2a1d0e5f7a,'67e55044-10b1-426f-9247-bb680e5fe0c8')";
+
+        assert_eq!(
+            crate::from_str::<[Uuid; 2]>(rison).unwrap(),
+            [LETTER, DIGIT]
+        );
+        assert_eq!(
+            crate::from_reader::<[Uuid; 2], _>(rison.as_bytes()).unwrap(),
+            [LETTER, DIGIT]
+        );
+        assert!(crate::from_str::<Uuid>("e4d0a1c6-8a8e").is_err());
+    }
+}
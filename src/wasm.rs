@@ -0,0 +1,40 @@
+//! Functions for encoding and decoding JavaScript values from WebAssembly
+//!
+//! These are exported through `wasm-bindgen` with the names used by rison.js,
+//! so a frontend can use the same encoder and decoder as its backend:
+//!
+//! ```js
+//! import { encode, decode } from "rison";
+//!
+//! encode({ q: "rust", page: 2 }); // "(page:2,q:rust)"
+//! decode("!(1,'x y',!t)"); // [1, "x y", true]
+//! ```
+//!
+//! Values pass through [`Value`], so objects are encoded with their keys in
+//! sorted order, rather than in insertion order as rison.js does, unless the
+//! `preserve_order` feature is enabled.
+
+use serde::ser::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::value::Value;
+
+/// Decode Rison text into a JavaScript value
+///
+/// Objects become plain JavaScript objects, rather than `Map`s.
+#[wasm_bindgen(js_name = decode)]
+pub fn parse_to_js(rison: &str) -> Result<JsValue, JsError> {
+    let value: Value = crate::from_str(rison)?;
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(value.serialize(&serializer)?)
+}
+
+/// Encode a JavaScript value as Rison text
+///
+/// As with `JSON.stringify`, `undefined`, `NaN` and infinities are encoded
+/// as `!n`.
+#[wasm_bindgen(js_name = encode)]
+pub fn stringify_from_js(value: JsValue) -> Result<String, JsError> {
+    let value: Value = serde_wasm_bindgen::from_value(value)?;
+    Ok(crate::to_string(&value)?)
+}
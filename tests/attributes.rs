@@ -0,0 +1,200 @@
+//! Typed structs using common serde attributes, against the Rison which
+//! Kibana writes into its URLs
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalState {
+    #[serde(default)]
+    filters: Vec<Filter>,
+    refresh_interval: RefreshInterval,
+    time: TimeRange,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RefreshInterval {
+    pause: bool,
+    value: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct TimeRange {
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Filter {
+    #[serde(rename = "$state", default, skip_serializing_if = "Option::is_none")]
+    state: Option<FilterState>,
+    meta: FilterMeta,
+    query: serde_json::Value,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FilterState {
+    store: Store,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Store {
+    AppState,
+    GlobalState,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FilterMeta {
+    #[serde(default)]
+    alias: Option<String>,
+    disabled: bool,
+    #[serde(alias = "indexPattern")]
+    index: String,
+    #[serde(rename = "key")]
+    field: String,
+    negate: bool,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppState {
+    #[serde(default)]
+    columns: Vec<String>,
+    #[serde(default = "default_interval")]
+    interval: String,
+    query: Query,
+    #[serde(default)]
+    sort: Vec<(String, SortDirection)>,
+}
+
+fn default_interval() -> String {
+    "auto".into()
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Query {
+    language: String,
+    query: String,
+}
+
+const GLOBAL: &str = "(filters:!(),refreshInterval:(pause:!t,value:0),time:(from:now-15m,to:now))";
+
+const GLOBAL_WITH_FILTER: &str = "(filters:!(('$state':(store:globalState),meta:(alias:!n,disabled:!f,index:'90943e30-9a47-11e8-b64d-95841ca0b247',key:geo.src,negate:!f,params:(query:US),type:phrase),query:(match_phrase:(geo.src:US)))),refreshInterval:(pause:!t,value:0),time:(from:'2024-01-01T00:00:00.000Z',to:now))";
+
+const APP: &str = "(columns:!(_source),interval:auto,query:(language:kuery,query:'response:200'),sort:!(!('@timestamp',desc)))";
+
+#[test]
+fn deserialize_global_state() {
+    let state: GlobalState = rison::from_str(GLOBAL).unwrap();
+
+    assert_eq!(
+        state,
+        GlobalState {
+            filters: vec![],
+            refresh_interval: RefreshInterval {
+                pause: true,
+                value: 0
+            },
+            time: TimeRange {
+                from: "now-15m".into(),
+                to: "now".into()
+            },
+        }
+    );
+    assert_eq!(rison::to_string(&state).unwrap(), GLOBAL);
+}
+#[test]
+fn deserialize_global_state_with_filter() {
+    let state: GlobalState = rison::from_str(GLOBAL_WITH_FILTER).unwrap();
+    let filter = &state.filters[0];
+
+    assert_eq!(
+        filter.state,
+        Some(FilterState {
+            store: Store::GlobalState
+        })
+    );
+    assert_eq!(filter.meta.field, "geo.src");
+    assert_eq!(filter.meta.kind, "phrase");
+    assert_eq!(filter.meta.index, "90943e30-9a47-11e8-b64d-95841ca0b247");
+    assert_eq!(
+        filter.query,
+        serde_json::json!({"match_phrase": {"geo.src": "US"}})
+    );
+}
+#[test]
+fn deserialize_app_state() {
+    let state: AppState = rison::from_str(APP).unwrap();
+
+    assert_eq!(
+        state,
+        AppState {
+            columns: vec!["_source".into()],
+            interval: "auto".into(),
+            query: Query {
+                language: "kuery".into(),
+                query: "response:200".into()
+            },
+            sort: vec![("@timestamp".into(), SortDirection::Desc)],
+        }
+    );
+    assert_eq!(rison::to_string(&state).unwrap(), APP);
+}
+#[test]
+fn deserialize_defaults() {
+    let state: GlobalState =
+        rison::from_str("(refreshInterval:(pause:!f,value:5000),time:(from:now-1h,to:now))")
+            .unwrap();
+    assert!(state.filters.is_empty());
+
+    let state: AppState = rison::from_str("(query:(language:lucene,query:''))").unwrap();
+    assert_eq!(state.interval, "auto");
+    assert!(state.columns.is_empty() && state.sort.is_empty());
+}
+#[test]
+fn deserialize_aliases() {
+    let meta: FilterMeta =
+        rison::from_str("(disabled:!f,indexPattern:logs,key:host,negate:!t,type:exists)").unwrap();
+
+    assert_eq!(meta.index, "logs");
+    assert_eq!(meta.alias, None);
+    assert!(meta.negate);
+}
+#[test]
+fn fail_deserialize_unknown_fields() {
+    let err = rison::from_str::<Query>("(language:kuery,query:'',mode:x)").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown field `mode`, expected `language` or `query` at line 1 column 26"
+    );
+
+    let err = rison::from_str::<GlobalState>(
+        "(refreshInterval:(pause:!t,value:0,interval:1),time:(from:a,to:b))",
+    )
+    .unwrap_err();
+    assert_eq!(err.position(), Some(35));
+}
+#[test]
+fn fail_deserialize_wrongly_cased_fields() {
+    let err =
+        rison::from_str::<GlobalState>("(refresh_interval:(pause:!t,value:0),time:(from:a,to:b))")
+            .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .starts_with("missing field `refreshInterval`"));
+}
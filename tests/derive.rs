@@ -0,0 +1,37 @@
+//! Types implementing `Display` and `FromStr` through `RisonDisplay`
+#![cfg(feature = "derive")]
+
+use rison::RisonDisplay;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, RisonDisplay)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, RisonDisplay)]
+#[serde(rename_all = "lowercase")]
+enum Tab {
+    Open,
+    Closed,
+}
+
+#[test]
+fn display_generic_struct() {
+    let page = Page {
+        items: vec![Tab::Open, Tab::Closed],
+        next: Some("it's".into()),
+    };
+
+    assert_eq!(page.to_string(), "(items:!(open,closed),next:'it!'s')");
+    assert_eq!(page.to_string().parse::<Page<Tab>>().unwrap(), page);
+}
+#[test]
+fn parse_enum() {
+    assert_eq!(format!("tab={}", Tab::Closed), "tab=closed");
+    assert_eq!("open".parse::<Tab>().unwrap(), Tab::Open);
+
+    let err = "pending".parse::<Tab>().unwrap_err();
+    assert!(err.is_data());
+}